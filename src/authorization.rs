@@ -1,32 +1,127 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::BTreeMap, fmt};
 
-use oauth2::{CsrfToken, PkceCodeChallenge};
+use oauth2::{
+    AuthorizationCode, CsrfToken, ErrorResponseType, PkceCodeChallenge, PkceCodeChallengeMethod,
+    ResponseType, StandardErrorResponse,
+};
 use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::{
-    profiles::AuthorizationDetailsObjectProfile,
-    types::{IssuerState, IssuerUrl, UserHint},
+    metadata::CredentialIssuerMetadata,
+    presentation::{PresentationDefinition, RESPONSE_TYPE_CODE_VP_TOKEN},
+    profiles::{AuthorizationDetailsObjectProfile, CredentialConfigurationProfile},
+    types::{
+        CredentialConfigurationId, IssuerState, IssuerUrl, LanguageTag, ResponseMode, UserHint,
+    },
 };
 
 pub struct AuthorizationRequest<'a> {
     inner: oauth2::AuthorizationRequest<'a>,
+    code_challenge_methods_supported: Option<Vec<PkceCodeChallengeMethod>>,
+    response_modes_supported: Option<Vec<ResponseMode>>,
+    /// Space-delimited parameters, added to `inner` when the request is built so that setting
+    /// them again replaces their value.
+    space_delimited_params: BTreeMap<&'static str, String>,
+}
+
+/// The code challenge method is not advertised by the authorization server, or is `plain` in a
+/// pushed authorization request that does not allow it.
+#[derive(Debug, thiserror::Error)]
+#[error("code challenge method `{}` is not supported", .0.as_str())]
+pub struct UnsupportedCodeChallengeMethod(pub PkceCodeChallengeMethod);
+
+#[derive(Debug, thiserror::Error)]
+#[error("response mode `{}` is not supported by the authorization server", .0.as_str())]
+pub struct UnsupportedResponseMode(pub ResponseMode);
+
+#[derive(Debug, thiserror::Error)]
+#[error("location `{}` is neither the credential issuer nor one of its authorization servers", .0.as_str())]
+pub struct UnknownLocation(pub IssuerUrl);
+
+/// Value of the OpenID Connect `prompt` authorization parameter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Prompt {
+    None,
+    Login,
+    Consent,
+    SelectAccount,
+    Extension(String),
+}
+
+impl Prompt {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::None => "none",
+            Self::Login => "login",
+            Self::Consent => "consent",
+            Self::SelectAccount => "select_account",
+            Self::Extension(prompt) => prompt,
+        }
+    }
 }
 
 // TODO 5.1.2 scopes
 
 impl<'a> AuthorizationRequest<'a> {
-    pub(crate) fn new(inner: oauth2::AuthorizationRequest<'a>) -> Self {
-        Self { inner }
+    pub(crate) fn new(
+        inner: oauth2::AuthorizationRequest<'a>,
+        code_challenge_methods_supported: Option<Vec<PkceCodeChallengeMethod>>,
+        response_modes_supported: Option<Vec<ResponseMode>>,
+    ) -> Self {
+        Self {
+            inner,
+            code_challenge_methods_supported,
+            response_modes_supported,
+            space_delimited_params: BTreeMap::new(),
+        }
     }
 
     pub fn url(self) -> (Url, CsrfToken) {
-        self.inner.url()
+        self.space_delimited_params
+            .into_iter()
+            .fold(self.inner, |inner, (name, value)| {
+                inner.add_extra_param(name, value)
+            })
+            .url()
     }
 
-    pub fn set_pkce_challenge(mut self, pkce_code_challenge: PkceCodeChallenge) -> Self {
+    /// Sets the PKCE challenge of the request.
+    ///
+    /// Fails if the authorization server advertises `code_challenge_methods_supported` and the
+    /// method of the challenge is not one of them.
+    pub fn set_pkce_challenge(
+        mut self,
+        pkce_code_challenge: PkceCodeChallenge,
+    ) -> Result<Self, UnsupportedCodeChallengeMethod> {
+        if let Some(methods) = &self.code_challenge_methods_supported {
+            if !methods.contains(pkce_code_challenge.method()) {
+                return Err(UnsupportedCodeChallengeMethod(
+                    pkce_code_challenge.method().clone(),
+                ));
+            }
+        }
         self.inner = self.inner.set_pkce_challenge(pkce_code_challenge);
-        self
+        Ok(self)
+    }
+
+    /// Sets the `response_mode` of the request, e.g. `form_post`.
+    ///
+    /// Fails if the authorization server advertises `response_modes_supported` and the mode is
+    /// not one of them.
+    pub fn set_response_mode(
+        mut self,
+        response_mode: ResponseMode,
+    ) -> Result<Self, UnsupportedResponseMode> {
+        if let Some(modes) = &self.response_modes_supported {
+            if !modes.contains(&response_mode) {
+                return Err(UnsupportedResponseMode(response_mode));
+            }
+        }
+        self.inner = self
+            .inner
+            .add_extra_param("response_mode", String::from(response_mode));
+        Ok(self)
     }
 
     pub fn set_authorization_details<AD: AuthorizationDetailsObjectProfile>(
@@ -40,6 +135,22 @@ impl<'a> AuthorizationRequest<'a> {
         Ok(self)
     }
 
+    /// Requests a Verifiable Presentation matching `presentation_definition` alongside the
+    /// authorization code, for issuers that require an existing credential to be presented.
+    pub fn set_presentation_definition(
+        mut self,
+        presentation_definition: &PresentationDefinition,
+    ) -> Result<Self, serde_json::Error> {
+        self.inner = self
+            .inner
+            .set_response_type(&ResponseType::new(RESPONSE_TYPE_CODE_VP_TOKEN.into()))
+            .add_extra_param(
+                "presentation_definition",
+                serde_json::to_string(presentation_definition)?,
+            );
+        Ok(self)
+    }
+
     pub fn set_issuer_state(mut self, issuer_state: &'a IssuerState) -> Self {
         self.inner = self
             .inner
@@ -52,6 +163,38 @@ impl<'a> AuthorizationRequest<'a> {
         self
     }
 
+    /// Sets the space-delimited `prompt` parameter, e.g. `login consent`, replacing any previous
+    /// value. An empty `prompt` removes the parameter.
+    pub fn set_prompt(self, prompt: &[Prompt]) -> Self {
+        self.set_space_delimited_param("prompt", prompt.iter().map(Prompt::as_str))
+    }
+
+    /// Sets the space-delimited `ui_locales` parameter, by order of preference, replacing any
+    /// previous value. Empty `ui_locales` remove the parameter.
+    pub fn set_ui_locales(self, ui_locales: &[LanguageTag]) -> Self {
+        self.set_space_delimited_param("ui_locales", ui_locales.iter().map(|l| l.as_str()))
+    }
+
+    /// Sets the space-delimited `acr_values` parameter, by order of preference, replacing any
+    /// previous value. Empty `acr_values` remove the parameter.
+    pub fn set_acr_values(self, acr_values: &[String]) -> Self {
+        self.set_space_delimited_param("acr_values", acr_values.iter().map(String::as_str))
+    }
+
+    fn set_space_delimited_param<'v>(
+        mut self,
+        name: &'static str,
+        values: impl Iterator<Item = &'v str>,
+    ) -> Self {
+        let value = values.collect::<Vec<_>>().join(" ");
+        if value.is_empty() {
+            self.space_delimited_params.remove(name);
+        } else {
+            self.space_delimited_params.insert(name, value);
+        }
+        self
+    }
+
     pub fn set_wallet_issuer(mut self, wallet_issuer: &'a IssuerUrl) -> Self {
         self.inner = self
             .inner
@@ -79,6 +222,13 @@ where
     additional_profile_fields: AD,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     locations: Vec<IssuerUrl>,
+    /// Identifiers assigned by the authorization server in the token response, to be used in the
+    /// credential request.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    credential_identifiers: Vec<CredentialConfigurationId>,
+    /// Credentials that must be presented in a `vp_token` before the credential is issued.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    presentation_definition: Option<PresentationDefinition>,
 }
 
 impl<AD> AuthorizationDetailsObject<AD>
@@ -90,6 +240,8 @@ where
             r#type: AuthorizationDetailsObjectType::OpenidCredential,
             additional_profile_fields,
             locations: Vec::new(),
+            credential_identifiers: Vec::new(),
+            presentation_definition: None,
         }
     }
 
@@ -97,8 +249,43 @@ where
         pub self [self] ["authorization detail value"] {
             set_additional_profile_fields -> additional_profile_fields[AD],
             set_locations -> locations[Vec<IssuerUrl>],
+            set_credential_identifiers -> credential_identifiers[Vec<CredentialConfigurationId>],
+            set_presentation_definition -> presentation_definition[Option<PresentationDefinition>],
         }
     ];
+
+    /// Whether a `vp_token` must be presented to obtain this credential.
+    pub fn requires_vp_token(&self) -> bool {
+        self.presentation_definition.is_some()
+    }
+
+    /// Adds a location the authorization detail applies to, to target a specific credential
+    /// issuer when the authorization server serves several of them.
+    pub fn add_location(mut self, location: IssuerUrl) -> Self {
+        if !self.locations.contains(&location) {
+            self.locations.push(location);
+        }
+        self
+    }
+
+    /// Checks that all the locations are either the credential issuer of `metadata`, or one of
+    /// its authorization servers.
+    pub fn check_locations<CM>(
+        &self,
+        metadata: &CredentialIssuerMetadata<CM>,
+    ) -> Result<(), UnknownLocation>
+    where
+        CM: CredentialConfigurationProfile,
+    {
+        let authorization_servers = metadata.authorization_servers();
+        match self.locations.iter().find(|location| {
+            *location != metadata.credential_issuer()
+                && !authorization_servers.is_some_and(|servers| servers.contains(location))
+        }) {
+            Some(location) => Err(UnknownLocation(location.clone())),
+            None => Ok(()),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
@@ -108,6 +295,116 @@ pub enum AuthorizationDetailsObjectType {
     OpenidCredential,
 }
 
+/// Error code of an authorization error response.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc6749#section-4.1.2.1>.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthorizationErrorType {
+    InvalidRequest,
+    UnauthorizedClient,
+    AccessDenied,
+    UnsupportedResponseType,
+    InvalidScope,
+    ServerError,
+    TemporarilyUnavailable,
+    #[serde(untagged)]
+    Extension(String),
+}
+impl ErrorResponseType for AuthorizationErrorType {}
+
+impl fmt::Display for AuthorizationErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let error = match self {
+            Self::InvalidRequest => "invalid_request",
+            Self::UnauthorizedClient => "unauthorized_client",
+            Self::AccessDenied => "access_denied",
+            Self::UnsupportedResponseType => "unsupported_response_type",
+            Self::InvalidScope => "invalid_scope",
+            Self::ServerError => "server_error",
+            Self::TemporarilyUnavailable => "temporarily_unavailable",
+            Self::Extension(error) => error,
+        };
+        f.write_str(error)
+    }
+}
+
+pub type AuthorizationErrorResponse = StandardErrorResponse<AuthorizationErrorType>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AuthorizationResponseError {
+    #[error("failed to parse the authorization response")]
+    Parse(#[source] serde_urlencoded::de::Error),
+    #[error("the state of the authorization response does not match the authorization request")]
+    StateMismatch,
+    #[error("authorization server returned an error response: {0}")]
+    ErrorResponse(AuthorizationErrorResponse),
+    #[error("the authorization response has no `code`")]
+    MissingCode,
+}
+
+#[derive(Deserialize)]
+struct AuthorizationResponseParams {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<AuthorizationErrorType>,
+    error_description: Option<String>,
+    error_uri: Option<String>,
+}
+
+/// Authorization response received at the redirect URI of the wallet, once the user agent is
+/// redirected back from the authorization server.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc6749#section-4.1.2>.
+#[derive(Debug)]
+pub struct AuthorizationResponse {
+    code: AuthorizationCode,
+    state: CsrfToken,
+}
+
+impl AuthorizationResponse {
+    /// Parses the query of the redirect URL, checking that its `state` matches the one of the
+    /// authorization request. Error responses are returned as
+    /// [`AuthorizationResponseError::ErrorResponse`].
+    pub fn from_redirect_url(
+        url: &Url,
+        expected_state: &CsrfToken,
+    ) -> Result<Self, AuthorizationResponseError> {
+        let params: AuthorizationResponseParams =
+            serde_urlencoded::from_str(url.query().unwrap_or_default())
+                .map_err(AuthorizationResponseError::Parse)?;
+
+        let state = CsrfToken::new(params.state.unwrap_or_default());
+        if &state != expected_state {
+            return Err(AuthorizationResponseError::StateMismatch);
+        }
+
+        if let Some(error) = params.error {
+            return Err(AuthorizationResponseError::ErrorResponse(
+                AuthorizationErrorResponse::new(error, params.error_description, params.error_uri),
+            ));
+        }
+
+        let code = params.code.ok_or(AuthorizationResponseError::MissingCode)?;
+        Ok(Self {
+            code: AuthorizationCode::new(code),
+            state,
+        })
+    }
+
+    pub fn code(&self) -> &AuthorizationCode {
+        &self.code
+    }
+
+    pub fn state(&self) -> &CsrfToken {
+        &self.state
+    }
+
+    pub fn into_code(self) -> AuthorizationCode {
+        self.code
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::collections::HashSet;
@@ -116,8 +413,9 @@ mod test {
     use serde_json::json;
 
     use crate::{
-        metadata::AuthorizationServerMetadata,
+        metadata::{authorization_server::ResponseModes, AuthorizationServerMetadata},
         profiles::core::{
+            client::Client,
             metadata::CredentialIssuerMetadata,
             profiles::{jwt_vc_json, CoreProfilesAuthorizationDetailsObject},
         },
@@ -126,6 +424,28 @@ mod test {
 
     use super::*;
 
+    fn authorization_server_metadata() -> AuthorizationServerMetadata {
+        AuthorizationServerMetadata::new(
+            IssuerUrl::new("https://server.example.com".into()).unwrap(),
+            TokenUrl::new("https://server.example.com/token".into()).unwrap(),
+        )
+        .set_authorization_endpoint(Some(
+            AuthUrl::new("https://server.example.com/authorize".into()).unwrap(),
+        ))
+    }
+
+    fn client(authorization_server_metadata: AuthorizationServerMetadata) -> Client {
+        Client::from_issuer_metadata(
+            ClientId::new("s6BhdRkqt3".to_string()),
+            RedirectUrl::new("https://client.example.org/cb".into()).unwrap(),
+            CredentialIssuerMetadata::new(
+                authorization_server_metadata.issuer().clone(),
+                CredentialUrl::new("https://server.example.com/credential".into()).unwrap(),
+            ),
+            authorization_server_metadata,
+        )
+    }
+
     #[test]
     fn example_authorization_details() {
         let _: Vec<AuthorizationDetailsObject<CoreProfilesAuthorizationDetailsObject>> =
@@ -191,6 +511,52 @@ mod test {
             .unwrap();
     }
 
+    #[test]
+    fn authorization_details_add_location() {
+        let authorization_detail: AuthorizationDetailsObject<
+            CoreProfilesAuthorizationDetailsObject,
+        > = serde_json::from_value(json!({
+            "type": "openid_credential",
+            "format": "jwt_vc_json",
+            "credential_definition": {
+                "type": ["VerifiableCredential", "UniversityDegreeCredential"]
+            }
+        }))
+        .unwrap();
+        let issuer = IssuerUrl::new("https://credential-issuer.example.com".into()).unwrap();
+        let server = IssuerUrl::new("https://server.example.com".into()).unwrap();
+        let authorization_detail = authorization_detail
+            .add_location(issuer.clone())
+            .add_location(server.clone())
+            .add_location(issuer.clone());
+
+        assert_json_diff::assert_json_eq!(
+            serde_json::to_value(&authorization_detail).unwrap(),
+            json!({
+                "type": "openid_credential",
+                "locations": [
+                    "https://credential-issuer.example.com",
+                    "https://server.example.com"
+                ],
+                "format": "jwt_vc_json",
+                "credential_definition": {
+                    "type": ["VerifiableCredential", "UniversityDegreeCredential"]
+                }
+            })
+        );
+
+        let metadata = CredentialIssuerMetadata::new(
+            issuer,
+            CredentialUrl::new("https://credential-issuer.example.com/credential".into()).unwrap(),
+        );
+        assert!(matches!(
+            authorization_detail.check_locations(&metadata),
+            Err(UnknownLocation(location)) if location == server
+        ));
+        let metadata = metadata.set_authorization_servers(Some(vec![server]));
+        authorization_detail.check_locations(&metadata).unwrap();
+    }
+
     #[test]
     fn example_authorization_details_multiple() {
         let _: Vec<crate::profiles::core::authorization::AuthorizationDetailsObject> =
@@ -264,15 +630,19 @@ mod test {
                         authorization_detail,
                     ),
                 _credential_identifier: (),
+                _credential_configuration_id: (),
             },
             locations: vec![],
+            credential_identifiers: vec![],
+            presentation_definition: None,
         }];
         let req = client
             .authorize_url(move || state)
             .unwrap()
             .set_authorization_details(authorization_details)
             .unwrap()
-            .set_pkce_challenge(pkce_challenge);
+            .set_pkce_challenge(pkce_challenge)
+            .unwrap();
 
         let (mut url, _) = req.url();
         let expected_query: HashSet<(String, String)> =
@@ -283,4 +653,208 @@ mod test {
         assert_eq!(expected_url, url);
         assert_eq!(expected_query, query);
     }
+
+    #[test]
+    fn response_mode() {
+        let client = client(
+            authorization_server_metadata().set_response_modes_supported(ResponseModes(vec![
+                ResponseMode::new("query".into()),
+                ResponseMode::new("form_post".into()),
+            ])),
+        );
+
+        let (url, _) = client
+            .authorize_url(|| CsrfToken::new("state".into()))
+            .unwrap()
+            .set_response_mode(ResponseMode::new("form_post".into()))
+            .unwrap()
+            .url();
+        assert!(url
+            .query_pairs()
+            .any(|(name, value)| name == "response_mode" && value == "form_post"));
+
+        let err = client
+            .authorize_url(|| CsrfToken::new("state".into()))
+            .unwrap()
+            .set_response_mode(ResponseMode::new("fragment".into()))
+            .err()
+            .unwrap();
+        assert_eq!(err.0.as_str(), "fragment");
+    }
+
+    #[test]
+    fn oidc_parameters() {
+        let client = client(authorization_server_metadata());
+
+        let (url, _) = client
+            .authorize_url(|| CsrfToken::new("state".into()))
+            .unwrap()
+            .set_prompt(&[Prompt::Login, Prompt::Consent])
+            .set_ui_locales(&[
+                LanguageTag::new("en-US".into()),
+                LanguageTag::new("fr-FR".into()),
+            ])
+            .set_acr_values(&["urn:mace:incommon:iap:silver".into()])
+            .url();
+
+        assert!(url.query().unwrap().contains("ui_locales=en-US+fr-FR"));
+        let query: HashSet<(String, String)> = url.query_pairs().into_owned().collect();
+        assert!(query.contains(&("prompt".into(), "login consent".into())));
+        assert!(query.contains(&("ui_locales".into(), "en-US fr-FR".into())));
+        assert!(query.contains(&("acr_values".into(), "urn:mace:incommon:iap:silver".into())));
+
+        let (url, _) = client
+            .authorize_url(|| CsrfToken::new("state".into()))
+            .unwrap()
+            .set_ui_locales(&[])
+            .url();
+        assert!(!url.query_pairs().any(|(name, _)| name == "ui_locales"));
+
+        let (url, _) = client
+            .authorize_url(|| CsrfToken::new("state".into()))
+            .unwrap()
+            .set_prompt(&[Prompt::Login])
+            .set_prompt(&[Prompt::Consent])
+            .set_ui_locales(&[LanguageTag::new("en-US".into())])
+            .set_ui_locales(&[])
+            .url();
+        let prompt: Vec<_> = url
+            .query_pairs()
+            .filter(|(name, _)| name == "prompt")
+            .map(|(_, value)| value.into_owned())
+            .collect();
+        assert_eq!(prompt, ["consent"]);
+        assert!(!url.query_pairs().any(|(name, _)| name == "ui_locales"));
+    }
+
+    #[test]
+    fn example_authorization_details_presentation_definition() {
+        let authorization_details: Vec<
+            AuthorizationDetailsObject<CoreProfilesAuthorizationDetailsObject>,
+        > = serde_json::from_value(json!([
+            {
+              "type": "openid_credential",
+              "credential_configuration_id": "UniversityDegreeCredential",
+              "presentation_definition": {
+                "id": "pid",
+                "input_descriptors": [
+                  {
+                    "id": "eu.europa.ec.eudi.pid.1",
+                    "format": {
+                      "mso_mdoc": {
+                        "alg": ["ES256"]
+                      }
+                    },
+                    "constraints": {
+                      "limit_disclosure": "required",
+                      "fields": [
+                        {
+                          "path": ["$['eu.europa.ec.eudi.pid.1']['family_name']"]
+                        }
+                      ]
+                    }
+                  }
+                ]
+              }
+            }
+        ]))
+        .unwrap();
+
+        assert!(authorization_details[0].requires_vp_token());
+        let presentation_definition = authorization_details[0].presentation_definition().unwrap();
+        assert_eq!(presentation_definition.id(), "pid");
+        assert_eq!(
+            presentation_definition.input_descriptors()[0].id(),
+            "eu.europa.ec.eudi.pid.1"
+        );
+    }
+
+    #[test]
+    fn authorization_request_presentation_definition() {
+        let client = client(authorization_server_metadata());
+
+        let presentation_definition = PresentationDefinition::new(
+            "pid".into(),
+            vec![crate::presentation::InputDescriptor::new(
+                "eu.europa.ec.eudi.pid.1".into(),
+            )],
+        );
+
+        let (url, _) = client
+            .authorize_url(|| CsrfToken::new("state".into()))
+            .unwrap()
+            .set_presentation_definition(&presentation_definition)
+            .unwrap()
+            .url();
+
+        let query: HashSet<(String, String)> = url.query_pairs().into_owned().collect();
+        assert!(query.contains(&("response_type".into(), "code vp_token".into())));
+        assert!(query.contains(&(
+            "presentation_definition".into(),
+            r#"{"id":"pid","input_descriptors":[{"id":"eu.europa.ec.eudi.pid.1"}]}"#.into()
+        )));
+    }
+
+    #[test]
+    fn authorization_response() {
+        let state = CsrfToken::new("xyz".into());
+
+        let url: Url = "https://client.example.org/cb?code=SplxlOBeZQQYbYS6WxSbIA&state=xyz"
+            .parse()
+            .unwrap();
+        let response = AuthorizationResponse::from_redirect_url(&url, &state).unwrap();
+        assert_eq!(response.code().secret(), "SplxlOBeZQQYbYS6WxSbIA");
+
+        let url: Url = "https://client.example.org/cb?code=SplxlOBeZQQYbYS6WxSbIA&state=abc"
+            .parse()
+            .unwrap();
+        assert!(matches!(
+            AuthorizationResponse::from_redirect_url(&url, &state),
+            Err(AuthorizationResponseError::StateMismatch)
+        ));
+
+        let url: Url = "https://client.example.org/cb?code=SplxlOBeZQQYbYS6WxSbIA"
+            .parse()
+            .unwrap();
+        assert!(matches!(
+            AuthorizationResponse::from_redirect_url(&url, &state),
+            Err(AuthorizationResponseError::StateMismatch)
+        ));
+
+        let url: Url = "https://client.example.org/cb?state=xyz".parse().unwrap();
+        assert!(matches!(
+            AuthorizationResponse::from_redirect_url(&url, &state),
+            Err(AuthorizationResponseError::MissingCode)
+        ));
+    }
+
+    #[test]
+    fn authorization_error_response() {
+        let url: Url = "https://client.example.org/cb?error=access_denied&error_description=The%20user%20denied%20the%20request&state=xyz"
+            .parse()
+            .unwrap();
+        let Err(AuthorizationResponseError::ErrorResponse(error)) =
+            AuthorizationResponse::from_redirect_url(&url, &CsrfToken::new("xyz".into()))
+        else {
+            panic!("expected an error response");
+        };
+        assert_eq!(error.error(), &AuthorizationErrorType::AccessDenied);
+        assert_eq!(
+            error.error_description().map(String::as_str),
+            Some("The user denied the request")
+        );
+
+        let url: Url = "https://client.example.org/cb?error=interaction_required&state=xyz"
+            .parse()
+            .unwrap();
+        let Err(AuthorizationResponseError::ErrorResponse(error)) =
+            AuthorizationResponse::from_redirect_url(&url, &CsrfToken::new("xyz".into()))
+        else {
+            panic!("expected an error response");
+        };
+        assert_eq!(
+            error.error(),
+            &AuthorizationErrorType::Extension("interaction_required".into())
+        );
+    }
 }
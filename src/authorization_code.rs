@@ -0,0 +1,206 @@
+use std::{borrow::Cow, future::Future};
+
+use oauth2::{
+    basic::BasicErrorResponse, AsyncHttpClient, CodeTokenRequest, HttpRequest, RequestTokenError,
+    SyncHttpClient,
+};
+
+use crate::{
+    authorization::AuthorizationDetailsObject, http_utils::set_no_store,
+    profiles::AuthorizationDetailsObjectProfile, token,
+};
+
+/// A request to exchange an authorization code for an access token.
+///
+/// The request is sent with the `Cache-Control: no-store` and `Pragma: no-cache` headers, as
+/// token responses must not be persisted by HTTP caches.
+///
+/// See <https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html#section-6.1>.
+#[derive(Debug)]
+pub struct AuthorizationCodeTokenRequest<'a> {
+    inner: CodeTokenRequest<'a, BasicErrorResponse, token::Response>,
+}
+
+impl<'a> AuthorizationCodeTokenRequest<'a> {
+    pub(crate) fn new(inner: CodeTokenRequest<'a, BasicErrorResponse, token::Response>) -> Self {
+        Self { inner }
+    }
+
+    /// Sets the `authorization_details` of the token request, which is required when
+    /// `authorization_details` were not used in the authorization request.
+    pub fn set_authorization_details<AD: AuthorizationDetailsObjectProfile>(
+        mut self,
+        authorization_details: Vec<AuthorizationDetailsObject<AD>>,
+    ) -> Result<Self, serde_json::Error> {
+        self.inner = self.inner.add_extra_param(
+            "authorization_details",
+            serde_json::to_string(&authorization_details)?,
+        );
+        Ok(self)
+    }
+
+    /// Appends an extra param to the token request.
+    ///
+    /// See [`CodeTokenRequest::add_extra_param`].
+    pub fn add_extra_param<N, V>(mut self, name: N, value: V) -> Self
+    where
+        N: Into<Cow<'a, str>>,
+        V: Into<Cow<'a, str>>,
+    {
+        self.inner = self.inner.add_extra_param(name, value);
+        self
+    }
+
+    /// Synchronously sends the request to the authorization server and awaits a response.
+    pub fn request<C>(
+        self,
+        http_client: &C,
+    ) -> Result<token::Response, RequestTokenError<<C as SyncHttpClient>::Error, BasicErrorResponse>>
+    where
+        C: SyncHttpClient,
+    {
+        self.inner.request(&|mut request: HttpRequest| {
+            set_no_store(request.headers_mut());
+            http_client.call(request)
+        })
+    }
+
+    /// Asynchronously sends the request to the authorization server and returns a Future.
+    pub fn request_async<'c, C>(
+        self,
+        http_client: &'c C,
+    ) -> impl Future<
+        Output = Result<
+            token::Response,
+            RequestTokenError<<C as AsyncHttpClient<'c>>::Error, BasicErrorResponse>,
+        >,
+    > + 'c
+    where
+        Self: 'c,
+        C: AsyncHttpClient<'c>,
+    {
+        Box::pin(async move {
+            let http_client = |mut request: HttpRequest| {
+                set_no_store(request.headers_mut());
+                http_client.call(request)
+            };
+            self.inner.request_async(&http_client).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use oauth2::{
+        http::{
+            self,
+            header::{CACHE_CONTROL, CONTENT_TYPE, PRAGMA},
+        },
+        AuthUrl, AuthorizationCode, ClientId, HttpRequest, HttpResponse, PkceCodeVerifier,
+        RedirectUrl, TokenResponse, TokenUrl,
+    };
+    use serde_json::json;
+
+    use crate::{
+        metadata::AuthorizationServerMetadata,
+        profiles::core::{
+            metadata::CredentialIssuerMetadata, profiles::CoreProfilesAuthorizationDetailsObject,
+        },
+        types::{CredentialConfigurationId, CredentialUrl, IssuerUrl},
+    };
+
+    use super::*;
+
+    #[test]
+    fn exchange_authorization_code() {
+        let issuer = IssuerUrl::new("https://server.example.com".into()).unwrap();
+
+        let credential_issuer_metadata = CredentialIssuerMetadata::new(
+            issuer.clone(),
+            CredentialUrl::new("https://server.example.com/credential".into()).unwrap(),
+        );
+
+        let authorization_server_metadata = AuthorizationServerMetadata::new(
+            issuer,
+            TokenUrl::new("https://server.example.com/token".into()).unwrap(),
+        )
+        .set_authorization_endpoint(Some(
+            AuthUrl::new("https://server.example.com/authorize".into()).unwrap(),
+        ));
+
+        let client = crate::profiles::core::client::Client::from_issuer_metadata(
+            ClientId::new("s6BhdRkqt3".to_string()),
+            RedirectUrl::new("https://client.example.org/cb".into()).unwrap(),
+            credential_issuer_metadata,
+            authorization_server_metadata,
+        );
+
+        let authorization_details = vec![AuthorizationDetailsObject::new(
+            CoreProfilesAuthorizationDetailsObject::WithIdAndUnresolvedProfile {
+                credential_configuration_id: CredentialConfigurationId::new(
+                    "UniversityDegreeCredential".into(),
+                ),
+                inner: HashMap::new(),
+                _format: (),
+            },
+        )];
+
+        let http_client = |request: HttpRequest| -> Result<HttpResponse, std::io::Error> {
+            let params: HashMap<String, String> = form_urlencoded::parse(request.body())
+                .into_owned()
+                .collect();
+            assert_eq!(request.headers()[CACHE_CONTROL], "no-store");
+            assert_eq!(request.headers()[PRAGMA], "no-cache");
+            assert_eq!(params["grant_type"], "authorization_code");
+            assert_eq!(params["code"], "SplxlOBeZQQYbYS6WxSbIA");
+            assert_eq!(
+                params["code_verifier"],
+                "challengechallengechallengechallengechallenge"
+            );
+            assert_eq!(
+                params["authorization_details"],
+                r#"[{"type":"openid_credential","credential_configuration_id":"UniversityDegreeCredential"}]"#
+            );
+
+            Ok(http::Response::builder()
+                .status(200)
+                .header(CONTENT_TYPE, "application/json")
+                .body(
+                    serde_json::to_vec(&json!({
+                        "access_token": "eyJhbGciOiJSUzI1NiIsInR5cCI6Ikp..sHQ",
+                        "token_type": "bearer",
+                        "expires_in": 86400,
+                        "c_nonce": "tZignsnFbp"
+                    }))
+                    .unwrap(),
+                )
+                .unwrap())
+        };
+
+        let token_response = client
+            .exchange_authorization_code(
+                AuthorizationCode::new("SplxlOBeZQQYbYS6WxSbIA".into()),
+                PkceCodeVerifier::new("challengechallengechallengechallengechallenge".into()),
+            )
+            .set_authorization_details(authorization_details)
+            .unwrap()
+            .request(&http_client)
+            .unwrap();
+
+        assert_eq!(
+            token_response.access_token().secret(),
+            "eyJhbGciOiJSUzI1NiIsInR5cCI6Ikp..sHQ"
+        );
+        assert_eq!(
+            token_response
+                .extra_fields()
+                .c_nonce
+                .as_ref()
+                .unwrap()
+                .secret(),
+            "tZignsnFbp"
+        );
+    }
+}
@@ -1,27 +1,42 @@
 use std::marker::PhantomData;
 
+use futures::stream::{self, StreamExt};
 use oauth2::{
     basic::{BasicErrorResponse, BasicRevocationErrorResponse, BasicTokenIntrospectionResponse},
-    AccessToken, AuthUrl, AuthorizationCode, ClientId, CodeTokenRequest, ConfigurationError,
-    CsrfToken, EndpointMaybeSet, EndpointNotSet, EndpointSet, RedirectUrl, StandardRevocableToken,
-    TokenUrl,
+    AccessToken, AsyncHttpClient, AuthType, AuthUrl, AuthorizationCode, ClientId, CodeTokenRequest,
+    ConfigurationError, CsrfToken, EndpointMaybeSet, EndpointNotSet, EndpointSet,
+    IntrospectionRequest, IntrospectionUrl, PkceCodeChallenge, PkceCodeChallengeMethod,
+    PkceCodeVerifier, RedirectUrl, RevocationRequest, RevocationUrl, Scope, StandardRevocableToken,
+    SyncHttpClient, TokenResponse, TokenUrl,
 };
+use url::Url;
 
 use crate::{
-    authorization::AuthorizationRequest,
-    credential,
+    authorization::{
+        AuthorizationDetailsObject, AuthorizationRequest, UnsupportedCodeChallengeMethod,
+    },
+    authorization_code::AuthorizationCodeTokenRequest,
+    credential::{self, RequestError},
+    credential_offer::{CredentialOffer, CredentialOfferParameters},
     credential_response_encryption::CredentialResponseEncryptionMetadata,
     metadata::{
-        credential_issuer::{CredentialConfiguration, CredentialIssuerMetadataDisplay},
-        AuthorizationServerMetadata, CredentialIssuerMetadata,
+        authorization_server::{GrantType, GrantTypesSupported},
+        credential_issuer::{
+            BatchCredentialIssuance, CredentialConfiguration, CredentialIssuerMetadataDisplay,
+        },
+        AuthorizationServerMetadata, CredentialIssuerMetadata, DiscoveryError, MetadataDiscovery,
     },
     pre_authorized_code::PreAuthorizedCodeTokenRequest,
-    profiles::Profile,
+    profiles::{
+        AuthorizationDetailsObjectProfile, CredentialConfigurationProfile,
+        CredentialRequestProfile, Profile,
+    },
+    proof_of_possession::{ProofOfPossessionController, ProofOfPossessionParams},
     pushed_authorization::PushedAuthorizationRequest,
     token,
     types::{
-        BatchCredentialUrl, CredentialUrl, DeferredCredentialUrl, IssuerUrl, ParUrl,
-        PreAuthorizedCode,
+        BatchCredentialUrl, CredentialConfigurationId, CredentialUrl, DeferredCredentialUrl,
+        IssuerUrl, Nonce, NotificationUrl, ParUrl, PreAuthorizedCode, ResponseMode,
     },
 };
 
@@ -29,12 +44,92 @@ use crate::{
 pub enum Error {
     #[error("Batch Credential Request are not supported by this issuer")]
     BcrUnsupported,
+    #[error("Batch of {requested} credentials exceeds the issuer limit of {max}")]
+    BatchTooLarge { requested: usize, max: usize },
+    #[error("Deferred Credential Request are not supported by this issuer")]
+    DeferredUnsupported,
     #[error("Pushed Authorization Requests are not supported by this issuer")]
     ParUnsupported,
+    #[error("Pushed Authorization Requests are required by this issuer, but no endpoint is known")]
+    ParEndpointMissing,
+    #[error("Token introspection is not supported by the authorization server: {0}")]
+    IntrospectionUnsupported(ConfigurationError),
+    #[error("Token revocation is not supported by the authorization server: {0}")]
+    RevocationUnsupported(ConfigurationError),
     #[error("Authorization Requests are not supported by this issuer: {0}")]
     AuthUnsupported(ConfigurationError),
-    #[error("An error occurred when discovering metadata: {0}")]
-    MetadataDiscovery(anyhow::Error),
+    #[error("An error occurred when discovering metadata")]
+    MetadataDiscovery(#[from] DiscoveryError),
+    #[error("Failed to resolve the credential offer")]
+    CredentialOffer(#[source] anyhow::Error),
+    #[error("Credential requests by credential identifier are not supported by this profile")]
+    CredentialIdentifierUnsupported,
+    #[error("Authorization details by credential configuration identifier are not supported by this profile")]
+    CredentialConfigurationIdUnsupported,
+    #[error("Failed to serialize authorization details")]
+    AuthorizationDetails(#[source] serde_json::Error),
+    #[error("The state of the authorization response does not match the authorization request")]
+    StateMismatch,
+    #[error(transparent)]
+    PkceUnsupported(#[from] UnsupportedCodeChallengeMethod),
+}
+
+/// An authorization code flow started with [`Client::begin_authorization`].
+///
+/// It must be kept until the user agent is redirected back to the wallet, and then passed to
+/// [`Client::complete_authorization`].
+#[derive(Debug)]
+pub struct PendingAuthorization {
+    pkce_verifier: PkceCodeVerifier,
+    state: CsrfToken,
+}
+
+impl PendingAuthorization {
+    pub fn state(&self) -> &CsrfToken {
+        &self.state
+    }
+}
+
+/// Authorization request started from a credential offer with
+/// [`Client::begin_authorization_for_offer`].
+///
+/// It is pushed to the authorization server when the server requires pushed authorization
+/// requests.
+pub enum OfferAuthorizationRequest<'a> {
+    Redirect(AuthorizationRequest<'a>),
+    Pushed(PushedAuthorizationRequest<'a>),
+}
+
+impl<'a> OfferAuthorizationRequest<'a> {
+    /// Returns the URL to which the user agent must be redirected, pushing the request first if
+    /// needed.
+    pub fn request<C>(
+        self,
+        http_client: &C,
+    ) -> Result<Url, RequestError<<C as SyncHttpClient>::Error>>
+    where
+        C: SyncHttpClient,
+    {
+        match self {
+            Self::Redirect(request) => Ok(request.url().0),
+            Self::Pushed(request) => Ok(request.request(http_client)?.0),
+        }
+    }
+
+    /// Asynchronous version of [`OfferAuthorizationRequest::request`].
+    pub async fn request_async<'c, C>(
+        self,
+        http_client: &'c C,
+    ) -> Result<Url, RequestError<<C as AsyncHttpClient<'c>>::Error>>
+    where
+        'a: 'c,
+        C: AsyncHttpClient<'c>,
+    {
+        match self {
+            Self::Redirect(request) => Ok(request.url().0),
+            Self::Pushed(request) => Ok(request.async_request(http_client).await?.0),
+        }
+    }
 }
 
 pub struct Client<C>
@@ -49,16 +144,22 @@ where
         BasicRevocationErrorResponse,
         EndpointMaybeSet,
         EndpointNotSet,
-        EndpointNotSet,
-        EndpointNotSet,
+        EndpointMaybeSet,
+        EndpointMaybeSet,
         EndpointSet,
     >,
     issuer: IssuerUrl,
     credential_endpoint: CredentialUrl,
     par_auth_url: Option<ParUrl>,
+    require_pushed_authorization_requests: bool,
+    grant_types_supported: GrantTypesSupported,
+    code_challenge_methods_supported: Option<Vec<PkceCodeChallengeMethod>>,
+    response_modes_supported: Option<Vec<ResponseMode>>,
     batch_credential_endpoint: Option<BatchCredentialUrl>,
     deferred_credential_endpoint: Option<DeferredCredentialUrl>,
+    notification_endpoint: Option<NotificationUrl>,
     credential_response_encryption: Option<CredentialResponseEncryptionMetadata>,
+    batch_credential_issuance: Option<BatchCredentialIssuance>,
     credential_configurations_supported: Vec<CredentialConfiguration<C::CredentialConfiguration>>,
     display: Option<Vec<CredentialIssuerMetadataDisplay>>,
 }
@@ -71,14 +172,29 @@ where
         pub self [self] ["client configuration value"] {
             set_issuer -> issuer[IssuerUrl],
             set_credential_endpoint -> credential_endpoint[CredentialUrl],
+            set_require_pushed_authorization_requests -> require_pushed_authorization_requests[bool],
+            set_grant_types_supported -> grant_types_supported[GrantTypesSupported],
             set_batch_credential_endpoint -> batch_credential_endpoint[Option<BatchCredentialUrl>],
             set_deferred_credential_endpoint -> deferred_credential_endpoint[Option<DeferredCredentialUrl>],
+            set_notification_endpoint -> notification_endpoint[Option<NotificationUrl>],
             set_credential_response_encryption -> credential_response_encryption[Option<CredentialResponseEncryptionMetadata>],
+            set_batch_credential_issuance -> batch_credential_issuance[Option<BatchCredentialIssuance>],
             set_credential_configurations_supported -> credential_configurations_supported[Vec<CredentialConfiguration<C::CredentialConfiguration>>],
             set_display -> display[Option<Vec<CredentialIssuerMetadataDisplay>>],
         }
     ];
 
+    /// Sets how the client authenticates to the token endpoint, in the `Authorization` header
+    /// with [`AuthType::BasicAuth`] or in the request body with [`AuthType::RequestBody`].
+    pub fn set_auth_type(mut self, auth_type: AuthType) -> Self {
+        self.inner = self.inner.set_auth_type(auth_type);
+        self
+    }
+
+    pub fn auth_type(&self) -> &AuthType {
+        self.inner.auth_type()
+    }
+
     pub fn from_issuer_metadata(
         client_id: ClientId,
         redirect_uri: RedirectUrl,
@@ -90,6 +206,8 @@ where
             redirect_uri,
             authorization_metadata.authorization_endpoint().cloned(),
             authorization_metadata.token_endpoint().clone(),
+            authorization_metadata.introspection_endpoint().cloned(),
+            authorization_metadata.revocation_endpoint().cloned(),
         );
 
         Self {
@@ -99,15 +217,28 @@ where
             par_auth_url: authorization_metadata
                 .pushed_authorization_request_endpoint()
                 .cloned(),
+            require_pushed_authorization_requests: *authorization_metadata
+                .require_pushed_authorization_requests(),
+            grant_types_supported: authorization_metadata.grant_types_supported().clone(),
+            code_challenge_methods_supported: authorization_metadata
+                .code_challenge_methods_supported()
+                .cloned(),
+            response_modes_supported: Some(
+                authorization_metadata.response_modes_supported().0.clone(),
+            ),
             batch_credential_endpoint: credential_issuer_metadata
                 .batch_credential_endpoint()
                 .cloned(),
             deferred_credential_endpoint: credential_issuer_metadata
                 .deferred_credential_endpoint()
                 .cloned(),
+            notification_endpoint: credential_issuer_metadata.notification_endpoint().cloned(),
             credential_response_encryption: credential_issuer_metadata
                 .credential_response_encryption()
                 .cloned(),
+            batch_credential_issuance: credential_issuer_metadata
+                .batch_credential_issuance()
+                .cloned(),
             credential_configurations_supported: credential_issuer_metadata
                 .credential_configurations_supported()
                 .clone(),
@@ -115,6 +246,188 @@ where
         }
     }
 
+    /// Same as [`Client::from_issuer_metadata`], but discovers the metadata of the first
+    /// authorization server of the credential issuer that supports `grant_type`, see
+    /// [`AuthorizationServerMetadata::discover_from_credential_issuer_metadata_async`].
+    pub async fn from_issuer_metadata_auto<'c, H>(
+        client_id: ClientId,
+        redirect_uri: RedirectUrl,
+        credential_issuer_metadata: CredentialIssuerMetadata<C::CredentialConfiguration>,
+        grant_type: GrantType,
+        http_client: &'c H,
+    ) -> Result<Self, Error>
+    where
+        H: AsyncHttpClient<'c>,
+        H::Error: Send + Sync,
+    {
+        let authorization_metadata =
+            AuthorizationServerMetadata::discover_from_credential_issuer_metadata_async(
+                http_client,
+                &credential_issuer_metadata,
+                Some(&grant_type),
+                None,
+            )
+            .await?;
+
+        Ok(Self::from_issuer_metadata(
+            client_id,
+            redirect_uri,
+            credential_issuer_metadata,
+            authorization_metadata,
+        ))
+    }
+
+    /// Build a client for a credential offer.
+    ///
+    /// The offer is resolved, then the metadata of the credential issuer and of the authorization
+    /// server of the offer's grant are discovered, using the `authorization_server` hint of the
+    /// grant. The pre-authorized code grant is preferred if the offer has both grants.
+    ///
+    /// Returns the client along with the resolved offer, which holds the grant to use.
+    pub async fn from_offer<'c, H>(
+        client_id: ClientId,
+        redirect_uri: RedirectUrl,
+        credential_offer: CredentialOffer<C::CredentialConfiguration>,
+        http_client: &'c H,
+    ) -> Result<(Self, CredentialOfferParameters<C::CredentialConfiguration>), Error>
+    where
+        H: AsyncHttpClient<'c>,
+        H::Error: Send + Sync,
+    {
+        let credential_offer = credential_offer
+            .resolve_async(http_client)
+            .await
+            .map_err(Error::CredentialOffer)?;
+
+        let credential_issuer_metadata =
+            CredentialIssuerMetadata::discover_async(credential_offer.issuer(), http_client)
+                .await?;
+
+        let (grant_type, authorization_server) = match (
+            credential_offer.pre_authorized_code_grant(),
+            credential_offer.authorization_code_grant(),
+        ) {
+            (Some(grant), _) => (
+                Some(GrantType::PreAuthorizedCode),
+                grant.authorization_server(),
+            ),
+            (None, Some(grant)) => (
+                Some(GrantType::AuthorizationCode),
+                grant.authorization_server(),
+            ),
+            (None, None) => (None, None),
+        };
+        let authorization_metadata =
+            AuthorizationServerMetadata::discover_from_credential_issuer_metadata_async(
+                http_client,
+                &credential_issuer_metadata,
+                grant_type.as_ref(),
+                authorization_server,
+            )
+            .await?;
+
+        Ok((
+            Self::from_issuer_metadata(
+                client_id,
+                redirect_uri,
+                credential_issuer_metadata,
+                authorization_metadata,
+            ),
+            credential_offer,
+        ))
+    }
+
+    /// Whether the authorization server only accepts authorization requests pushed with
+    /// [`Client::pushed_authorization_request`].
+    pub fn requires_par(&self) -> bool {
+        self.require_pushed_authorization_requests
+    }
+
+    /// Returns the supported credential configurations of the given `format`, e.g. `mso_mdoc`.
+    pub fn configurations_by_format<'a>(
+        &'a self,
+        format: &'a str,
+    ) -> impl Iterator<Item = &'a CredentialConfiguration<C::CredentialConfiguration>> {
+        self.credential_configurations_supported
+            .iter()
+            .filter(move |configuration| {
+                configuration
+                    .profile_specific_fields()
+                    .format_identifier()
+                    .is_some_and(|identifier| identifier == format)
+            })
+    }
+
+    /// Returns the supported credential configurations that can be requested with `scope`.
+    pub fn configurations_by_scope<'a>(
+        &'a self,
+        scope: &'a Scope,
+    ) -> impl Iterator<Item = &'a CredentialConfiguration<C::CredentialConfiguration>> {
+        self.credential_configurations_supported
+            .iter()
+            .filter(move |configuration| configuration.scope() == Some(scope))
+    }
+
+    /// Whether the authorization server supports the grant type.
+    pub fn supports_grant(&self, grant_type: &GrantType) -> bool {
+        self.grant_types_supported.contains(grant_type)
+    }
+
+    /// Returns the parameters to generate a [`ProofOfPossession`] for this issuer.
+    ///
+    /// The `audience` of the proof is the credential issuer identifier, not the credential
+    /// endpoint, and its `issuer` is the client identifier.
+    ///
+    /// [`ProofOfPossession`]: crate::proof_of_possession::ProofOfPossession
+    pub fn proof_params(
+        &self,
+        nonce: Option<Nonce>,
+        controller: ProofOfPossessionController,
+    ) -> ProofOfPossessionParams {
+        ProofOfPossessionParams {
+            audience: self.issuer.url().clone(),
+            issuer: self.inner.client_id().as_str().to_owned(),
+            nonce,
+            controller,
+        }
+    }
+
+    /// Returns the introspection endpoint of the authorization server, if any.
+    pub fn introspection_url(&self) -> Option<&IntrospectionUrl> {
+        self.inner.introspection_url()
+    }
+
+    /// Build an [RFC 7662](https://tools.ietf.org/html/rfc7662) introspection request for
+    /// `token`, sent to the introspection endpoint of the authorization server.
+    pub fn introspect_token<'a>(
+        &'a self,
+        token: &'a AccessToken,
+    ) -> Result<IntrospectionRequest<'a, BasicErrorResponse, BasicTokenIntrospectionResponse>, Error>
+    {
+        self.inner
+            .introspect(token)
+            .map_err(Error::IntrospectionUnsupported)
+    }
+
+    /// Returns the revocation endpoint of the authorization server, if any.
+    pub fn revocation_url(&self) -> Option<&RevocationUrl> {
+        self.inner.revocation_url()
+    }
+
+    /// Build an [RFC 7009](https://tools.ietf.org/html/rfc7009) revocation request for `token`,
+    /// sent to the revocation endpoint of the authorization server.
+    ///
+    /// The `token_type_hint` depends on whether `token` is an access or a refresh token.
+    pub fn revoke_token(
+        &self,
+        token: StandardRevocableToken,
+    ) -> Result<RevocationRequest<StandardRevocableToken, BasicRevocationErrorResponse>, Error>
+    {
+        self.inner
+            .revoke_token(token)
+            .map_err(Error::RevocationUnsupported)
+    }
+
     pub fn pushed_authorization_request<S>(
         &self,
         state_fn: S,
@@ -123,6 +436,9 @@ where
         S: FnOnce() -> CsrfToken,
     {
         let Some(par_url) = self.par_auth_url.as_ref() else {
+            if self.requires_par() {
+                return Err(Error::ParEndpointMissing);
+            }
             return Err(Error::ParUnsupported);
         };
         let inner = self.authorize_url(state_fn)?;
@@ -146,7 +462,116 @@ where
             .inner
             .authorize_url(state_fn)
             .map_err(Error::AuthUnsupported)?;
-        Ok(AuthorizationRequest::new(inner))
+        Ok(AuthorizationRequest::new(
+            inner,
+            self.code_challenge_methods_supported.clone(),
+            self.response_modes_supported.clone(),
+        ))
+    }
+
+    /// Start an authorization code flow for the given credential configurations.
+    ///
+    /// A PKCE challenge and a CSRF state are generated, and the `authorization_details` parameter
+    /// references each credential configuration. Returns the URL to which the user agent must be
+    /// redirected.
+    pub fn begin_authorization(
+        &self,
+        credential_configuration_ids: &[CredentialConfigurationId],
+    ) -> Result<(Url, PendingAuthorization), Error> {
+        let authorization_details = self.authorization_details(credential_configuration_ids)?;
+
+        let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+        let (url, state) = self
+            .authorize_url(CsrfToken::new_random)?
+            .set_pkce_challenge(pkce_challenge)?
+            .set_authorization_details(authorization_details)
+            .map_err(Error::AuthorizationDetails)?
+            .url();
+
+        Ok((
+            url,
+            PendingAuthorization {
+                pkce_verifier,
+                state,
+            },
+        ))
+    }
+
+    /// Start an authorization code flow for the credential configurations of a credential offer.
+    ///
+    /// The `issuer_state` of the offer's authorization code grant is forwarded to the
+    /// authorization server, and the request is routed through the pushed authorization request
+    /// endpoint if the authorization server requires it.
+    pub fn begin_authorization_for_offer<'a>(
+        &'a self,
+        credential_offer: &'a CredentialOfferParameters<C::CredentialConfiguration>,
+    ) -> Result<(OfferAuthorizationRequest<'a>, PendingAuthorization), Error> {
+        let authorization_details =
+            self.authorization_details(credential_offer.credential_configuration_ids())?;
+        let issuer_state = credential_offer
+            .authorization_code_grant()
+            .and_then(|grant| grant.issuer_state());
+
+        let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+        let state = CsrfToken::new_random();
+        let pending_authorization = PendingAuthorization {
+            pkce_verifier,
+            state: state.clone(),
+        };
+
+        let request = if self.require_pushed_authorization_requests {
+            let mut request = self
+                .pushed_authorization_request(move || state)?
+                .set_pkce_challenge(pkce_challenge)?
+                .set_authorization_details(authorization_details)
+                .map_err(Error::AuthorizationDetails)?;
+            if let Some(issuer_state) = issuer_state {
+                request = request.set_issuer_state(issuer_state);
+            }
+            OfferAuthorizationRequest::Pushed(request)
+        } else {
+            let mut request = self
+                .authorize_url(move || state)?
+                .set_pkce_challenge(pkce_challenge)?
+                .set_authorization_details(authorization_details)
+                .map_err(Error::AuthorizationDetails)?;
+            if let Some(issuer_state) = issuer_state {
+                request = request.set_issuer_state(issuer_state);
+            }
+            OfferAuthorizationRequest::Redirect(request)
+        };
+
+        Ok((request, pending_authorization))
+    }
+
+    fn authorization_details(
+        &self,
+        credential_configuration_ids: &[CredentialConfigurationId],
+    ) -> Result<Vec<AuthorizationDetailsObject<C::AuthorizationDetailsObject>>, Error> {
+        credential_configuration_ids
+            .iter()
+            .map(|credential_configuration_id| {
+                C::AuthorizationDetailsObject::from_credential_configuration_id(
+                    credential_configuration_id.clone(),
+                )
+                .map(AuthorizationDetailsObject::new)
+                .ok_or(Error::CredentialConfigurationIdUnsupported)
+            })
+            .collect()
+    }
+
+    /// Complete an authorization code flow started with [`Client::begin_authorization`], with
+    /// the `code` and `state` of the authorization response.
+    pub fn complete_authorization(
+        &self,
+        pending_authorization: PendingAuthorization,
+        code: AuthorizationCode,
+        state: &CsrfToken,
+    ) -> Result<AuthorizationCodeTokenRequest<'_>, Error> {
+        if &pending_authorization.state != state {
+            return Err(Error::StateMismatch);
+        }
+        Ok(self.exchange_authorization_code(code, pending_authorization.pkce_verifier))
     }
 
     pub fn exchange_code(
@@ -156,6 +581,20 @@ where
         self.inner.exchange_code(code)
     }
 
+    /// Exchange an authorization code obtained through the authorization code flow, completing
+    /// the PKCE flow started with [`AuthorizationRequest::set_pkce_challenge`].
+    pub fn exchange_authorization_code(
+        &self,
+        code: AuthorizationCode,
+        pkce_verifier: PkceCodeVerifier,
+    ) -> AuthorizationCodeTokenRequest<'_> {
+        AuthorizationCodeTokenRequest::new(
+            self.inner
+                .exchange_code(code)
+                .set_pkce_verifier(pkce_verifier),
+        )
+    }
+
     pub fn exchange_pre_authorized_code(
         &self,
         pre_authorized_code: PreAuthorizedCode,
@@ -168,6 +607,7 @@ where
             extra_params: Vec::new(),
             token_url: self.inner.token_uri(),
             tx_code: None,
+            legacy_user_pin: false,
             _phantom: PhantomData,
         }
     }
@@ -178,35 +618,128 @@ where
         profile_fields: C::CredentialRequest,
     ) -> credential::RequestBuilder<C::CredentialRequest> {
         let body = credential::Request::new(profile_fields);
-        credential::RequestBuilder::new(body, self.credential_endpoint().clone(), access_token)
+        credential::RequestBuilder::new(
+            body,
+            self.credential_endpoint().clone(),
+            access_token,
+            self.credential_response_encryption.clone(),
+        )
     }
 
+    /// Send several credential requests, at most `concurrency_limit` of them at a time.
+    ///
+    /// Meant for issuers that support neither a batch credential endpoint nor the `proofs`
+    /// parameter. The results are in the order of `requests`.
+    pub async fn request_credentials_parallel<'c, H>(
+        &self,
+        requests: Vec<credential::RequestBuilder<C::CredentialRequest>>,
+        concurrency_limit: usize,
+        http_client: &'c H,
+    ) -> Vec<
+        Result<
+            credential::Response<<C::CredentialRequest as CredentialRequestProfile>::Response>,
+            RequestError<<H as AsyncHttpClient<'c>>::Error>,
+        >,
+    >
+    where
+        H: AsyncHttpClient<'c>,
+        C::CredentialRequest: 'c,
+    {
+        stream::iter(
+            requests
+                .into_iter()
+                .map(|request| request.request_async(http_client)),
+        )
+        .buffered(concurrency_limit.max(1))
+        .collect()
+        .await
+    }
+
+    /// Build a credential request for each of the credential identifiers returned in the
+    /// `authorization_details` of the token response.
+    pub fn request_credentials_by_identifier(
+        &self,
+        token_response: &token::Response,
+    ) -> Result<Vec<credential::RequestBuilder<C::CredentialRequest>>, Error> {
+        token_response
+            .extra_fields()
+            .credential_identifiers()
+            .map(|credential_identifier| {
+                C::CredentialRequest::from_credential_identifier(credential_identifier.clone())
+                    .map(|profile_fields| {
+                        self.request_credential(
+                            token_response.access_token().clone(),
+                            profile_fields,
+                        )
+                    })
+                    .ok_or(Error::CredentialIdentifierUnsupported)
+            })
+            .collect()
+    }
+
+    /// Build a request for several credentials.
+    ///
+    /// Without a batch credential endpoint, falls back to a single credential request with a
+    /// `proofs` parameter if the issuer supports batch issuance and all the requests are for the
+    /// same credential.
     pub fn batch_request_credential(
         &self,
         access_token: AccessToken,
         profile_fields: Vec<C::CredentialRequest>,
     ) -> Result<credential::BatchRequestBuilder<C::CredentialRequest>, Error> {
+        if let Some(batch_credential_issuance) = &self.batch_credential_issuance {
+            let max = *batch_credential_issuance.batch_size();
+            if profile_fields.len() > max {
+                return Err(Error::BatchTooLarge {
+                    requested: profile_fields.len(),
+                    max,
+                });
+            }
+        }
         let Some(endpoint) = self.batch_credential_endpoint() else {
-            return Err(Error::BcrUnsupported);
+            let request = batch_request(profile_fields);
+            if self.batch_credential_issuance.is_none() || !request.all_same_request() {
+                return Err(Error::BcrUnsupported);
+            }
+            return Ok(credential::BatchRequestBuilder::new_with_proofs(
+                request,
+                self.credential_endpoint.clone(),
+                access_token,
+            ));
         };
-        let body = credential::BatchRequest::new(
-            profile_fields
-                .into_iter()
-                .map(credential::Request::new)
-                .collect(),
-        );
         Ok(credential::BatchRequestBuilder::new(
-            body,
+            batch_request(profile_fields),
             endpoint.clone(),
             access_token,
         ))
     }
 
+    /// Poll the deferred credential endpoint for the credential of a `transaction_id` returned by
+    /// a previous credential request.
+    pub fn poll_deferred_credential(
+        &self,
+        access_token: AccessToken,
+        transaction_id: String,
+        policy: credential::DeferredPollingPolicy,
+    ) -> Result<credential::DeferredRequestBuilder<C::CredentialResponse>, Error> {
+        let Some(endpoint) = self.deferred_credential_endpoint() else {
+            return Err(Error::DeferredUnsupported);
+        };
+        Ok(credential::DeferredRequestBuilder::new(
+            credential::DeferredRequest::new(transaction_id),
+            endpoint.clone(),
+            access_token,
+            policy,
+        ))
+    }
+
     fn new_inner_client(
         client_id: ClientId,
         redirect_uri: RedirectUrl,
         auth_url: Option<AuthUrl>,
         token_url: TokenUrl,
+        introspection_url: Option<IntrospectionUrl>,
+        revocation_url: Option<RevocationUrl>,
     ) -> oauth2::Client<
         BasicErrorResponse,
         token::Response,
@@ -215,13 +748,1118 @@ where
         BasicRevocationErrorResponse,
         EndpointMaybeSet,
         EndpointNotSet,
-        EndpointNotSet,
-        EndpointNotSet,
+        EndpointMaybeSet,
+        EndpointMaybeSet,
         EndpointSet,
     > {
         oauth2::Client::new(client_id)
             .set_redirect_uri(redirect_uri)
             .set_auth_uri_option(auth_url)
             .set_token_uri(token_url)
+            .set_introspection_url_option(introspection_url)
+            .set_revocation_url_option(revocation_url)
+    }
+}
+
+fn batch_request<CR: CredentialRequestProfile>(
+    profile_fields: Vec<CR>,
+) -> credential::BatchRequest<CR> {
+    credential::BatchRequest::new(
+        profile_fields
+            .into_iter()
+            .map(credential::Request::new)
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        cell::{Cell, RefCell},
+        collections::HashMap,
+        future::Future,
+        task::Poll,
+        time::Duration,
+    };
+
+    use oauth2::{
+        http::{self, header::CONTENT_TYPE},
+        ClientSecret, HttpRequest, HttpResponse, RefreshToken, RequestTokenError,
+        TokenIntrospectionResponse,
+    };
+    use serde_json::json;
+
+    use crate::{
+        credential_response_encryption::{
+            Alg, CredentialResponseEncryption, Enc, ResponseEncryptionError,
+        },
+        profiles::core::{
+            client::Client, metadata::CredentialIssuerMetadata,
+            profiles::CoreProfilesCredentialRequest,
+        },
+        proof_of_possession::Proof,
+        types::TxCode,
+    };
+
+    use super::*;
+
+    fn client() -> Client {
+        let issuer = IssuerUrl::new("https://server.example.com".into()).unwrap();
+        Client::from_issuer_metadata(
+            ClientId::new("s6BhdRkqt3".to_string()),
+            RedirectUrl::new("https://client.example.org/cb".into()).unwrap(),
+            CredentialIssuerMetadata::new(
+                issuer.clone(),
+                CredentialUrl::new("https://server.example.com/credential".into()).unwrap(),
+            ),
+            AuthorizationServerMetadata::new(
+                issuer,
+                TokenUrl::new("https://server.example.com/token".into()).unwrap(),
+            )
+            .set_authorization_endpoint(Some(
+                AuthUrl::new("https://server.example.com/authorize".into()).unwrap(),
+            )),
+        )
+    }
+
+    #[test]
+    fn proof_params() {
+        let client = client();
+        let params = client.proof_params(
+            Some(Nonce::new("tZignsnFbp".into())),
+            ProofOfPossessionController {
+                vm: None,
+                jwk: ssi::jwk::JWK::generate_p256(),
+            },
+        );
+        assert_eq!(params.audience.as_str(), client.issuer().url().as_str());
+        assert_ne!(
+            params.audience.as_str(),
+            client.credential_endpoint().as_str()
+        );
+        assert_eq!(params.issuer, "s6BhdRkqt3");
+        assert_eq!(params.nonce.unwrap().secret(), "tZignsnFbp");
+    }
+
+    #[test]
+    fn supports_grant() {
+        let client = client();
+        assert!(!client.requires_par());
+        assert!(client.supports_grant(&GrantType::AuthorizationCode));
+        assert!(!client.supports_grant(&GrantType::PreAuthorizedCode));
+
+        let client = client
+            .set_grant_types_supported(GrantTypesSupported(vec![GrantType::PreAuthorizedCode]));
+        assert!(!client.supports_grant(&GrantType::AuthorizationCode));
+        assert!(client.supports_grant(&GrantType::PreAuthorizedCode));
+    }
+
+    #[test]
+    fn from_issuer_metadata_notification_endpoint() {
+        assert!(client().notification_endpoint().is_none());
+
+        let issuer = IssuerUrl::new("https://server.example.com".into()).unwrap();
+        let client = Client::from_issuer_metadata(
+            ClientId::new("s6BhdRkqt3".to_string()),
+            RedirectUrl::new("https://client.example.org/cb".into()).unwrap(),
+            CredentialIssuerMetadata::new(
+                issuer.clone(),
+                CredentialUrl::new("https://server.example.com/credential".into()).unwrap(),
+            )
+            .set_notification_endpoint(Some(
+                NotificationUrl::new("https://server.example.com/notification".into()).unwrap(),
+            )),
+            AuthorizationServerMetadata::new(
+                issuer,
+                TokenUrl::new("https://server.example.com/token".into()).unwrap(),
+            ),
+        );
+        assert_eq!(
+            client.notification_endpoint().unwrap().as_str(),
+            "https://server.example.com/notification"
+        );
+    }
+
+    #[test]
+    fn introspect_token() {
+        assert!(matches!(
+            client().introspect_token(&AccessToken::new("token".into())),
+            Err(Error::IntrospectionUnsupported(_))
+        ));
+
+        let issuer = IssuerUrl::new("https://server.example.com".into()).unwrap();
+        let client = Client::from_issuer_metadata(
+            ClientId::new("s6BhdRkqt3".to_string()),
+            RedirectUrl::new("https://client.example.org/cb".into()).unwrap(),
+            CredentialIssuerMetadata::new(
+                issuer.clone(),
+                CredentialUrl::new("https://server.example.com/credential".into()).unwrap(),
+            ),
+            AuthorizationServerMetadata::new(
+                issuer,
+                TokenUrl::new("https://server.example.com/token".into()).unwrap(),
+            )
+            .set_introspection_endpoint(Some(
+                IntrospectionUrl::new("https://server.example.com/introspect".into()).unwrap(),
+            )),
+        );
+        assert_eq!(
+            client.introspection_url().unwrap().as_str(),
+            "https://server.example.com/introspect"
+        );
+
+        let token = AccessToken::new("token".into());
+        let response = client
+            .introspect_token(&token)
+            .unwrap()
+            .request(&|request: HttpRequest| {
+                assert_eq!(request.uri(), "https://server.example.com/introspect");
+                http::Response::builder()
+                    .status(200)
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(br#"{"active":true}"#.to_vec())
+            })
+            .unwrap();
+        assert!(response.active());
+    }
+
+    #[test]
+    fn revoke_token() {
+        assert!(matches!(
+            client().revoke_token(AccessToken::new("token".into()).into()),
+            Err(Error::RevocationUnsupported(_))
+        ));
+
+        let issuer = IssuerUrl::new("https://server.example.com".into()).unwrap();
+        let client = Client::from_issuer_metadata(
+            ClientId::new("s6BhdRkqt3".to_string()),
+            RedirectUrl::new("https://client.example.org/cb".into()).unwrap(),
+            CredentialIssuerMetadata::new(
+                issuer.clone(),
+                CredentialUrl::new("https://server.example.com/credential".into()).unwrap(),
+            ),
+            AuthorizationServerMetadata::new(
+                issuer,
+                TokenUrl::new("https://server.example.com/token".into()).unwrap(),
+            )
+            .set_revocation_endpoint(Some(
+                RevocationUrl::new("https://server.example.com/revoke".into()).unwrap(),
+            )),
+        );
+
+        client
+            .revoke_token(RefreshToken::new("refresh".into()).into())
+            .unwrap()
+            .request(&|request: HttpRequest| {
+                assert_eq!(request.uri(), "https://server.example.com/revoke");
+                let body: HashMap<String, String> = form_urlencoded::parse(request.body())
+                    .into_owned()
+                    .collect();
+                assert_eq!(body["token"], "refresh");
+                assert_eq!(body["token_type_hint"], "refresh_token");
+                http::Response::builder().status(200).body(vec![])
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn begin_authorization() {
+        let (url, pending_authorization) = client()
+            .begin_authorization(&[CredentialConfigurationId::new(
+                "UniversityDegreeCredential".into(),
+            )])
+            .unwrap();
+
+        assert_eq!(url.path(), "/authorize");
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        assert_eq!(params["response_type"], "code");
+        assert_eq!(params["client_id"], "s6BhdRkqt3");
+        assert_eq!(params["code_challenge_method"], "S256");
+        assert!(params.contains_key("code_challenge"));
+        assert_eq!(&params["state"], pending_authorization.state().secret());
+        assert_json_diff::assert_json_eq!(
+            serde_json::from_str::<serde_json::Value>(&params["authorization_details"]).unwrap(),
+            json!([{
+                "type": "openid_credential",
+                "credential_configuration_id": "UniversityDegreeCredential"
+            }])
+        );
+    }
+
+    #[test]
+    fn begin_authorization_unsupported_pkce_method() {
+        let issuer = IssuerUrl::new("https://server.example.com".into()).unwrap();
+        let client = Client::from_issuer_metadata(
+            ClientId::new("s6BhdRkqt3".to_string()),
+            RedirectUrl::new("https://client.example.org/cb".into()).unwrap(),
+            CredentialIssuerMetadata::new(
+                issuer.clone(),
+                CredentialUrl::new("https://server.example.com/credential".into()).unwrap(),
+            ),
+            AuthorizationServerMetadata::new(
+                issuer,
+                TokenUrl::new("https://server.example.com/token".into()).unwrap(),
+            )
+            .set_authorization_endpoint(Some(
+                AuthUrl::new("https://server.example.com/authorize".into()).unwrap(),
+            ))
+            .set_code_challenge_methods_supported(Some(vec![PkceCodeChallengeMethod::new(
+                "plain".into(),
+            )])),
+        );
+
+        let err = client
+            .begin_authorization(&[CredentialConfigurationId::new(
+                "UniversityDegreeCredential".into(),
+            )])
+            .unwrap_err();
+        assert!(matches!(err, Error::PkceUnsupported(_)));
+    }
+
+    #[test]
+    fn complete_authorization_state_mismatch() {
+        let client = client();
+        let (_, pending_authorization) = client
+            .begin_authorization(&[CredentialConfigurationId::new(
+                "UniversityDegreeCredential".into(),
+            )])
+            .unwrap();
+
+        let err = client
+            .complete_authorization(
+                pending_authorization,
+                AuthorizationCode::new("SplxlOBeZQQYbYS6WxSbIA".into()),
+                &CsrfToken::new("xyz".into()),
+            )
+            .unwrap_err();
+        assert!(matches!(err, Error::StateMismatch));
+    }
+
+    fn credential_offer() -> CredentialOfferParameters {
+        serde_json::from_value(json!({
+            "credential_issuer": "https://server.example.com",
+            "credential_configuration_ids": ["UniversityDegreeCredential"],
+            "grants": {
+                "authorization_code": {
+                    "issuer_state": "eyJhbGciOiJSU0Et...FYUaBy"
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn begin_authorization_for_offer() {
+        let client = client();
+        let credential_offer = credential_offer();
+        let (request, pending_authorization) = client
+            .begin_authorization_for_offer(&credential_offer)
+            .unwrap();
+        assert!(matches!(request, OfferAuthorizationRequest::Redirect(_)));
+
+        let http_client = |_: HttpRequest| -> Result<HttpResponse, std::io::Error> {
+            panic!("unexpected request")
+        };
+        let url = request.request(&http_client).unwrap();
+        assert_eq!(url.path(), "/authorize");
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        assert_eq!(params["issuer_state"], "eyJhbGciOiJSU0Et...FYUaBy");
+        assert_eq!(&params["state"], pending_authorization.state().secret());
+    }
+
+    #[test]
+    fn begin_authorization_for_offer_required_par() {
+        let issuer = IssuerUrl::new("https://server.example.com".into()).unwrap();
+        let authorization_server_metadata = AuthorizationServerMetadata::new(
+            issuer.clone(),
+            TokenUrl::new("https://server.example.com/token".into()).unwrap(),
+        )
+        .set_authorization_endpoint(Some(
+            AuthUrl::new("https://server.example.com/authorize".into()).unwrap(),
+        ))
+        .set_require_pushed_authorization_requests(true);
+
+        let client = Client::from_issuer_metadata(
+            ClientId::new("s6BhdRkqt3".to_string()),
+            RedirectUrl::new("https://client.example.org/cb".into()).unwrap(),
+            CredentialIssuerMetadata::new(
+                issuer.clone(),
+                CredentialUrl::new("https://server.example.com/credential".into()).unwrap(),
+            ),
+            authorization_server_metadata.clone(),
+        );
+        assert!(client.requires_par());
+        let credential_offer = credential_offer();
+        assert!(matches!(
+            client.begin_authorization_for_offer(&credential_offer),
+            Err(Error::ParEndpointMissing)
+        ));
+
+        let client = Client::from_issuer_metadata(
+            ClientId::new("s6BhdRkqt3".to_string()),
+            RedirectUrl::new("https://client.example.org/cb".into()).unwrap(),
+            CredentialIssuerMetadata::new(
+                issuer,
+                CredentialUrl::new("https://server.example.com/credential".into()).unwrap(),
+            ),
+            authorization_server_metadata.set_pushed_authorization_request_endpoint(Some(
+                ParUrl::new("https://server.example.com/as/par".into()).unwrap(),
+            )),
+        );
+        let (request, pending_authorization) = client
+            .begin_authorization_for_offer(&credential_offer)
+            .unwrap();
+        assert!(matches!(request, OfferAuthorizationRequest::Pushed(_)));
+
+        let state = pending_authorization.state().secret().clone();
+        let http_client = move |request: HttpRequest| -> Result<HttpResponse, std::io::Error> {
+            assert_eq!(request.uri(), "https://server.example.com/as/par");
+            let params: HashMap<String, String> =
+                serde_urlencoded::from_bytes(request.body()).unwrap();
+            assert_eq!(params["issuer_state"], "eyJhbGciOiJSU0Et...FYUaBy");
+            assert_eq!(params["state"], state);
+            Ok(http::Response::builder()
+                .status(200)
+                .header(CONTENT_TYPE, "application/json")
+                .body(
+                    serde_json::to_vec(&json!({
+                        "request_uri": "urn:ietf:params:oauth:request_uri:6esc_11ACC5bwc014ltc14eY22c",
+                        "expires_in": 60
+                    }))
+                    .unwrap(),
+                )
+                .unwrap())
+        };
+        let url = request.request(&http_client).unwrap();
+        assert_eq!(url.path(), "/authorize");
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        assert_eq!(
+            params["request_uri"],
+            "urn:ietf:params:oauth:request_uri:6esc_11ACC5bwc014ltc14eY22c"
+        );
+        assert_eq!(params["client_id"], "s6BhdRkqt3");
+    }
+
+    #[test]
+    fn poll_deferred_credential() {
+        let client = client();
+        assert!(matches!(
+            client.poll_deferred_credential(
+                AccessToken::new("czZCaGRSa3F0MzpnWDFmQmF0M2JW".into()),
+                "8xLOxBtZp8".into(),
+                credential::DeferredPollingPolicy::new(3, Duration::ZERO),
+            ),
+            Err(Error::DeferredUnsupported)
+        ));
+
+        let client = client.set_deferred_credential_endpoint(Some(
+            DeferredCredentialUrl::new("https://server.example.com/deferred_credential".into())
+                .unwrap(),
+        ));
+        let polls = Cell::new(0);
+        let http_client = |request: HttpRequest| -> Result<HttpResponse, std::io::Error> {
+            assert_eq!(
+                request.uri(),
+                "https://server.example.com/deferred_credential"
+            );
+            let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+            assert_json_diff::assert_json_eq!(body, json!({"transaction_id": "8xLOxBtZp8"}));
+
+            polls.set(polls.get() + 1);
+            if polls.get() < 3 {
+                return Ok(http::Response::builder().status(202).body(vec![]).unwrap());
+            }
+            Ok(http::Response::builder()
+                .status(200)
+                .header(CONTENT_TYPE, "application/json")
+                .body(
+                    serde_json::to_vec(&json!({
+                        "credential": "LUpixVCWJk0eOt4CXQe1NXK....WZwmhmn9OQp6YxX0a2L",
+                    }))
+                    .unwrap(),
+                )
+                .unwrap())
+        };
+
+        let response = client
+            .poll_deferred_credential(
+                AccessToken::new("czZCaGRSa3F0MzpnWDFmQmF0M2JW".into()),
+                "8xLOxBtZp8".into(),
+                credential::DeferredPollingPolicy::new(3, Duration::ZERO),
+            )
+            .unwrap()
+            .request(&http_client)
+            .unwrap();
+        assert!(matches!(
+            response.response_kind(),
+            credential::ResponseEnum::Immediate { .. }
+        ));
+        assert_eq!(polls.get(), 3);
+
+        polls.set(0);
+        let err = client
+            .poll_deferred_credential(
+                AccessToken::new("czZCaGRSa3F0MzpnWDFmQmF0M2JW".into()),
+                "8xLOxBtZp8".into(),
+                credential::DeferredPollingPolicy::new(2, Duration::ZERO),
+            )
+            .unwrap()
+            .request(&http_client)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RequestError::DeferredTimeout { attempts: 2, ref transaction_id }
+                if transaction_id == "8xLOxBtZp8"
+        ));
+        assert_eq!(polls.get(), 2);
+    }
+
+    #[test]
+    fn configurations_by_format_and_scope() {
+        let client = client().set_credential_configurations_supported(
+            serde_json::from_value::<Vec<_>>(json!([
+                {
+                    "$key$": "UniversityDegreeCredential",
+                    "format": "jwt_vc_json",
+                    "scope": "UniversityDegree",
+                    "credential_definition": {
+                        "type": ["VerifiableCredential", "UniversityDegreeCredential"]
+                    }
+                },
+                {
+                    "$key$": "org.iso.18013.5.1.mDL",
+                    "format": "mso_mdoc",
+                    "scope": "mDL",
+                    "doctype": "org.iso.18013.5.1.mDL"
+                },
+                {
+                    "$key$": "eu.europa.ec.eudi.pid.1",
+                    "format": "mso_mdoc",
+                    "doctype": "eu.europa.ec.eudi.pid.1"
+                }
+            ]))
+            .unwrap(),
+        );
+        let mdoc: Vec<_> = client
+            .configurations_by_format("mso_mdoc")
+            .map(|configuration| configuration.id().as_str())
+            .collect();
+        assert_eq!(
+            mdoc,
+            vec!["org.iso.18013.5.1.mDL", "eu.europa.ec.eudi.pid.1"]
+        );
+        assert_eq!(client.configurations_by_format("ldp_vc").count(), 0);
+
+        let university_degree: Vec<_> = client
+            .configurations_by_scope(&Scope::new("UniversityDegree".into()))
+            .map(|configuration| configuration.id().as_str())
+            .collect();
+        assert_eq!(university_degree, vec!["UniversityDegreeCredential"]);
+        assert_eq!(
+            client
+                .configurations_by_scope(&Scope::new("openid".into()))
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn pre_authorized_code_user_pin_legacy() {
+        let client = client();
+        let body = RefCell::new(Vec::new());
+        let http_client = |request: HttpRequest| -> Result<HttpResponse, std::io::Error> {
+            *body.borrow_mut() = request.body().clone();
+            Ok(http::Response::builder()
+                .status(200)
+                .header(CONTENT_TYPE, "application/json")
+                .body(
+                    serde_json::to_vec(&json!({
+                        "access_token": "eyJhbGciOiJSUzI1NiIsInR5cCI6Ikp..sHQ",
+                        "token_type": "bearer"
+                    }))
+                    .unwrap(),
+                )
+                .unwrap())
+        };
+        let params = || {
+            form_urlencoded::parse(&body.borrow())
+                .into_owned()
+                .collect::<HashMap<_, _>>()
+        };
+        let pin = TxCode::new("493536".into());
+
+        client
+            .exchange_pre_authorized_code(PreAuthorizedCode::new("SplxlOBeZQQYbYS6WxSbIA".into()))
+            .set_user_pin_legacy(&pin)
+            .request(&http_client)
+            .unwrap();
+        let legacy_params = params();
+        assert_eq!(legacy_params.get("user_pin").unwrap(), "493536");
+        assert!(!legacy_params.contains_key("tx_code"));
+
+        client
+            .exchange_pre_authorized_code(PreAuthorizedCode::new("SplxlOBeZQQYbYS6WxSbIA".into()))
+            .set_tx_code(&pin)
+            .request(&http_client)
+            .unwrap();
+        let params = params();
+        assert_eq!(params.get("tx_code").unwrap(), "493536");
+        assert!(!params.contains_key("user_pin"));
+    }
+
+    #[test]
+    fn pre_authorized_code_basic_auth() {
+        let client = client().set_auth_type(AuthType::BasicAuth);
+        let client_secret = ClientSecret::new("7Fjfp0ZBr1KtDRbnfVdmIw".into());
+
+        let request = client
+            .exchange_pre_authorized_code(PreAuthorizedCode::new("SplxlOBeZQQYbYS6WxSbIA".into()))
+            .set_client_secret(&client_secret)
+            .prepare_request::<std::io::Error>()
+            .unwrap();
+
+        assert_eq!(
+            request.headers().get(http::header::AUTHORIZATION).unwrap(),
+            "Basic czZCaGRSa3F0Mzo3RmpmcDBaQnIxS3REUmJuZlZkbUl3"
+        );
+        let params = form_urlencoded::parse(request.body())
+            .into_owned()
+            .collect::<HashMap<_, _>>();
+        assert!(!params.contains_key("client_id"));
+        assert!(!params.contains_key("client_secret"));
+    }
+
+    #[test]
+    fn pre_authorized_code_polling() {
+        let client = client();
+        let polls = Cell::new(0);
+        let slow_down = Cell::new(true);
+        let http_client = |request: HttpRequest| -> Result<HttpResponse, std::io::Error> {
+            assert_eq!(request.uri(), "https://server.example.com/token");
+            polls.set(polls.get() + 1);
+            let (status, body) = match (polls.get(), slow_down.get()) {
+                (1, _) => (400, json!({"error": "authorization_pending"})),
+                (2, true) => (400, json!({"error": "slow_down"})),
+                _ => (
+                    200,
+                    json!({
+                        "access_token": "eyJhbGciOiJSUzI1NiIsInR5cCI6Ikp..sHQ",
+                        "token_type": "bearer",
+                        "expires_in": 86400
+                    }),
+                ),
+            };
+            Ok(http::Response::builder()
+                .status(status)
+                .header(CONTENT_TYPE, "application/json")
+                .body(serde_json::to_vec(&body).unwrap())
+                .unwrap())
+        };
+
+        // `slow_down` increases the interval beyond the timeout.
+        let err = client
+            .exchange_pre_authorized_code(PreAuthorizedCode::new("SplxlOBeZQQYbYS6WxSbIA".into()))
+            .request_polling(&http_client, Duration::ZERO, Duration::from_secs(1))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RequestTokenError::ServerResponse(ref error)
+                if error.error().as_ref() == "slow_down"
+        ));
+        assert_eq!(polls.get(), 2);
+
+        polls.set(0);
+        slow_down.set(false);
+        let response = client
+            .exchange_pre_authorized_code(PreAuthorizedCode::new("SplxlOBeZQQYbYS6WxSbIA".into()))
+            .request_polling(&http_client, Duration::ZERO, Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(
+            response.access_token().secret(),
+            "eyJhbGciOiJSUzI1NiIsInR5cCI6Ikp..sHQ"
+        );
+        assert_eq!(polls.get(), 2);
+    }
+
+    #[test]
+    fn poll_deferred_credential_updated_transaction_id() {
+        let client = client().set_deferred_credential_endpoint(Some(
+            DeferredCredentialUrl::new("https://server.example.com/deferred_credential".into())
+                .unwrap(),
+        ));
+        let transaction_ids = RefCell::new(Vec::new());
+        let http_client = |request: HttpRequest| -> Result<HttpResponse, std::io::Error> {
+            let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+            transaction_ids
+                .borrow_mut()
+                .push(body["transaction_id"].as_str().unwrap().to_owned());
+            let response = match transaction_ids.borrow().len() {
+                1 => http::Response::builder()
+                    .status(202)
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(serde_json::to_vec(&json!({"transaction_id": "9yMPyCuaq9"})).unwrap()),
+                2 => http::Response::builder()
+                    .status(400)
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(
+                        serde_json::to_vec(&json!({
+                            "error": "issuance_pending",
+                            "transaction_id": "0zNQzDvbr0"
+                        }))
+                        .unwrap(),
+                    ),
+                _ => http::Response::builder()
+                    .status(200)
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(
+                        serde_json::to_vec(&json!({
+                            "credential": "LUpixVCWJk0eOt4CXQe1NXK....WZwmhmn9OQp6YxX0a2L",
+                        }))
+                        .unwrap(),
+                    ),
+            };
+            Ok(response.unwrap())
+        };
+
+        client
+            .poll_deferred_credential(
+                AccessToken::new("czZCaGRSa3F0MzpnWDFmQmF0M2JW".into()),
+                "8xLOxBtZp8".into(),
+                credential::DeferredPollingPolicy::new(3, Duration::ZERO),
+            )
+            .unwrap()
+            .request(&http_client)
+            .unwrap();
+        assert_eq!(
+            *transaction_ids.borrow(),
+            ["8xLOxBtZp8", "9yMPyCuaq9", "0zNQzDvbr0"]
+        );
+    }
+
+    #[tokio::test]
+    async fn request_credentials_parallel() {
+        /// Resolves after being polled `polls` times, to stagger the responses without a timer.
+        fn delay(mut polls: usize) -> impl Future<Output = ()> {
+            futures::future::poll_fn(move |cx| {
+                if polls == 0 {
+                    return Poll::Ready(());
+                }
+                polls -= 1;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            })
+        }
+
+        let identifiers = [
+            "CivilEngineeringDegree-2023",
+            "ElectricalEngineeringDegree-2023",
+            "MechanicalEngineeringDegree-2023",
+        ];
+        // The first request is the slowest, so that responses complete out of order.
+        let delays = [30, 10, 0];
+
+        let client = client();
+        let completed = RefCell::new(Vec::new());
+        let http_client = |request: HttpRequest| {
+            let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+            let identifier = body["credential_identifier"].as_str().unwrap().to_owned();
+            let index = identifiers.iter().position(|i| *i == identifier).unwrap();
+            let completed = &completed;
+            async move {
+                delay(delays[index]).await;
+                completed.borrow_mut().push(identifier.clone());
+                http::Response::builder()
+                    .status(200)
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(serde_json::to_vec(&json!({ "credential": identifier })).unwrap())
+                    .map_err(std::io::Error::other)
+            }
+        };
+
+        let requests = identifiers
+            .iter()
+            .map(|identifier| {
+                client.request_credential(
+                    AccessToken::new("czZCaGRSa3F0MzpnWDFmQmF0M2JW".into()),
+                    CoreProfilesCredentialRequest::from_credential_identifier(
+                        CredentialConfigurationId::new(identifier.to_string()),
+                    )
+                    .unwrap(),
+                )
+            })
+            .collect();
+
+        let responses = client
+            .request_credentials_parallel(requests, 2, &http_client)
+            .await;
+        let credentials: Vec<serde_json::Value> = responses
+            .into_iter()
+            .map(|response| {
+                serde_json::to_value(response.unwrap().response_kind()).unwrap()["credential"]
+                    .clone()
+            })
+            .collect();
+        assert_eq!(
+            *completed.borrow(),
+            [identifiers[1], identifiers[2], identifiers[0]]
+        );
+        assert_eq!(credentials, identifiers.map(serde_json::Value::from));
+    }
+
+    #[test]
+    fn request_credential_encryption_required() {
+        let client = client().set_credential_response_encryption(Some(
+            CredentialResponseEncryptionMetadata::new(
+                vec![Alg::Other("ECDH-ES".into())],
+                vec![Enc::Other("A128GCM".into())],
+                true,
+            ),
+        ));
+        let profile_fields = CoreProfilesCredentialRequest::from_credential_identifier(
+            CredentialConfigurationId::new("UniversityDegreeCredential".into()),
+        )
+        .unwrap();
+        let http_client = |_: HttpRequest| -> Result<HttpResponse, std::io::Error> {
+            panic!("unexpected request")
+        };
+
+        let err = client
+            .request_credential(
+                AccessToken::new("czZCaGRSa3F0MzpnWDFmQmF0M2JW".into()),
+                profile_fields.clone(),
+            )
+            .request(&http_client)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RequestError::ResponseEncryption(ResponseEncryptionError::Required)
+        ));
+
+        let err = client
+            .request_credential(
+                AccessToken::new("czZCaGRSa3F0MzpnWDFmQmF0M2JW".into()),
+                profile_fields.clone(),
+            )
+            .set_credential_response_encryption(Some(CredentialResponseEncryption::new(
+                ssi::jwk::JWK::generate_p256().to_public(),
+                Alg::Other("RSA-OAEP-256".into()),
+                Enc::Other("A128GCM".into()),
+            )))
+            .request(&http_client)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RequestError::ResponseEncryption(ResponseEncryptionError::UnsupportedAlg(_))
+        ));
+
+        let mut jwk = ssi::jwk::JWK::generate_p256().to_public();
+        jwk.public_key_use = Some("sig".into());
+        let err = client
+            .request_credential(
+                AccessToken::new("czZCaGRSa3F0MzpnWDFmQmF0M2JW".into()),
+                profile_fields,
+            )
+            .set_credential_response_encryption(Some(CredentialResponseEncryption::new(
+                jwk,
+                Alg::Other("ECDH-ES".into()),
+                Enc::Other("A128GCM".into()),
+            )))
+            .request(&http_client)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RequestError::ResponseEncryption(ResponseEncryptionError::InvalidJwk(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn from_offer() {
+        let requests = RefCell::new(Vec::new());
+        let http_client = |request: HttpRequest| {
+            let uri = request.uri().to_string();
+            requests.borrow_mut().push(uri.clone());
+            let response = match uri.as_str() {
+                "https://credential-issuer.example.com/credential-offer/1" => Some(json!({
+                    "credential_issuer": "https://credential-issuer.example.com",
+                    "credential_configuration_ids": ["UniversityDegreeCredential"],
+                    "grants": {
+                        "urn:ietf:params:oauth:grant-type:pre-authorized_code": {
+                            "pre-authorized_code": "adhjhdjajkdkhjhdj",
+                            "authorization_server": "https://server2.example.com"
+                        }
+                    }
+                })),
+                "https://credential-issuer.example.com/.well-known/openid-credential-issuer" => {
+                    Some(json!({
+                        "credential_issuer": "https://credential-issuer.example.com",
+                        "credential_endpoint": "https://credential-issuer.example.com/credential",
+                        "authorization_servers": [
+                            "https://server1.example.com",
+                            "https://server2.example.com"
+                        ],
+                        "credential_configurations_supported": {}
+                    }))
+                }
+                "https://server2.example.com/.well-known/oauth-authorization-server" => {
+                    Some(json!({
+                        "issuer": "https://server2.example.com",
+                        "token_endpoint": "https://server2.example.com/token",
+                        "grant_types_supported": ["urn:ietf:params:oauth:grant-type:pre-authorized_code"]
+                    }))
+                }
+                _ => None,
+            };
+            let response = match response {
+                Some(body) => http::Response::builder()
+                    .status(200)
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(serde_json::to_vec(&body).unwrap()),
+                None => http::Response::builder().status(404).body(vec![]),
+            };
+            async move { response.map_err(std::io::Error::other) }
+        };
+
+        let (client, credential_offer) = Client::from_offer(
+            ClientId::new("s6BhdRkqt3".to_string()),
+            RedirectUrl::new("https://client.example.org/cb".into()).unwrap(),
+            CredentialOffer::Reference {
+                credential_offer_uri: "https://credential-issuer.example.com/credential-offer/1"
+                    .parse()
+                    .unwrap(),
+            },
+            &http_client,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            client.issuer().as_str(),
+            "https://credential-issuer.example.com"
+        );
+        assert_eq!(
+            client.credential_endpoint().as_str(),
+            "https://credential-issuer.example.com/credential"
+        );
+        assert!(credential_offer.pre_authorized_code_grant().is_some());
+        assert!(
+            requests
+                .borrow()
+                .iter()
+                .any(|uri| uri
+                    == "https://server2.example.com/.well-known/oauth-authorization-server")
+        );
+        assert!(!requests
+            .borrow()
+            .iter()
+            .any(|uri| uri.starts_with("https://server1.example.com")));
+    }
+
+    #[tokio::test]
+    async fn from_issuer_metadata_auto() {
+        let requests = RefCell::new(Vec::new());
+        let http_client = |request: HttpRequest| {
+            let uri = request.uri().to_string();
+            requests.borrow_mut().push(uri.clone());
+            let response = match uri.as_str() {
+                "https://server1.example.com/.well-known/oauth-authorization-server" => {
+                    Some(json!({
+                        "issuer": "https://server1.example.com",
+                        "token_endpoint": "https://server1.example.com/token",
+                        "grant_types_supported": ["authorization_code"]
+                    }))
+                }
+                "https://server2.example.com/.well-known/oauth-authorization-server" => {
+                    Some(json!({
+                        "issuer": "https://server2.example.com",
+                        "token_endpoint": "https://server2.example.com/token",
+                        "grant_types_supported": ["urn:ietf:params:oauth:grant-type:pre-authorized_code"]
+                    }))
+                }
+                _ => None,
+            };
+            let response = match response {
+                Some(body) => http::Response::builder()
+                    .status(200)
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(serde_json::to_vec(&body).unwrap()),
+                None => http::Response::builder().status(404).body(vec![]),
+            };
+            async move { response.map_err(std::io::Error::other) }
+        };
+        let credential_issuer_metadata: CredentialIssuerMetadata = serde_json::from_value(json!({
+            "credential_issuer": "https://credential-issuer.example.com",
+            "credential_endpoint": "https://credential-issuer.example.com/credential",
+            "authorization_servers": [
+                "https://server1.example.com",
+                "https://server2.example.com"
+            ],
+            "credential_configurations_supported": {}
+        }))
+        .unwrap();
+
+        let client = Client::from_issuer_metadata_auto(
+            ClientId::new("s6BhdRkqt3".to_string()),
+            RedirectUrl::new("https://client.example.org/cb".into()).unwrap(),
+            credential_issuer_metadata.clone(),
+            GrantType::PreAuthorizedCode,
+            &http_client,
+        )
+        .await
+        .unwrap();
+        assert!(client.supports_grant(&GrantType::PreAuthorizedCode));
+        assert!(
+            requests
+                .borrow()
+                .iter()
+                .any(|uri| uri
+                    == "https://server2.example.com/.well-known/oauth-authorization-server")
+        );
+
+        let client = Client::from_issuer_metadata_auto(
+            ClientId::new("s6BhdRkqt3".to_string()),
+            RedirectUrl::new("https://client.example.org/cb".into()).unwrap(),
+            credential_issuer_metadata,
+            GrantType::AuthorizationCode,
+            &http_client,
+        )
+        .await
+        .unwrap();
+        assert!(client.supports_grant(&GrantType::AuthorizationCode));
+        assert!(!client.supports_grant(&GrantType::PreAuthorizedCode));
+    }
+
+    #[test]
+    fn batch_request_credential_too_large() {
+        let client = client()
+            .set_batch_credential_endpoint(Some(
+                BatchCredentialUrl::new("https://server.example.com/batch_credential".into())
+                    .unwrap(),
+            ))
+            .set_batch_credential_issuance(Some(BatchCredentialIssuance::new(2)));
+        let profile_fields = CoreProfilesCredentialRequest::from_credential_identifier(
+            CredentialConfigurationId::new("UniversityDegreeCredential".into()),
+        )
+        .unwrap();
+
+        let err = client
+            .batch_request_credential(
+                AccessToken::new("czZCaGRSa3F0MzpnWDFmQmF0M2JW".into()),
+                vec![profile_fields.clone(); 3],
+            )
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            Error::BatchTooLarge {
+                requested: 3,
+                max: 2
+            }
+        ));
+
+        assert!(client
+            .batch_request_credential(
+                AccessToken::new("czZCaGRSa3F0MzpnWDFmQmF0M2JW".into()),
+                vec![profile_fields; 2],
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn batch_request_credential_with_proofs() {
+        let credential_issuer_metadata: CredentialIssuerMetadata = serde_json::from_value(json!({
+            "credential_issuer": "https://server.example.com",
+            "credential_endpoint": "https://server.example.com/credential",
+            "batch_credential_issuance": {
+                "batch_size": 2
+            },
+            "credential_configurations_supported": {}
+        }))
+        .unwrap();
+        let client = Client::from_issuer_metadata(
+            ClientId::new("s6BhdRkqt3".to_string()),
+            RedirectUrl::new("https://client.example.org/cb".into()).unwrap(),
+            credential_issuer_metadata,
+            AuthorizationServerMetadata::new(
+                IssuerUrl::new("https://server.example.com".into()).unwrap(),
+                TokenUrl::new("https://server.example.com/token".into()).unwrap(),
+            ),
+        );
+        let profile_fields = CoreProfilesCredentialRequest::from_credential_identifier(
+            CredentialConfigurationId::new("UniversityDegreeCredential".into()),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            client.batch_request_credential(
+                AccessToken::new("czZCaGRSa3F0MzpnWDFmQmF0M2JW".into()),
+                vec![profile_fields.clone(); 3],
+            ),
+            Err(Error::BatchTooLarge {
+                requested: 3,
+                max: 2
+            })
+        ));
+
+        let http_client = |request: HttpRequest| -> Result<HttpResponse, std::io::Error> {
+            assert_eq!(request.uri(), "https://server.example.com/credential");
+            let body: serde_json::Value = serde_json::from_slice(request.body()).unwrap();
+            assert_json_diff::assert_json_eq!(
+                body,
+                json!({
+                    "credential_identifier": "UniversityDegreeCredential",
+                    "proofs": {
+                        "jwt": ["eyJhbGciOiJFUzI1NiJ9.e30.c2ln1", "eyJhbGciOiJFUzI1NiJ9.e30.c2ln2"]
+                    }
+                })
+            );
+
+            Ok(http::Response::builder()
+                .status(200)
+                .header(CONTENT_TYPE, "application/json")
+                .body(
+                    serde_json::to_vec(&json!({
+                        "credentials": [
+                            "eyJhbGciOiJFUzI1NiJ9.e30.c2lnMQ",
+                            "eyJhbGciOiJFUzI1NiJ9.e30.c2lnMg"
+                        ],
+                        "c_nonce": "fGFF7UkhLa",
+                        "notification_id": "3fwe98js"
+                    }))
+                    .unwrap(),
+                )
+                .unwrap())
+        };
+
+        let unproven = client
+            .batch_request_credential(
+                AccessToken::new("czZCaGRSa3F0MzpnWDFmQmF0M2JW".into()),
+                vec![profile_fields.clone(); 2],
+            )
+            .unwrap()
+            .request(&http_client);
+        assert!(matches!(unproven, Err(RequestError::Other(_))));
+
+        let proof = |jwt: &str| Proof::Jwt { jwt: jwt.into() };
+        let response = client
+            .batch_request_credential(
+                AccessToken::new("czZCaGRSa3F0MzpnWDFmQmF0M2JW".into()),
+                vec![profile_fields; 2],
+            )
+            .unwrap()
+            .set_proofs::<std::io::Error>(vec![
+                proof("eyJhbGciOiJFUzI1NiJ9.e30.c2ln1"),
+                proof("eyJhbGciOiJFUzI1NiJ9.e30.c2ln2"),
+            ])
+            .unwrap()
+            .request(&http_client)
+            .unwrap();
+
+        assert_eq!(response.credential_responses().len(), 2);
+        assert!(response
+            .credential_responses()
+            .iter()
+            .all(|response| matches!(response, credential::ResponseEnum::Immediate { .. })));
+        assert_eq!(response.c_nonce().unwrap().secret(), "fGFF7UkhLa");
+        assert_eq!(response.notification_id().unwrap(), "3fwe98js");
     }
 }
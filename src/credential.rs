@@ -1,4 +1,4 @@
-use std::future::Future;
+use std::{fmt, future::Future, marker::PhantomData, time::Duration};
 
 use oauth2::{
     http::{
@@ -6,17 +6,24 @@ use oauth2::{
         header::{ACCEPT, CONTENT_TYPE},
         HeaderValue, Method, StatusCode,
     },
-    AccessToken, AsyncHttpClient, ErrorResponseType, HttpRequest, HttpResponse,
-    StandardErrorResponse, SyncHttpClient,
+    AccessToken, AsyncHttpClient, ErrorResponseType, HttpRequest, HttpResponse, SyncHttpClient,
 };
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use ssi::jwk::JWK;
 
 use crate::{
-    credential_response_encryption::CredentialResponseEncryption,
-    http_utils::{auth_bearer, content_type_has_essence, MIME_TYPE_JSON},
+    credential_offer::PreAuthorizedCodeGrant,
+    credential_response_encryption::{
+        Alg, CredentialResponseEncryption, CredentialResponseEncryptionMetadata, Enc,
+        ResponseEncryptionError,
+    },
+    http_utils::{auth_bearer, content_type_has_essence, no_store, MIME_TYPE_JSON, MIME_TYPE_JWT},
     profiles::{CredentialRequestProfile, CredentialResponseProfile},
-    proof_of_possession::Proof,
-    types::{BatchCredentialUrl, CredentialUrl, Nonce},
+    proof_of_possession::{Proof, Proofs},
+    types::{
+        BatchCredentialUrl, CredentialConfigurationId, CredentialUrl, DeferredCredentialUrl, Nonce,
+    },
 };
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -29,6 +36,8 @@ where
     #[serde(default, skip_serializing_if = "Option::is_none")]
     proof: Option<Proof>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    proofs: Option<Proofs>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     credential_response_encryption: Option<CredentialResponseEncryption>,
 }
 
@@ -40,6 +49,7 @@ where
         Self {
             additional_profile_fields,
             proof: None,
+            proofs: None,
             credential_response_encryption: None,
         }
     }
@@ -48,11 +58,53 @@ where
         pub self [self] ["credential request value"] {
             set_additional_profile_fields -> additional_profile_fields[CR],
             set_proof -> proof[Option<Proof>],
+            set_proofs -> proofs[Option<Proofs>],
             set_credential_response_encryption -> credential_response_encryption[Option<CredentialResponseEncryption>],
         }
     ];
+
+    /// Parse a credential request, rejecting members that are not understood by the profile.
+    ///
+    /// Regular deserialization silently ignores unknown members within a known format. This
+    /// checks every member of `value`, in one pass, against the members read by the request and
+    /// by its profile, see [`CredentialRequestProfile::known_fields`].
+    pub fn from_json_strict(value: &Value) -> Result<Self, StrictParseError> {
+        let request: Self =
+            serde_path_to_error::deserialize(value).map_err(StrictParseError::Parse)?;
+
+        let mut unknown_fields = Vec::new();
+        if let Value::Object(members) = value {
+            let profile_fields = request.additional_profile_fields.known_fields();
+            for (key, value) in members {
+                match key.as_str() {
+                    "proof" | "proofs" | "credential_response_encryption" => {}
+                    _ => profile_fields.collect_unknown_member(key, value, "", &mut unknown_fields),
+                }
+            }
+        }
+        if !unknown_fields.is_empty() {
+            return Err(StrictParseError::UnknownFields(unknown_fields));
+        }
+
+        Ok(request)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum StrictParseError {
+    #[error("Failed to parse credential request")]
+    Parse(#[source] serde_path_to_error::Error<serde_json::Error>),
+    #[error("Unknown fields in credential request: {}", .0.join(", "))]
+    UnknownFields(Vec<String>),
 }
 
+/// A credential request, sent with the `Cache-Control: no-store` and `Pragma: no-cache` headers.
+///
+/// Credential responses contain the issued credential and a fresh `c_nonce`, and must not be
+/// persisted by HTTP caches.
+///
+/// The response encryption parameters of the request are checked against the
+/// `credential_response_encryption` metadata of the credential issuer before the request is sent.
 pub struct RequestBuilder<CR>
 where
     CR: CredentialRequestProfile,
@@ -60,17 +112,24 @@ where
     body: Request<CR>,
     url: CredentialUrl,
     access_token: AccessToken,
+    response_encryption: Option<CredentialResponseEncryptionMetadata>,
 }
 
 impl<CR> RequestBuilder<CR>
 where
     CR: CredentialRequestProfile,
 {
-    pub(crate) fn new(body: Request<CR>, url: CredentialUrl, access_token: AccessToken) -> Self {
+    pub(crate) fn new(
+        body: Request<CR>,
+        url: CredentialUrl,
+        access_token: AccessToken,
+        response_encryption: Option<CredentialResponseEncryptionMetadata>,
+    ) -> Self {
         Self {
             body,
             url,
             access_token,
+            response_encryption,
         }
     }
 
@@ -89,6 +148,7 @@ where
     where
         C: SyncHttpClient,
     {
+        self.check_response_encryption()?;
         http_client
             .call(self.prepare_request().map_err(|err| {
                 RequestError::Other(format!("failed to prepare request: {err:?}"))
@@ -108,6 +168,7 @@ where
         C: AsyncHttpClient<'c>,
     {
         Box::pin(async move {
+            self.check_response_encryption()?;
             let http_response = http_client
                 .call(self.prepare_request().map_err(|err| {
                     RequestError::Other(format!("failed to prepare request: {err:?}"))
@@ -119,9 +180,37 @@ where
         })
     }
 
+    /// Requests credential response encryption with a freshly generated P-256 key.
+    ///
+    /// Only the public key is sent to the credential issuer. The private key is returned, and
+    /// must be kept to decrypt the credential response.
+    pub fn with_generated_response_encryption(self, alg: Alg, enc: Enc) -> (Self, JWK) {
+        let jwk = JWK::generate_p256();
+        let encryption = CredentialResponseEncryption::new(jwk.to_public(), alg, enc);
+        (
+            self.set_credential_response_encryption(Some(encryption)),
+            jwk,
+        )
+    }
+
+    fn check_response_encryption<RE>(&self) -> Result<(), RequestError<RE>>
+    where
+        RE: std::error::Error + 'static,
+    {
+        if let Some(encryption) = &self.body.credential_response_encryption {
+            encryption.check_jwk()?;
+        }
+        match &self.response_encryption {
+            Some(metadata) => {
+                Ok(metadata.check_request(self.body.credential_response_encryption.as_ref())?)
+            }
+            None => Ok(()),
+        }
+    }
+
     fn prepare_request(&self) -> Result<HttpRequest, RequestError<http::Error>> {
         let (auth_header, auth_value) = auth_bearer(&self.access_token);
-        http::Request::builder()
+        no_store(http::Request::builder())
             .uri(self.url.to_string())
             .method(Method::POST)
             .header(CONTENT_TYPE, HeaderValue::from_static(MIME_TYPE_JSON))
@@ -140,11 +229,7 @@ where
     {
         // TODO status 202 if deferred
         if http_response.status() != StatusCode::OK {
-            return Err(RequestError::Response(
-                http_response.status(),
-                http_response.body().to_owned(),
-                "unexpected HTTP status code".to_string(),
-            ));
+            return Err(error_response(&http_response));
         }
 
         match http_response
@@ -159,6 +244,23 @@ where
                 ))
                 .map_err(RequestError::Parse)
             }
+            // Some issuers return the bare credential, e.g. a JWT VC, instead of a JSON object.
+            ref content_type if content_type_has_essence(content_type, MIME_TYPE_JWT) => {
+                let jwt = std::str::from_utf8(http_response.body())
+                    .map(str::trim)
+                    .ok()
+                    .filter(|jwt| jwt.split('.').count() == 3);
+                let Some(jwt) = jwt.filter(|_| self.body.credential_response_encryption.is_none())
+                else {
+                    return Err(RequestError::Response(
+                        http_response.status(),
+                        http_response.body().to_owned(),
+                        "expected an unencrypted JWT credential".to_string(),
+                    ));
+                };
+                serde_path_to_error::deserialize(serde_json::json!({ "credential": jwt }))
+                    .map_err(RequestError::Parse)
+            }
             ref content_type => Err(RequestError::Response(
                 http_response.status(),
                 http_response.body().to_owned(),
@@ -168,15 +270,26 @@ where
     }
 }
 
+/// A batch credential request, see [`RequestBuilder`].
+///
+/// Issuers without a batch credential endpoint may still issue several credentials of the same
+/// configuration in a single credential request, one for each of its `proofs`. In that case the
+/// request is sent to the credential endpoint and the response is converted to a
+/// [`BatchResponse`].
 pub struct BatchRequestBuilder<CR>
 where
     CR: CredentialRequestProfile,
 {
     body: BatchRequest<CR>,
-    url: BatchCredentialUrl,
+    endpoint: BatchEndpoint,
     access_token: AccessToken,
 }
 
+enum BatchEndpoint {
+    Batch(BatchCredentialUrl),
+    Proofs(CredentialUrl),
+}
+
 impl<CR> BatchRequestBuilder<CR>
 where
     CR: CredentialRequestProfile,
@@ -188,7 +301,21 @@ where
     ) -> Self {
         Self {
             body,
-            url,
+            endpoint: BatchEndpoint::Batch(url),
+            access_token,
+        }
+    }
+
+    /// Batch request sent as a single credential request with a `proofs` parameter. All the
+    /// credential requests of `body` must be identical but for their proof.
+    pub(crate) fn new_with_proofs(
+        body: BatchRequest<CR>,
+        url: CredentialUrl,
+        access_token: AccessToken,
+    ) -> Self {
+        Self {
+            body,
+            endpoint: BatchEndpoint::Proofs(url),
             access_token,
         }
     }
@@ -262,16 +389,45 @@ where
 
     fn prepare_request(&self) -> Result<HttpRequest, RequestError<http::Error>> {
         let (auth_header, auth_value) = auth_bearer(&self.access_token);
-        http::Request::builder()
-            .uri(self.url.to_string())
+        let (url, body) = match &self.endpoint {
+            BatchEndpoint::Batch(url) => (url.to_string(), serde_json::to_vec(&self.body)),
+            BatchEndpoint::Proofs(url) => (
+                url.to_string(),
+                serde_json::to_vec(&self.proofs_request().map_err(RequestError::Other)?),
+            ),
+        };
+        no_store(http::Request::builder())
+            .uri(url)
             .method(Method::POST)
             .header(CONTENT_TYPE, HeaderValue::from_static(MIME_TYPE_JSON))
             .header(ACCEPT, HeaderValue::from_static(MIME_TYPE_JSON))
             .header(auth_header, auth_value)
-            .body(serde_json::to_vec(&self.body).map_err(|e| RequestError::Other(e.to_string()))?)
+            .body(body.map_err(|e| RequestError::Other(e.to_string()))?)
             .map_err(RequestError::Request)
     }
 
+    /// Merges the credential requests into a single request carrying all their proofs.
+    ///
+    /// Every credential request must have a proof, so that the issuer returns one credential
+    /// per request, and the requests must not differ in anything but their proof.
+    fn proofs_request(&self) -> Result<Request<CR>, String> {
+        let Some(request) = self.body.credential_requests.first() else {
+            return Err("empty batch credential request".to_string());
+        };
+        let proofs: Vec<Proof> = self
+            .body
+            .credential_requests
+            .iter()
+            .map(|request| request.proof.clone())
+            .collect::<Option<_>>()
+            .ok_or("every credential request of the batch must have a proof")?;
+        if !self.body.all_same_request() {
+            return Err("credential requests of the batch differ in more than their proof".into());
+        }
+        let proofs = Proofs::from_proofs(proofs).ok_or("proofs must all be of the same type")?;
+        Ok(request.clone().set_proof(None).set_proofs(Some(proofs)))
+    }
+
     fn credential_response<RE>(
         self,
         http_response: HttpResponse,
@@ -281,11 +437,7 @@ where
     {
         // TODO status 202 if deferred
         if http_response.status() != StatusCode::OK {
-            return Err(RequestError::Response(
-                http_response.status(),
-                http_response.body().to_owned(),
-                "unexpected HTTP status code".to_string(),
-            ));
+            return Err(error_response(&http_response));
         }
 
         match http_response
@@ -295,10 +447,17 @@ where
             .unwrap_or_else(|| HeaderValue::from_static(MIME_TYPE_JSON))
         {
             ref content_type if content_type_has_essence(content_type, MIME_TYPE_JSON) => {
-                serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_slice(
-                    http_response.body(),
-                ))
-                .map_err(RequestError::Parse)
+                let mut deserializer = serde_json::Deserializer::from_slice(http_response.body());
+                match self.endpoint {
+                    BatchEndpoint::Batch(_) => serde_path_to_error::deserialize(&mut deserializer)
+                        .map_err(RequestError::Parse),
+                    BatchEndpoint::Proofs(_) => serde_path_to_error::deserialize::<
+                        _,
+                        Response<CR::Response>,
+                    >(&mut deserializer)
+                    .map(BatchResponse::from)
+                    .map_err(RequestError::Parse),
+                }
             }
             ref content_type => Err(RequestError::Response(
                 http_response.status(),
@@ -309,6 +468,27 @@ where
     }
 }
 
+/// Parses the credential error response, if any, of an unsuccessful request.
+fn error_response<RE>(http_response: &HttpResponse) -> RequestError<RE>
+where
+    RE: std::error::Error + 'static,
+{
+    let is_json = http_response
+        .headers()
+        .get(CONTENT_TYPE)
+        .map_or(true, |content_type| {
+            content_type_has_essence(content_type, MIME_TYPE_JSON)
+        });
+    match serde_json::from_slice(http_response.body()) {
+        Ok(error) if is_json => RequestError::ErrorResponse(http_response.status(), error),
+        _ => RequestError::Response(
+            http_response.status(),
+            http_response.body().to_owned(),
+            "unexpected HTTP status code".to_string(),
+        ),
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 #[non_exhaustive]
 pub enum RequestError<RE>
@@ -321,11 +501,21 @@ where
     Request(#[source] RE),
     #[error("Server returned invalid response: {2}")]
     Response(StatusCode, Vec<u8>, String),
+    #[error("Server returned error response: {1}")]
+    ErrorResponse(StatusCode, Error),
     #[error("Other error: {0}")]
     Other(String),
+    #[error("Deferred credential {transaction_id} still pending after {attempts} attempts")]
+    DeferredTimeout {
+        attempts: usize,
+        /// Latest transaction identifier returned by the issuer, to resume polling with.
+        transaction_id: String,
+    },
+    #[error(transparent)]
+    ResponseEncryption(#[from] ResponseEncryptionError),
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Response<CR>
 where
     CR: CredentialResponseProfile,
@@ -336,6 +526,12 @@ where
     c_nonce: Option<Nonce>,
     #[serde(skip_serializing_if = "Option::is_none")]
     c_nonce_expires_in: Option<i64>,
+    /// Identifier of the issued credentials, to be sent to the notification endpoint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notification_id: Option<String>,
+    /// Credential identifier of the request, echoed by some credential issuers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    credential_identifier: Option<CredentialConfigurationId>,
 }
 
 impl<CR> Response<CR>
@@ -347,6 +543,8 @@ where
             response_kind,
             c_nonce: None,
             c_nonce_expires_in: None,
+            notification_id: None,
+            credential_identifier: None,
         }
     }
     field_getters_setters![
@@ -354,10 +552,26 @@ where
             set_response_kind -> response_kind[ResponseEnum<CR>],
             set_nonce -> c_nonce[Option<Nonce>],
             set_nonce_expiration -> c_nonce_expires_in[Option<i64>],
+            set_notification_id -> notification_id[Option<String>],
+            set_credential_identifier -> credential_identifier[Option<CredentialConfigurationId>],
         }
     ];
 }
 
+impl<CR> PartialEq for Response<CR>
+where
+    CR: CredentialResponseProfile,
+    CR::Type: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.response_kind == other.response_kind
+            && self.c_nonce == other.c_nonce
+            && self.c_nonce_expires_in == other.c_nonce_expires_in
+            && self.notification_id == other.notification_id
+            && self.credential_identifier == other.credential_identifier
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum ResponseEnum<CR>
@@ -365,19 +579,63 @@ where
     CR: CredentialResponseProfile,
 {
     #[serde(bound = "CR: CredentialResponseProfile")]
-    Immediate {
-        credential: CR::Type,
-    },
+    Immediate { credential: CR::Type },
     /// Support for multiple credentials of a specific type from the latest working draft versions.
     #[serde(bound = "CR: CredentialResponseProfile")]
-    ImmediateMany {
-        credentials: Vec<CR::Type>,
-    },
+    ImmediateMany { credentials: Vec<CR::Type> },
+    /// Non-exhaustive, so that other parameters of deferred responses can be added, see
+    /// [`ResponseEnum::deferred`].
+    #[non_exhaustive]
     Deferred {
+        #[serde(alias = "acceptance_token")]
         transaction_id: Option<String>,
+        /// Minimum number of seconds to wait between deferred credential requests, as sent by
+        /// some issuers.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        interval: Option<u64>,
     },
 }
 
+impl<CR> ResponseEnum<CR>
+where
+    CR: CredentialResponseProfile,
+{
+    /// A deferred response, with the `transaction_id` to use at the deferred credential endpoint
+    /// and an optional polling `interval`, in seconds.
+    pub fn deferred(transaction_id: Option<String>, interval: Option<u64>) -> Self {
+        Self::Deferred {
+            transaction_id,
+            interval,
+        }
+    }
+}
+
+impl<CR> PartialEq for ResponseEnum<CR>
+where
+    CR: CredentialResponseProfile,
+    CR::Type: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Immediate { credential: a }, Self::Immediate { credential: b }) => a == b,
+            (Self::ImmediateMany { credentials: a }, Self::ImmediateMany { credentials: b }) => {
+                a == b
+            }
+            (
+                Self::Deferred {
+                    transaction_id: a,
+                    interval: a_interval,
+                },
+                Self::Deferred {
+                    transaction_id: b,
+                    interval: b_interval,
+                },
+            ) => a == b && a_interval == b_interval,
+            _ => false,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ErrorType {
@@ -387,9 +645,83 @@ pub enum ErrorType {
     UnsupportedCredentialFormat,
     InvalidProof,
     InvalidEncryptionParameters,
+    IssuancePending,
+    InvalidTransactionId,
+    #[serde(untagged)]
+    Extension(String),
 }
 impl ErrorResponseType for ErrorType {}
-pub type Error = StandardErrorResponse<ErrorType>;
+
+impl fmt::Display for ErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let error = match self {
+            Self::InvalidToken => "invalid_token",
+            Self::InvalidCredentialRequest => "invalid_credential_request",
+            Self::UnsupportedCredentialType => "unsupported_credential_type",
+            Self::UnsupportedCredentialFormat => "unsupported_credential_format",
+            Self::InvalidProof => "invalid_proof",
+            Self::InvalidEncryptionParameters => "invalid_encryption_parameters",
+            Self::IssuancePending => "issuance_pending",
+            Self::InvalidTransactionId => "invalid_transaction_id",
+            Self::Extension(error) => error,
+        };
+        f.write_str(error)
+    }
+}
+
+/// A credential error response.
+///
+/// Besides the standard OAuth 2.0 error fields, the credential issuer may provide a fresh
+/// `c_nonce`, e.g. with an `invalid_proof` error, to be used when retrying the request.
+///
+/// See <https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0-13.html#section-7.3.1.2>.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Error {
+    error: ErrorType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error_description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error_uri: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    c_nonce: Option<Nonce>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    c_nonce_expires_in: Option<i64>,
+}
+
+impl Error {
+    pub fn new(error: ErrorType) -> Self {
+        Self {
+            error,
+            error_description: None,
+            error_uri: None,
+            c_nonce: None,
+            c_nonce_expires_in: None,
+        }
+    }
+
+    field_getters_setters![
+        pub self [self] ["credential error response value"] {
+            set_error -> error[ErrorType],
+            set_error_description -> error_description[Option<String>],
+            set_error_uri -> error_uri[Option<String>],
+            set_nonce -> c_nonce[Option<Nonce>],
+            set_nonce_expiration -> c_nonce_expires_in[Option<i64>],
+        }
+    ];
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+        if let Some(error_description) = &self.error_description {
+            write!(f, ": {error_description}")?;
+        }
+        if let Some(error_uri) = &self.error_uri {
+            write!(f, " (see {error_uri})")?;
+        }
+        Ok(())
+    }
+}
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct BatchRequest<CR>
@@ -409,6 +741,18 @@ where
             credential_requests,
         }
     }
+
+    /// Whether the credential requests are all identical but for their proof.
+    pub(crate) fn all_same_request(&self) -> bool {
+        let mut requests = self
+            .credential_requests
+            .iter()
+            .map(|request| serde_json::to_value(request.clone().set_proof(None)));
+        let Some(Ok(first)) = requests.next() else {
+            return false;
+        };
+        requests.all(|request| request.is_ok_and(|request| request == first))
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -422,6 +766,10 @@ where
     c_nonce: Option<Nonce>,
     #[serde(skip_serializing_if = "Option::is_none")]
     c_nonce_expires_in: Option<i64>,
+    /// Identifier of the issued credentials, to be sent to the notification endpoint, when the
+    /// batch was issued by the credential endpoint with `proofs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notification_id: Option<String>,
 }
 
 impl<CR> BatchResponse<CR>
@@ -433,6 +781,7 @@ where
             credential_responses,
             c_nonce: None,
             c_nonce_expires_in: None,
+            notification_id: None,
         }
     }
     field_getters_setters![
@@ -440,23 +789,413 @@ where
             set_credential_responses -> credential_responses[Vec<ResponseEnum<CR>>],
             set_nonce -> c_nonce[Option<Nonce>],
             set_nonce_expiration -> c_nonce_expires_in[Option<i64>],
+            set_notification_id -> notification_id[Option<String>],
         }
     ];
 }
 
+/// Splits the credentials issued for a request with several `proofs`.
+impl<CR> From<Response<CR>> for BatchResponse<CR>
+where
+    CR: CredentialResponseProfile,
+{
+    fn from(response: Response<CR>) -> Self {
+        let credential_responses = match response.response_kind {
+            ResponseEnum::ImmediateMany { credentials } => credentials
+                .into_iter()
+                .map(|credential| ResponseEnum::Immediate { credential })
+                .collect(),
+            response_kind => vec![response_kind],
+        };
+        Self {
+            credential_responses,
+            c_nonce: response.c_nonce,
+            c_nonce_expires_in: response.c_nonce_expires_in,
+            notification_id: response.notification_id,
+        }
+    }
+}
+
+impl<CR> PartialEq for BatchResponse<CR>
+where
+    CR: CredentialResponseProfile,
+    CR::Type: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.credential_responses == other.credential_responses
+            && self.c_nonce == other.c_nonce
+            && self.c_nonce_expires_in == other.c_nonce_expires_in
+            && self.notification_id == other.notification_id
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct DeferredRequest {
+    #[serde(alias = "acceptance_token")]
     transaction_id: String,
 }
 
+impl DeferredRequest {
+    pub fn new(transaction_id: String) -> Self {
+        Self { transaction_id }
+    }
+
+    field_getters_setters![
+        pub self [self] ["deferred credential request value"] {
+            set_transaction_id -> transaction_id[String],
+        }
+    ];
+}
+
+/// Response of the deferred credential endpoint.
+#[derive(Debug)]
+pub enum DeferredResponse<CR>
+where
+    CR: CredentialResponseProfile,
+{
+    Ready(Response<CR>),
+    /// The credential is not issued yet, signaled with a `202 Accepted` status or an
+    /// `issuance_pending` error.
+    ///
+    /// The issuer may return a new `transaction_id`, which must be used for the next request.
+    ///
+    /// The issuer may also return a new polling `interval`, in seconds.
+    #[non_exhaustive]
+    Pending {
+        transaction_id: Option<String>,
+        interval: Option<u64>,
+    },
+}
+
+/// Body of a pending deferred credential response, either a `202 Accepted` response or an
+/// `issuance_pending` error.
+#[derive(Default, Deserialize)]
+struct DeferredPending {
+    #[serde(default, alias = "acceptance_token")]
+    transaction_id: Option<String>,
+    #[serde(default)]
+    interval: Option<u64>,
+}
+
+/// How often, and how many times, the deferred credential endpoint is polled, see
+/// [`DeferredRequestBuilder`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeferredPollingPolicy {
+    max_attempts: usize,
+    interval: Duration,
+}
+
+impl DeferredPollingPolicy {
+    /// Interval used when the credential offer does not specify one.
+    pub const DEFAULT_INTERVAL: Duration = PreAuthorizedCodeGrant::DEFAULT_INTERVAL;
+
+    /// Longest interval accepted from the issuer, so that a misbehaving issuer cannot stall
+    /// polling.
+    pub const MAX_INTERVAL: Duration = Duration::from_secs(300);
+
+    pub fn new(max_attempts: usize, interval: Duration) -> Self {
+        Self {
+            max_attempts,
+            interval,
+        }
+    }
+
+    /// Polls at the `interval` of the pre-authorized code grant of the credential offer.
+    pub fn from_grant(grant: &PreAuthorizedCodeGrant, max_attempts: usize) -> Self {
+        Self::new(max_attempts, grant.polling_interval())
+    }
+
+    /// Polls at the `interval` of a deferred credential response, if any, capped at
+    /// [`Self::MAX_INTERVAL`], or at the default interval.
+    pub fn from_response<CR>(response: &ResponseEnum<CR>, max_attempts: usize) -> Self
+    where
+        CR: CredentialResponseProfile,
+    {
+        let interval = match response {
+            ResponseEnum::Deferred {
+                interval: Some(interval),
+                ..
+            } => Duration::from_secs(*interval).min(Self::MAX_INTERVAL),
+            _ => Self::DEFAULT_INTERVAL,
+        };
+        Self::new(max_attempts, interval)
+    }
+
+    field_getters_setters![
+        pub self [self] ["deferred polling policy value"] {
+            set_max_attempts -> max_attempts[usize],
+            set_interval -> interval[Duration],
+        }
+    ];
+}
+
+/// A deferred credential request, sent repeatedly until the credential is issued, the endpoint
+/// returns an error or the attempts of the [`DeferredPollingPolicy`] are exhausted.
+pub struct DeferredRequestBuilder<CR>
+where
+    CR: CredentialResponseProfile,
+{
+    body: DeferredRequest,
+    url: DeferredCredentialUrl,
+    access_token: AccessToken,
+    policy: DeferredPollingPolicy,
+    /// Current polling interval, which the issuer may raise above the one of the policy.
+    interval: Duration,
+    _phantom: PhantomData<CR>,
+}
+
+impl<CR> DeferredRequestBuilder<CR>
+where
+    CR: CredentialResponseProfile,
+{
+    pub(crate) fn new(
+        body: DeferredRequest,
+        url: DeferredCredentialUrl,
+        access_token: AccessToken,
+        policy: DeferredPollingPolicy,
+    ) -> Self {
+        Self {
+            body,
+            url,
+            access_token,
+            interval: policy.interval,
+            policy,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Polls the deferred credential endpoint, sleeping the current thread between attempts.
+    pub fn request<C>(
+        mut self,
+        http_client: &C,
+    ) -> Result<Response<CR>, RequestError<<C as SyncHttpClient>::Error>>
+    where
+        C: SyncHttpClient,
+    {
+        for attempt in 0..self.policy.max_attempts {
+            if attempt > 0 {
+                std::thread::sleep(self.interval);
+            }
+            let http_response = http_client
+                .call(self.prepare_request().map_err(|err| {
+                    RequestError::Other(format!("failed to prepare request: {err:?}"))
+                })?)
+                .map_err(RequestError::Request)?;
+            match self.deferred_response(http_response)? {
+                DeferredResponse::Ready(response) => return Ok(response),
+                DeferredResponse::Pending {
+                    transaction_id,
+                    interval,
+                } => self.update_pending(transaction_id, interval),
+            }
+        }
+        Err(RequestError::DeferredTimeout {
+            attempts: self.policy.max_attempts,
+            transaction_id: self.body.transaction_id,
+        })
+    }
+
+    /// Polls the deferred credential endpoint, awaiting `sleep` between attempts so that the
+    /// caller can use the timer of its runtime.
+    pub fn request_async<'c, C, S, F>(
+        mut self,
+        http_client: &'c C,
+        sleep: S,
+    ) -> impl Future<Output = Result<Response<CR>, RequestError<<C as AsyncHttpClient<'c>>::Error>>> + 'c
+    where
+        Self: 'c,
+        C: AsyncHttpClient<'c>,
+        S: Fn(Duration) -> F + 'c,
+        F: Future<Output = ()> + 'c,
+    {
+        Box::pin(async move {
+            for attempt in 0..self.policy.max_attempts {
+                if attempt > 0 {
+                    sleep(self.interval).await;
+                }
+                let http_response = http_client
+                    .call(self.prepare_request().map_err(|err| {
+                        RequestError::Other(format!("failed to prepare request: {err:?}"))
+                    })?)
+                    .await
+                    .map_err(RequestError::Request)?;
+                match self.deferred_response(http_response)? {
+                    DeferredResponse::Ready(response) => return Ok(response),
+                    DeferredResponse::Pending {
+                        transaction_id,
+                        interval,
+                    } => self.update_pending(transaction_id, interval),
+                }
+            }
+            Err(RequestError::DeferredTimeout {
+                attempts: self.policy.max_attempts,
+                transaction_id: self.body.transaction_id,
+            })
+        })
+    }
+
+    fn prepare_request(&self) -> Result<HttpRequest, RequestError<http::Error>> {
+        let (auth_header, auth_value) = auth_bearer(&self.access_token);
+        no_store(http::Request::builder())
+            .uri(self.url.to_string())
+            .method(Method::POST)
+            .header(CONTENT_TYPE, HeaderValue::from_static(MIME_TYPE_JSON))
+            .header(ACCEPT, HeaderValue::from_static(MIME_TYPE_JSON))
+            .header(auth_header, auth_value)
+            .body(serde_json::to_vec(&self.body).map_err(|e| RequestError::Other(e.to_string()))?)
+            .map_err(RequestError::Request)
+    }
+
+    fn update_pending(&mut self, transaction_id: Option<String>, interval: Option<u64>) {
+        if let Some(transaction_id) = transaction_id {
+            self.body.transaction_id = transaction_id;
+        }
+        // The issuer can slow polling down, up to a limit, but not speed it up.
+        if let Some(interval) = interval {
+            let max_interval = DeferredPollingPolicy::MAX_INTERVAL.max(self.policy.interval);
+            self.interval = Duration::from_secs(interval).clamp(self.policy.interval, max_interval);
+        }
+    }
+
+    fn deferred_response<RE>(
+        &self,
+        http_response: HttpResponse,
+    ) -> Result<DeferredResponse<CR>, RequestError<RE>>
+    where
+        RE: std::error::Error + 'static,
+    {
+        let pending = || {
+            let pending =
+                serde_json::from_slice::<DeferredPending>(http_response.body()).unwrap_or_default();
+            DeferredResponse::Pending {
+                transaction_id: pending.transaction_id,
+                interval: pending.interval,
+            }
+        };
+        match http_response.status() {
+            StatusCode::OK => {}
+            StatusCode::ACCEPTED => return Ok(pending()),
+            _ => {
+                return match error_response(&http_response) {
+                    RequestError::ErrorResponse(_, error)
+                        if error.error == ErrorType::IssuancePending =>
+                    {
+                        Ok(pending())
+                    }
+                    err => Err(err),
+                }
+            }
+        }
+
+        match http_response
+            .headers()
+            .get(CONTENT_TYPE)
+            .map(ToOwned::to_owned)
+            .unwrap_or_else(|| HeaderValue::from_static(MIME_TYPE_JSON))
+        {
+            ref content_type if content_type_has_essence(content_type, MIME_TYPE_JSON) => {
+                serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_slice(
+                    http_response.body(),
+                ))
+                .map(DeferredResponse::Ready)
+                .map_err(RequestError::Parse)
+            }
+            ref content_type => Err(RequestError::Response(
+                http_response.status(),
+                http_response.body().to_owned(),
+                format!("unexpected response Content-Type: `{:?}`", content_type),
+            )),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use serde_json::json;
 
-    use crate::profiles::core::profiles::CoreProfilesCredentialResponse;
+    use crate::profiles::{
+        core::profiles::{CoreProfilesCredentialRequest, CoreProfilesCredentialResponse},
+        CredentialRequestProfile,
+    };
 
     use super::*;
 
+    #[test]
+    fn with_generated_response_encryption() {
+        let metadata: CredentialResponseEncryptionMetadata = serde_json::from_value(json!({
+            "alg_values_supported": ["ECDH-ES"],
+            "enc_values_supported": ["A128GCM"],
+            "encryption_required": true
+        }))
+        .unwrap();
+        let builder = RequestBuilder::new(
+            Request::new(
+                CoreProfilesCredentialRequest::from_credential_identifier(
+                    CredentialConfigurationId::new("CivilEngineeringDegree-2023".into()),
+                )
+                .unwrap(),
+            ),
+            CredentialUrl::new("https://server.example.com/credential".into()).unwrap(),
+            AccessToken::new("token".into()),
+            Some(metadata),
+        );
+
+        let (builder, jwk) = builder.with_generated_response_encryption(
+            Alg::Other("ECDH-ES".into()),
+            Enc::Other("A128GCM".into()),
+        );
+        builder
+            .check_response_encryption::<std::io::Error>()
+            .unwrap();
+
+        let encryption = builder.credential_response_encryption().unwrap();
+        assert_eq!(encryption.jwk(), &jwk.to_public());
+        let body = serde_json::to_value(&builder.body).unwrap();
+        assert!(body["credential_response_encryption"]["jwk"]
+            .get("d")
+            .is_none());
+    }
+
+    #[test]
+    fn jwt_credential_response() {
+        let builder = || {
+            RequestBuilder::<CoreProfilesCredentialRequest>::new(
+                Request::new(
+                    CoreProfilesCredentialRequest::from_credential_identifier(
+                        CredentialConfigurationId::new("UniversityDegreeCredential".into()),
+                    )
+                    .unwrap(),
+                ),
+                CredentialUrl::new("https://server.example.com/credential".into()).unwrap(),
+                AccessToken::new("token".into()),
+                None,
+            )
+        };
+        let http_response = |body: &str| {
+            http::Response::builder()
+                .status(200)
+                .header(CONTENT_TYPE, MIME_TYPE_JWT)
+                .body(body.as_bytes().to_vec())
+                .unwrap()
+        };
+
+        let jwt = "eyJhbGciOiJFUzI1NiJ9.eyJ2YyI6e319.c2lnbmF0dXJl";
+        let response = builder()
+            .credential_response::<std::io::Error>(http_response(&format!("{jwt}\n")))
+            .unwrap();
+        assert_json_diff::assert_json_eq!(
+            serde_json::to_value(&response).unwrap(),
+            json!({ "credential": jwt })
+        );
+
+        let err = builder()
+            .credential_response::<std::io::Error>(http_response(
+                "eyJhbGciOiJFQ0RILUVTIn0..iv.ct.tag",
+            ))
+            .unwrap_err();
+        assert!(matches!(err, RequestError::Response(..)));
+    }
+
     #[test]
     fn example_credential_request_object() {
         let _: crate::profiles::core::credential::Request = serde_json::from_value(json!({
@@ -478,6 +1217,69 @@ mod test {
         .unwrap();
     }
 
+    #[test]
+    fn example_credential_error_response() {
+        let error: Error = serde_json::from_value(json!({
+            "error": "invalid_proof",
+            "error_description":
+                "Credential Issuer requires key proof to be bound to a Credential Issuer provided nonce.",
+            "c_nonce": "8YE9hCnyV2",
+            "c_nonce_expires_in": 86400
+        }))
+        .unwrap();
+
+        assert_eq!(error.error(), &ErrorType::InvalidProof);
+        assert_eq!(error.c_nonce().unwrap().secret(), "8YE9hCnyV2");
+        assert_eq!(error.c_nonce_expires_in(), Some(&86400));
+    }
+
+    #[test]
+    fn credential_error_extension() {
+        let error: Error = serde_json::from_value(json!({
+            "error": "issuance_pending"
+        }))
+        .unwrap();
+        assert_eq!(error.error(), &ErrorType::IssuancePending);
+
+        let error: Error = serde_json::from_value(json!({
+            "error": "vendor_specific_error",
+            "error_description": "Something vendor specific went wrong."
+        }))
+        .unwrap();
+        assert_eq!(
+            error.error(),
+            &ErrorType::Extension("vendor_specific_error".into())
+        );
+        assert_eq!(error.error().to_string(), "vendor_specific_error");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap()["error"],
+            "vendor_specific_error"
+        );
+    }
+
+    #[test]
+    fn credential_error_response() {
+        let http_response = http::Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .header(CONTENT_TYPE, MIME_TYPE_JSON)
+            .body(
+                serde_json::to_vec(&json!({
+                    "error": "invalid_proof",
+                    "c_nonce": "8YE9hCnyV2"
+                }))
+                .unwrap(),
+            )
+            .unwrap();
+
+        match error_response::<std::io::Error>(&http_response) {
+            RequestError::ErrorResponse(status, error) => {
+                assert_eq!(status, StatusCode::BAD_REQUEST);
+                assert_eq!(error.c_nonce().unwrap().secret(), "8YE9hCnyV2");
+            }
+            err => panic!("unexpected error: {err:?}"),
+        }
+    }
+
     #[test]
     fn example_credential_request_referenced() {
         let _: crate::profiles::core::credential::Request = serde_json::from_value(json!({
@@ -511,6 +1313,84 @@ mod test {
         );
     }
 
+    #[test]
+    fn strict_credential_request() {
+        crate::profiles::core::credential::Request::from_json_strict(&json!({
+            "format": "jwt_vc_json",
+            "credential_definition": {
+                "type": [
+                    "VerifiableCredential",
+                    "UniversityDegreeCredential"
+                ],
+                "credentialSubject": {}
+            },
+            "proof": {
+               "proof_type": "jwt",
+               "jwt": "eyJraWQiOiJkaWQ6ZXhhbXBsZTpl...C_aZKPxgihac0aW9EkL1nOzM"
+            }
+        }))
+        .unwrap();
+    }
+
+    #[test]
+    fn strict_credential_request_unknown_field() {
+        let err = crate::profiles::core::credential::Request::from_json_strict(&json!({
+            "format": "jwt_vc_json",
+            "credential_definition": {
+                "type": [
+                    "VerifiableCredential",
+                    "UniversityDegreeCredential"
+                ],
+                "unexpected": "value"
+            },
+            "also_unexpected": 1
+        }))
+        .unwrap_err();
+
+        let StrictParseError::UnknownFields(mut fields) = err else {
+            panic!("unexpected error: {err:?}")
+        };
+        fields.sort();
+        assert_eq!(
+            fields,
+            vec!["also_unexpected", "credential_definition.unexpected"]
+        );
+
+        // Empty values are only allowed for known fields.
+        let err = crate::profiles::core::credential::Request::from_json_strict(&json!({
+            "format": "jwt_vc_json",
+            "credential_definition": {
+                "type": ["VerifiableCredential", "UniversityDegreeCredential"],
+                "credentialSubject": {},
+                "unexpected": null
+            },
+            "also_unexpected": []
+        }))
+        .unwrap_err();
+        let StrictParseError::UnknownFields(mut fields) = err else {
+            panic!("unexpected error: {err:?}")
+        };
+        fields.sort();
+        assert_eq!(
+            fields,
+            vec!["also_unexpected", "credential_definition.unexpected"]
+        );
+    }
+
+    #[test]
+    fn strict_credential_request_aliases() {
+        crate::profiles::core::credential::Request::from_json_strict(&json!({
+            "format": "jwt_vc_json",
+            "credential_definition": {
+                "types": ["VerifiableCredential", "UniversityDegreeCredential"],
+                "credential_subject": {
+                    "given_name": {}
+                }
+            }
+        }))
+        .unwrap();
+    }
+
     #[test]
     fn example_credential_response_object() {
         let _: Response<CoreProfilesCredentialResponse> = serde_json::from_value(json!({
@@ -522,6 +1402,27 @@ mod test {
         .unwrap();
     }
 
+    #[test]
+    fn credential_response_notification_id() {
+        let response: Response<CoreProfilesCredentialResponse> = serde_json::from_value(json!({
+            "format": "jwt_vc_json",
+            "credential": "LUpixVCWJk0eOt4CXQe1NXK....WZwmhmn9OQp6YxX0a2L",
+            "notification_id": "3fwe98js",
+            "credential_identifier": "CivilEngineeringDegree-2023"
+        }))
+        .unwrap();
+
+        assert!(matches!(
+            response.response_kind(),
+            ResponseEnum::Immediate { .. }
+        ));
+        assert_eq!(response.notification_id().unwrap(), "3fwe98js");
+        assert_eq!(
+            response.credential_identifier().unwrap().as_str(),
+            "CivilEngineeringDegree-2023"
+        );
+    }
+
     #[test]
     fn example_credential_deferred_response_object() {
         let _: Response<CoreProfilesCredentialResponse> = serde_json::from_value(json!({
@@ -532,6 +1433,75 @@ mod test {
         .unwrap();
     }
 
+    #[test]
+    fn clone_response() {
+        let response: Response<CoreProfilesCredentialResponse> = serde_json::from_value(json!({
+            "credential": "LUpixVCWJk0eOt4CXQe1NXK....WZwmhmn9OQp6YxX0a2L",
+            "c_nonce": "fGFF7UkhLa",
+            "c_nonce_expires_in": 86400
+        }))
+        .unwrap();
+        let cached = response.clone();
+        assert_eq!(
+            serde_json::to_value(cached).unwrap(),
+            serde_json::to_value(response).unwrap()
+        );
+
+        let batch_response: BatchResponse<CoreProfilesCredentialResponse> =
+            serde_json::from_value(json!({
+                "credential_responses": [{ "transaction_id": "8xLOxBtZp8" }]
+            }))
+            .unwrap();
+        let _cached = batch_response.clone();
+    }
+
+    #[test]
+    fn deferred_response_interval() {
+        let value = json!({
+            "transaction_id": "8xLOxBtZp8",
+            "interval": 30,
+            "c_nonce": "wlbQc6pCJp",
+            "c_nonce_expires_in": 86400
+        });
+        let response: Response<CoreProfilesCredentialResponse> =
+            serde_json::from_value(value.clone()).unwrap();
+        assert!(matches!(
+            response.response_kind(),
+            ResponseEnum::Deferred { transaction_id: Some(transaction_id), interval: Some(30) }
+                if transaction_id == "8xLOxBtZp8"
+        ));
+        assert_json_diff::assert_json_eq!(serde_json::to_value(&response).unwrap(), value);
+
+        let policy = DeferredPollingPolicy::from_response(response.response_kind(), 3);
+        assert_eq!(policy.interval(), &Duration::from_secs(30));
+
+        let response: Response<CoreProfilesCredentialResponse> =
+            serde_json::from_value(json!({ "transaction_id": "8xLOxBtZp8", "interval": 86400 }))
+                .unwrap();
+        let policy = DeferredPollingPolicy::from_response(response.response_kind(), 3);
+        assert_eq!(policy.interval(), &DeferredPollingPolicy::MAX_INTERVAL);
+    }
+
+    #[test]
+    fn deferred_pending_interval_is_clamped() {
+        let mut builder = DeferredRequestBuilder::<CoreProfilesCredentialResponse>::new(
+            DeferredRequest::new("8xLOxBtZp8".into()),
+            DeferredCredentialUrl::new("https://server.example.com/deferred_credential".into())
+                .unwrap(),
+            AccessToken::new("czZCaGRSa3F0MzpnWDFmQmF0M2JW".into()),
+            DeferredPollingPolicy::new(3, Duration::from_secs(5)),
+        );
+
+        builder.update_pending(None, Some(0));
+        assert_eq!(builder.interval, Duration::from_secs(5));
+
+        builder.update_pending(None, Some(30));
+        assert_eq!(builder.interval, Duration::from_secs(30));
+
+        builder.update_pending(None, Some(86400));
+        assert_eq!(builder.interval, DeferredPollingPolicy::MAX_INTERVAL);
+    }
+
     #[test]
     fn example_error() {
         let _: Error = serde_json::from_value(json!({
@@ -615,4 +1585,54 @@ mod test {
         }))
         .unwrap();
     }
+
+    #[test]
+    fn example_credential_request_object_previous_drafts() {
+        let previous: crate::profiles::core::credential::Request = serde_json::from_value(json!({
+            "format": "jwt_vc_json",
+            "credential_definition": {
+                "types": [
+                    "VerifiableCredential",
+                    "UniversityDegreeCredential"
+                ]
+            }
+        }))
+        .unwrap();
+        let current: crate::profiles::core::credential::Request = serde_json::from_value(json!({
+            "format": "jwt_vc_json",
+            "credential_definition": {
+                "type": [
+                    "VerifiableCredential",
+                    "UniversityDegreeCredential"
+                ]
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(previous, current);
+    }
+
+    #[test]
+    fn example_deferred_previous_drafts() {
+        let response: Response<CoreProfilesCredentialResponse> = serde_json::from_value(json!({
+            "acceptance_token": "8xLOxBtZp8",
+            "c_nonce": "wlbQc6pCJp",
+            "c_nonce_expires_in": 86400
+        }))
+        .unwrap();
+        assert!(matches!(
+            response.response_kind(),
+            ResponseEnum::Deferred { transaction_id: Some(transaction_id), .. } if transaction_id == "8xLOxBtZp8"
+        ));
+
+        let previous: DeferredRequest = serde_json::from_value(json!({
+            "acceptance_token": "8xLOxBtZp8"
+        }))
+        .unwrap();
+        let current: DeferredRequest = serde_json::from_value(json!({
+            "transaction_id": "8xLOxBtZp8"
+        }))
+        .unwrap();
+        assert_eq!(previous, current);
+    }
 }
@@ -1,27 +1,53 @@
 #![allow(clippy::large_enum_variant, deprecated)]
 
+use std::time::Duration;
+
 use anyhow::{bail, Context, Result};
 use oauth2::{
-    http::{self, header::ACCEPT, HeaderValue, Method, StatusCode},
+    http::{
+        self,
+        header::{ACCEPT, CONTENT_TYPE},
+        HeaderValue, Method, StatusCode,
+    },
     AsyncHttpClient, SyncHttpClient,
 };
-use serde::{Deserialize, Serialize};
-use serde_with::{serde_as, skip_serializing_none};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use serde_with::{formats::PreferMany, serde_as, skip_serializing_none, KeyValueMap, OneOrMany};
+use ssi::{
+    claims::{
+        jws::{self, Header},
+        jwt,
+    },
+    jwk::{Algorithm, JWK},
+};
+use time::OffsetDateTime;
+use tracing::warn;
 use url::Url;
 
 use crate::{
-    http_utils::{check_content_type, MIME_TYPE_JSON},
+    http_utils::{check_content_type, content_type_has_essence, MIME_TYPE_JSON, MIME_TYPE_JWT},
+    metadata::{
+        authorization_server::GrantType, credential_issuer::CredentialConfiguration,
+        CredentialIssuerMetadata, JwtVcIssuerMetadata, MetadataDiscovery,
+    },
+    profiles::{
+        core::profiles::CoreProfilesCredentialConfiguration, CredentialConfigurationProfile,
+    },
     types::{
-        CredentialConfigurationId, CredentialOfferRequest, IssuerState, IssuerUrl,
+        CredentialConfigurationId, CredentialOfferRequest, IssuerState, IssuerUrl, JsonWebKeySet,
         PreAuthorizedCode,
     },
 };
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
-#[serde(untagged)]
-pub enum CredentialOffer {
+#[serde(untagged, bound = "CM: CredentialConfigurationProfile")]
+pub enum CredentialOffer<CM = CoreProfilesCredentialConfiguration>
+where
+    CM: CredentialConfigurationProfile,
+{
     Value {
-        credential_offer: CredentialOfferParameters,
+        credential_offer: CredentialOfferParameters<CM>,
     },
     Reference {
         credential_offer_uri: Url,
@@ -35,28 +61,154 @@ enum CredentialOfferFlat {
     Reference { credential_offer_uri: Url },
 }
 
-impl CredentialOffer {
+impl<CM> CredentialOffer<CM>
+where
+    CM: CredentialConfigurationProfile,
+{
     pub fn from_request(uri: CredentialOfferRequest) -> Result<Self> {
-        match serde_path_to_error::deserialize(serde_urlencoded::Deserializer::new(
-            form_urlencoded::parse(uri.url().query().unwrap_or_default().as_bytes()),
-        ))? {
+        Self::from_flat(Self::parse_request(&uri)?)
+    }
+
+    /// Parses a credential offer from the bare query string of a credential offer request, e.g.
+    /// `credential_offer_uri=https%3A%2F%2Fserver.example.com%2Fcredential-offer`, as received
+    /// without the scheme and host by some apps.
+    pub fn from_query_str(query: &str) -> Result<Self> {
+        let query = query.strip_prefix('?').unwrap_or(query);
+        Self::from_flat(Self::parse_query(query)?)
+    }
+
+    fn from_flat(flat: CredentialOfferFlat) -> Result<Self> {
+        match flat {
+            CredentialOfferFlat::Reference {
+                credential_offer_uri,
+            } => Ok(CredentialOffer::Reference {
+                credential_offer_uri,
+            }),
+            CredentialOfferFlat::Value { credential_offer } => Ok(CredentialOffer::Value {
+                credential_offer: serde_json::from_str(&Self::decode_value(&credential_offer)?)
+                    .context("could not decode inner JSON")?,
+            }),
+        }
+    }
+
+    /// Same as [`CredentialOffer::from_request`], but requires a credential offer passed by value
+    /// to be a signed JWT, as produced by [`CredentialOfferParameters::to_signed_request`].
+    ///
+    /// The JWT is verified as in [`CredentialOffer::resolve_signed`], and unsigned JSON
+    /// offers are rejected.
+    pub fn from_request_signed<C>(uri: CredentialOfferRequest, http_client: &C) -> Result<Self>
+    where
+        C: SyncHttpClient,
+        C::Error: Send + Sync,
+    {
+        Self::from_request_signed_with_options(uri, http_client, VerificationOptions::default())
+    }
+
+    /// Same as [`CredentialOffer::from_request_signed`], with custom JWT verification `options`.
+    pub fn from_request_signed_with_options<C>(
+        uri: CredentialOfferRequest,
+        http_client: &C,
+        options: VerificationOptions,
+    ) -> Result<Self>
+    where
+        C: SyncHttpClient,
+        C::Error: Send + Sync,
+    {
+        match Self::parse_signed_request(&uri)? {
+            CredentialOfferFlat::Reference {
+                credential_offer_uri,
+            } => Ok(CredentialOffer::Reference {
+                credential_offer_uri,
+            }),
+            CredentialOfferFlat::Value { credential_offer } => Ok(CredentialOffer::Value {
+                credential_offer: Self::verify_signed_credential_offer(
+                    &credential_offer,
+                    http_client,
+                    options,
+                )?,
+            }),
+        }
+    }
+
+    /// Async version of [`CredentialOffer::from_request_signed`].
+    pub async fn from_request_signed_async<'c, C>(
+        uri: CredentialOfferRequest,
+        http_client: &'c C,
+    ) -> Result<Self>
+    where
+        C: AsyncHttpClient<'c>,
+        C::Error: Send + Sync,
+    {
+        Self::from_request_signed_async_with_options(
+            uri,
+            http_client,
+            VerificationOptions::default(),
+        )
+        .await
+    }
+
+    /// Async version of [`CredentialOffer::from_request_signed_with_options`].
+    pub async fn from_request_signed_async_with_options<'c, C>(
+        uri: CredentialOfferRequest,
+        http_client: &'c C,
+        options: VerificationOptions,
+    ) -> Result<Self>
+    where
+        C: AsyncHttpClient<'c>,
+        C::Error: Send + Sync,
+    {
+        match Self::parse_signed_request(&uri)? {
             CredentialOfferFlat::Reference {
                 credential_offer_uri,
             } => Ok(CredentialOffer::Reference {
                 credential_offer_uri,
             }),
             CredentialOfferFlat::Value { credential_offer } => Ok(CredentialOffer::Value {
-                credential_offer: serde_json::from_str(
-                    &percent_encoding::percent_decode_str(&credential_offer)
-                        .decode_utf8()
-                        .context("could not percent decode credential offer JSON")?,
+                credential_offer: Self::verify_signed_credential_offer_async(
+                    &credential_offer,
+                    http_client,
+                    options,
                 )
-                .context("could not decode inner JSON")?,
+                .await?,
             }),
         }
     }
 
-    pub fn resolve<C>(self, http_client: &C) -> Result<CredentialOfferParameters>
+    /// Parses a credential offer request whose credential offer, if passed by value, must be a
+    /// JWT, which is returned percent decoded.
+    fn parse_signed_request(uri: &CredentialOfferRequest) -> Result<CredentialOfferFlat> {
+        let flat = Self::parse_request(uri)?;
+        let CredentialOfferFlat::Value { credential_offer } = flat else {
+            return Ok(flat);
+        };
+        let credential_offer = Self::decode_value(&credential_offer)?;
+        let credential_offer = credential_offer.trim();
+        if credential_offer.starts_with('{') {
+            bail!("credential offer is not a signed JWT")
+        }
+        Ok(CredentialOfferFlat::Value {
+            credential_offer: credential_offer.to_string(),
+        })
+    }
+
+    fn parse_request(uri: &CredentialOfferRequest) -> Result<CredentialOfferFlat> {
+        Self::parse_query(uri.url().query().unwrap_or_default())
+    }
+
+    fn parse_query(query: &str) -> Result<CredentialOfferFlat> {
+        Ok(serde_path_to_error::deserialize(
+            serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes())),
+        )?)
+    }
+
+    fn decode_value(credential_offer: &str) -> Result<String> {
+        Ok(percent_encoding::percent_decode_str(credential_offer)
+            .decode_utf8()
+            .context("could not percent decode credential offer JSON")?
+            .into_owned())
+    }
+
+    pub fn resolve<C>(self, http_client: &C) -> Result<CredentialOfferParameters<CM>>
     where
         C: SyncHttpClient,
         C::Error: Send + Sync,
@@ -68,7 +220,7 @@ impl CredentialOffer {
             } => credential_offer_uri,
         };
 
-        let request = Self::build_request(&uri)?;
+        let request = Self::build_request(&uri, MIME_TYPE_JSON)?;
 
         let response = http_client
             .call(request)
@@ -77,7 +229,10 @@ impl CredentialOffer {
         Self::handle_response(response, &uri)
     }
 
-    pub async fn resolve_async<'c, C>(self, http_client: &'c C) -> Result<CredentialOfferParameters>
+    pub async fn resolve_async<'c, C>(
+        self,
+        http_client: &'c C,
+    ) -> Result<CredentialOfferParameters<CM>>
     where
         C: AsyncHttpClient<'c>,
         C::Error: Send + Sync,
@@ -89,7 +244,7 @@ impl CredentialOffer {
             } => credential_offer_uri,
         };
 
-        let request = Self::build_request(&uri)?;
+        let request = Self::build_request(&uri, MIME_TYPE_JSON)?;
 
         let response = http_client
             .call(request)
@@ -99,11 +254,208 @@ impl CredentialOffer {
         Self::handle_response(response, &uri)
     }
 
-    fn build_request(url: &Url) -> Result<http::Request<Vec<u8>>> {
+    /// Same as [`CredentialOffer::resolve`], but requires the credential offer at the
+    /// `credential_offer_uri` to be served as a signed JWT (`application/jwt`).
+    ///
+    /// The JWT must reference its signing key with a `kid` header, and its `iss` claim must match
+    /// the `credential_issuer` of the offer. The key must be in the JWK Set published by the
+    /// credential issuer, see [`JwtVcIssuerMetadata`], which is fetched with `http_client` before
+    /// the signature is verified. Its `exp`, `nbf` and `iat` claims, if any, are checked without
+    /// any clock skew tolerance, see [`CredentialOffer::resolve_signed_with_options`].
+    pub fn resolve_signed<C>(self, http_client: &C) -> Result<CredentialOfferParameters<CM>>
+    where
+        C: SyncHttpClient,
+        C::Error: Send + Sync,
+    {
+        self.resolve_signed_with_options(http_client, VerificationOptions::default())
+    }
+
+    /// Same as [`CredentialOffer::resolve_signed`], with custom JWT verification `options`.
+    pub fn resolve_signed_with_options<C>(
+        self,
+        http_client: &C,
+        options: VerificationOptions,
+    ) -> Result<CredentialOfferParameters<CM>>
+    where
+        C: SyncHttpClient,
+        C::Error: Send + Sync,
+    {
+        let uri = match self {
+            CredentialOffer::Value { credential_offer } => return Ok(credential_offer),
+            CredentialOffer::Reference {
+                credential_offer_uri,
+            } => credential_offer_uri,
+        };
+
+        let request = Self::build_request(&uri, MIME_TYPE_JWT)?;
+
+        let response = http_client
+            .call(request)
+            .context("error occurred when making the request")?;
+
+        let jwt = Self::handle_signed_response(response, &uri)?;
+
+        Self::verify_signed_credential_offer(&jwt, http_client, options)
+    }
+
+    /// Async version of [`CredentialOffer::resolve_signed`].
+    pub async fn resolve_signed_async<'c, C>(
+        self,
+        http_client: &'c C,
+    ) -> Result<CredentialOfferParameters<CM>>
+    where
+        C: AsyncHttpClient<'c>,
+        C::Error: Send + Sync,
+    {
+        self.resolve_signed_async_with_options(http_client, VerificationOptions::default())
+            .await
+    }
+
+    /// Async version of [`CredentialOffer::resolve_signed_with_options`].
+    pub async fn resolve_signed_async_with_options<'c, C>(
+        self,
+        http_client: &'c C,
+        options: VerificationOptions,
+    ) -> Result<CredentialOfferParameters<CM>>
+    where
+        C: AsyncHttpClient<'c>,
+        C::Error: Send + Sync,
+    {
+        let uri = match self {
+            CredentialOffer::Value { credential_offer } => return Ok(credential_offer),
+            CredentialOffer::Reference {
+                credential_offer_uri,
+            } => credential_offer_uri,
+        };
+
+        let request = Self::build_request(&uri, MIME_TYPE_JWT)?;
+
+        let response = http_client
+            .call(request)
+            .await
+            .context("error occurred when making the request")?;
+
+        let jwt = Self::handle_signed_response(response, &uri)?;
+
+        Self::verify_signed_credential_offer_async(&jwt, http_client, options).await
+    }
+
+    fn verify_signed_credential_offer<C>(
+        jwt: &str,
+        http_client: &C,
+        options: VerificationOptions,
+    ) -> Result<CredentialOfferParameters<CM>>
+    where
+        C: SyncHttpClient,
+        C::Error: Send + Sync,
+    {
+        let (kid, issuer) = Self::decode_signed_credential_offer(jwt)?;
+        let jwks = JwtVcIssuerMetadata::discover(&issuer, http_client)
+            .and_then(|metadata| metadata.resolve_jwks(http_client))
+            .with_context(|| {
+                format!(
+                    "failed to fetch the keys of credential issuer `{}`",
+                    issuer.as_str()
+                )
+            })?;
+        Self::verify_with_issuer_keys(jwt, &kid, &issuer, jwks.as_ref(), options)
+    }
+
+    async fn verify_signed_credential_offer_async<'c, C>(
+        jwt: &str,
+        http_client: &'c C,
+        options: VerificationOptions,
+    ) -> Result<CredentialOfferParameters<CM>>
+    where
+        C: AsyncHttpClient<'c>,
+        C::Error: Send + Sync,
+    {
+        let (kid, issuer) = Self::decode_signed_credential_offer(jwt)?;
+        let metadata = JwtVcIssuerMetadata::discover_async(&issuer, http_client).await;
+        let jwks = match metadata {
+            Ok(metadata) => metadata.resolve_jwks_async(http_client).await,
+            Err(e) => Err(e),
+        }
+        .with_context(|| {
+            format!(
+                "failed to fetch the keys of credential issuer `{}`",
+                issuer.as_str()
+            )
+        })?;
+        Self::verify_with_issuer_keys(jwt, &kid, &issuer, jwks.as_ref(), options)
+    }
+
+    /// Decodes the header and the claims of a signed credential offer without verifying its
+    /// signature, and returns the `kid` of its key and its credential issuer.
+    fn decode_signed_credential_offer(jwt: &str) -> Result<(String, IssuerUrl)> {
+        #[derive(Deserialize)]
+        struct Issuers {
+            iss: IssuerUrl,
+            credential_issuer: IssuerUrl,
+        }
+
+        let (header, payload) = jws::decode_unverified(jwt).context("failed to decode JWT")?;
+        if header.algorithm == Algorithm::None {
+            bail!("credential offer JWT must be signed")
+        }
+
+        // A key embedded in the JWT proves nothing about its signer, the key must be one
+        // published by the credential issuer.
+        if header.jwk.is_some() {
+            bail!("credential offer JWT must not embed its key in a `jwk` header")
+        }
+        let Some(kid) = header.key_id else {
+            bail!("credential offer JWT has no `kid` header")
+        };
+
+        let issuers: Issuers =
+            serde_json::from_slice(&payload).context("failed to parse credential offer JWT")?;
+        if issuers.iss != issuers.credential_issuer {
+            bail!(
+                "credential offer JWT issuer `{}` does not match credential issuer `{}`",
+                issuers.iss.as_str(),
+                issuers.credential_issuer.as_str()
+            )
+        }
+
+        Ok((kid, issuers.credential_issuer))
+    }
+
+    /// Verifies a signed credential offer with the key `kid` of the JWK Set of its credential
+    /// `issuer`.
+    fn verify_with_issuer_keys(
+        jwt: &str,
+        kid: &str,
+        issuer: &IssuerUrl,
+        jwks: Option<&JsonWebKeySet>,
+        options: VerificationOptions,
+    ) -> Result<CredentialOfferParameters<CM>> {
+        let Some(jwk) = jwks.and_then(|jwks| jwks.find(kid)) else {
+            bail!(
+                "credential offer JWT key `{}` is not published by credential issuer `{}`",
+                kid,
+                issuer.as_str()
+            )
+        };
+
+        let claims: SignedCredentialOfferClaims<CM> =
+            jwt::decode_verify(jwt, jwk).context("failed to verify credential offer JWT")?;
+
+        options.check_validity(
+            OffsetDateTime::now_utc(),
+            claims.not_before,
+            claims.issued_at,
+            claims.expires_at,
+        )?;
+
+        Ok(claims.credential_offer)
+    }
+
+    fn build_request(url: &Url, accept: &'static str) -> Result<http::Request<Vec<u8>>> {
         http::Request::builder()
             .uri(url.as_str())
             .method(Method::GET)
-            .header(ACCEPT, HeaderValue::from_static(MIME_TYPE_JSON))
+            .header(ACCEPT, HeaderValue::from_static(accept))
             .body(Vec::new())
             .context("failed to prepare request")
     }
@@ -111,7 +463,7 @@ impl CredentialOffer {
     fn handle_response(
         response: http::Response<Vec<u8>>,
         url: &Url,
-    ) -> Result<CredentialOfferParameters> {
+    ) -> Result<CredentialOfferParameters<CM>> {
         if response.status() != StatusCode::OK {
             bail!("HTTP status code {} at {}", response.status(), url)
         }
@@ -121,18 +473,134 @@ impl CredentialOffer {
         serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_slice(response.body()))
             .context("failed to parse response body")
     }
+
+    fn handle_signed_response(response: http::Response<Vec<u8>>, url: &Url) -> Result<String> {
+        if response.status() != StatusCode::OK {
+            bail!("HTTP status code {} at {}", response.status(), url)
+        }
+
+        let is_jwt = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .is_some_and(|content_type| content_type_has_essence(content_type, MIME_TYPE_JWT));
+        if !is_jwt {
+            bail!("credential offer at {} is not a signed JWT", url)
+        }
+
+        let jwt = std::str::from_utf8(response.body()).context("invalid JWT encoding")?;
+        Ok(jwt.trim().to_string())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "unknown credential configurations: {}",
+    .0.iter().map(|id| id.as_str()).collect::<Vec<_>>().join(", ")
+)]
+pub struct UnknownCredentialConfigurations(pub Vec<CredentialConfigurationId>);
+
+/// Options for the verification of JWTs signed by a credential issuer, such as signed credential
+/// offers.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VerificationOptions {
+    /// Slack in exp, nbf and iat validation to deal with clock synchronisation issues.
+    pub leeway: time::Duration,
+}
+
+impl VerificationOptions {
+    pub fn new(leeway: time::Duration) -> Self {
+        Self { leeway }
+    }
+
+    /// Checks the time claims of a JWT at `now`, allowing for `leeway` in either direction.
+    fn check_validity(
+        &self,
+        now: OffsetDateTime,
+        not_before: Option<OffsetDateTime>,
+        issued_at: Option<OffsetDateTime>,
+        expires_at: Option<OffsetDateTime>,
+    ) -> Result<()> {
+        if let Some(not_before) = not_before {
+            if (now + self.leeway) < not_before {
+                bail!("JWT is not yet valid")
+            }
+        }
+
+        if let Some(issued_at) = issued_at {
+            if (now + self.leeway) < issued_at {
+                bail!("JWT is issued in the future")
+            }
+        }
+
+        if let Some(expires_at) = expires_at {
+            if (now - self.leeway) > expires_at {
+                bail!("JWT is expired")
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Claims of a credential offer served as a signed JWT.
+#[derive(Deserialize, Serialize)]
+#[serde(bound = "CM: CredentialConfigurationProfile")]
+struct SignedCredentialOfferClaims<CM>
+where
+    CM: CredentialConfigurationProfile,
+{
+    #[serde(rename = "iss")]
+    issuer: IssuerUrl,
+    #[serde(
+        rename = "nbf",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "time::serde::timestamp::option"
+    )]
+    not_before: Option<OffsetDateTime>,
+    #[serde(
+        rename = "iat",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "time::serde::timestamp::option"
+    )]
+    issued_at: Option<OffsetDateTime>,
+    #[serde(
+        rename = "exp",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "time::serde::timestamp::option"
+    )]
+    expires_at: Option<OffsetDateTime>,
+    #[serde(flatten)]
+    credential_offer: CredentialOfferParameters<CM>,
 }
 
 #[serde_as]
 #[skip_serializing_none]
 #[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct CredentialOfferParameters {
+#[serde(bound = "CM: CredentialConfigurationProfile")]
+pub struct CredentialOfferParameters<CM = CoreProfilesCredentialConfiguration>
+where
+    CM: CredentialConfigurationProfile,
+{
     credential_issuer: IssuerUrl,
+    /// Some issuers send a single identifier instead of an array.
+    #[serde(alias = "credentials")]
+    #[serde_as(as = "OneOrMany<_, PreferMany>")]
     credential_configuration_ids: Vec<CredentialConfigurationId>,
     grants: Option<CredentialOfferGrants>,
+    /// Credential configurations inlined in the offer, keyed by identifier, for issuers that do
+    /// not publish them in their metadata. Those that cannot be parsed are skipped.
+    #[serde_as(serialize_as = "Option<KeyValueMap<_>>")]
+    #[serde(default, deserialize_with = "deserialize_inline_configurations")]
+    credential_configurations: Option<Vec<CredentialConfiguration<CM>>>,
 }
 
-impl CredentialOfferParameters {
+impl<CM> CredentialOfferParameters<CM>
+where
+    CM: CredentialConfigurationProfile,
+{
     pub fn new(
         credential_issuer: IssuerUrl,
         credential_configuration_ids: Vec<CredentialConfigurationId>,
@@ -142,9 +610,18 @@ impl CredentialOfferParameters {
             credential_issuer,
             credential_configuration_ids,
             grants,
+            credential_configurations: None,
         }
     }
 
+    pub fn set_credential_configurations(
+        mut self,
+        credential_configurations: Option<Vec<CredentialConfiguration<CM>>>,
+    ) -> Self {
+        self.credential_configurations = credential_configurations;
+        self
+    }
+
     pub fn issuer(&self) -> &IssuerUrl {
         &self.credential_issuer
     }
@@ -157,6 +634,65 @@ impl CredentialOfferParameters {
         &self.credential_configuration_ids
     }
 
+    pub fn credential_configurations(&self) -> Option<&[CredentialConfiguration<CM>]> {
+        self.credential_configurations.as_deref()
+    }
+
+    /// Returns the configuration inlined in the offer for the credential configuration `id`.
+    pub fn inline_configuration(
+        &self,
+        id: &CredentialConfigurationId,
+    ) -> Option<&CredentialConfiguration<CM>> {
+        self.credential_configurations()?
+            .iter()
+            .find(|configuration| configuration.id() == id)
+    }
+
+    /// Returns the configurations of the offered credentials.
+    ///
+    /// Configurations inlined in the offer are preferred, those of the credential issuer
+    /// `metadata` are used for the other identifiers.
+    pub fn configurations_for_offer(
+        &self,
+        metadata: Option<&CredentialIssuerMetadata<CM>>,
+    ) -> Result<Vec<CredentialConfiguration<CM>>> {
+        self.credential_configuration_ids
+            .iter()
+            .map(|id| {
+                self.inline_configuration(id)
+                    .or_else(|| {
+                        metadata?
+                            .credential_configurations_supported()
+                            .iter()
+                            .find(|configuration| configuration.id() == id)
+                    })
+                    .cloned()
+                    .with_context(|| format!("unknown credential configuration `{}`", id.as_str()))
+            })
+            .collect()
+    }
+
+    /// Checks that all the offered credential configurations are in the
+    /// `credential_configurations_supported` of the credential issuer `metadata`, or inlined in
+    /// the offer.
+    pub fn validate_against_metadata(
+        &self,
+        metadata: &CredentialIssuerMetadata<CM>,
+    ) -> Result<(), UnknownCredentialConfigurations> {
+        let supported = metadata.configurations_by_id();
+        let unknown: Vec<_> = self
+            .credential_configuration_ids
+            .iter()
+            .filter(|id| !supported.contains_key(id) && self.inline_configuration(id).is_none())
+            .cloned()
+            .collect();
+        if unknown.is_empty() {
+            Ok(())
+        } else {
+            Err(UnknownCredentialConfigurations(unknown))
+        }
+    }
+
     pub fn authorization_code_grant(&self) -> Option<&AuthorizationCodeGrant> {
         self.grants()?.authorization_code()
     }
@@ -164,6 +700,48 @@ impl CredentialOfferParameters {
     pub fn pre_authorized_code_grant(&self) -> Option<&PreAuthorizedCodeGrant> {
         self.grants()?.pre_authorized_code()
     }
+
+    /// Wraps the offer in a JWT signed with the credential issuer key `jwk`, whose `iss` claim
+    /// is the credential issuer.
+    ///
+    /// The key is referenced by its `kid`, which is required, and must be in the JWK Set
+    /// published by the credential issuer for wallets to accept the offer, see
+    /// [`JwtVcIssuerMetadata`].
+    pub fn to_signed_jwt(&self, jwk: &JWK) -> Result<String> {
+        let Some(algorithm) = jwk.get_algorithm() else {
+            bail!("credential offer signing key has no algorithm")
+        };
+        let Some(key_id) = jwk.key_id.clone() else {
+            bail!("credential offer signing key has no `kid`")
+        };
+        let header = Header {
+            algorithm,
+            key_id: Some(key_id),
+            ..Default::default()
+        };
+        let claims = SignedCredentialOfferClaims {
+            issuer: self.credential_issuer.clone(),
+            not_before: None,
+            issued_at: None,
+            expires_at: None,
+            credential_offer: self.clone(),
+        };
+        let payload = serde_json::to_string(&claims)?;
+        jws::encode_sign_custom_header(&payload, jwk, &header)
+            .context("failed to sign credential offer")
+    }
+
+    /// Signs the offer with [`CredentialOfferParameters::to_signed_jwt`], and passes it by
+    /// value in an `openid-credential-offer://?credential_offer=<jwt>` request.
+    pub fn to_signed_request(&self, jwk: &JWK) -> Result<CredentialOfferRequest> {
+        let jwt = self.to_signed_jwt(jwk)?;
+        let mut url = Url::parse(&format!(
+            "{}://",
+            CredentialOfferRequest::DEFAULT_URL_SCHEME
+        ))?;
+        url.query_pairs_mut().append_pair("credential_offer", &jwt);
+        Ok(CredentialOfferRequest::from_url(url))
+    }
 }
 
 #[serde_as]
@@ -191,6 +769,25 @@ impl CredentialOfferGrants {
             set_pre_authorized_code -> pre_authorized_code[Option<PreAuthorizedCodeGrant>],
         }
     ];
+
+    /// Returns the grant types offered, the authorization code grant first.
+    pub fn available_grant_types(&self) -> Vec<GrantType> {
+        let mut grant_types = Vec::new();
+        if self.authorization_code.is_some() {
+            grant_types.push(GrantType::AuthorizationCode);
+        }
+        if self.pre_authorized_code.is_some() {
+            grant_types.push(GrantType::PreAuthorizedCode);
+        }
+        grant_types
+    }
+
+    /// Whether the pre-authorized code grant is offered and requires a transaction code.
+    pub fn requires_tx_code(&self) -> bool {
+        self.pre_authorized_code
+            .as_ref()
+            .is_some_and(|grant| grant.tx_code.is_some())
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -218,12 +815,21 @@ impl AuthorizationCodeGrant {
 pub struct PreAuthorizedCodeGrant {
     #[serde(rename = "pre-authorized_code")]
     pre_authorized_code: PreAuthorizedCode,
+    #[serde(
+        default,
+        alias = "user_pin_required",
+        deserialize_with = "deserialize_tx_code"
+    )]
     tx_code: Option<TxCodeDefinition>,
     interval: Option<usize>,
     authorization_server: Option<IssuerUrl>,
 }
 
 impl PreAuthorizedCodeGrant {
+    /// Polling interval used when the grant does not specify one, as in
+    /// [RFC8628](https://datatracker.ietf.org/doc/html/rfc8628#section-3.5).
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+
     pub fn new(pre_authorized_code: PreAuthorizedCode) -> Self {
         Self {
             pre_authorized_code,
@@ -240,6 +846,69 @@ impl PreAuthorizedCodeGrant {
             set_authorization_server -> authorization_server[Option<IssuerUrl>],
         }
     ];
+
+    /// Returns the minimum time to wait between polling requests, i.e. the `interval` of the
+    /// grant, or [`PreAuthorizedCodeGrant::DEFAULT_INTERVAL`] if it has none.
+    pub fn polling_interval(&self) -> Duration {
+        self.interval.map_or(Self::DEFAULT_INTERVAL, |interval| {
+            Duration::from_secs(interval as u64)
+        })
+    }
+}
+
+/// Accepts the `user_pin_required` boolean of earlier drafts in place of `tx_code`.
+/// Deserializes the credential configurations inlined in an offer, skipping those that cannot be
+/// parsed, e.g. of an unknown format, as the lenient parsing of the credential issuer metadata
+/// does.
+fn deserialize_inline_configurations<'de, D, CM>(
+    deserializer: D,
+) -> Result<Option<Vec<CredentialConfiguration<CM>>>, D::Error>
+where
+    D: Deserializer<'de>,
+    CM: CredentialConfigurationProfile,
+{
+    let Some(entries) = Option::<Map<String, Value>>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    let configurations = entries
+        .into_iter()
+        .filter_map(|(id, mut entry)| {
+            if let Value::Object(entry) = &mut entry {
+                // The identifier is the map key, see `KeyValueMap`.
+                entry.insert("$key$".to_string(), Value::String(id.clone()));
+            }
+            match serde_json::from_value(entry) {
+                Ok(configuration) => Some(configuration),
+                Err(error) => {
+                    warn!("skipping inline credential configuration `{id}`: {error}");
+                    None
+                }
+            }
+        })
+        .collect();
+    Ok(Some(configurations))
+}
+
+fn deserialize_tx_code<'de, D>(deserializer: D) -> Result<Option<TxCodeDefinition>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum TxCodeOrUserPinRequired {
+        TxCode(TxCodeDefinition),
+        UserPinRequired(bool),
+    }
+
+    Ok(
+        match Option::<TxCodeOrUserPinRequired>::deserialize(deserializer)? {
+            Some(TxCodeOrUserPinRequired::TxCode(tx_code)) => Some(tx_code),
+            Some(TxCodeOrUserPinRequired::UserPinRequired(true)) => {
+                Some(TxCodeDefinition::new(None, None, None))
+            }
+            Some(TxCodeOrUserPinRequired::UserPinRequired(false)) | None => None,
+        },
+    )
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -284,14 +953,237 @@ impl TxCodeDefinition {
             set_description -> description[Option<String>],
         }
     ];
+
+    /// Returns what a wallet needs to prompt the user for the transaction code.
+    pub fn prompt(&self) -> TxCodePrompt<'_> {
+        TxCodePrompt {
+            numeric: matches!(
+                self.input_mode.clone().unwrap_or_default(),
+                InputMode::Numeric
+            ),
+            expected_length: self.length,
+            description: self.description.as_deref(),
+        }
+    }
+}
+
+/// A user prompt for a transaction code, derived from a [`TxCodeDefinition`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TxCodePrompt<'a> {
+    /// Whether the transaction code only contains digits.
+    pub numeric: bool,
+    /// Number of characters of the transaction code, if known.
+    pub expected_length: Option<usize>,
+    /// Guidance for the user, to be displayed alongside the input.
+    pub description: Option<&'a str>,
+}
+
+impl TxCodePrompt<'_> {
+    /// Checks the user input against the transaction code definition.
+    pub fn validate(&self, input: &str) -> Result<(), TxCodeError> {
+        if input.is_empty() {
+            return Err(TxCodeError::Empty);
+        }
+        if self.numeric && !input.chars().all(|c| c.is_ascii_digit()) {
+            return Err(TxCodeError::NotNumeric);
+        }
+        if let Some(expected) = self.expected_length {
+            let actual = input.chars().count();
+            if actual != expected {
+                return Err(TxCodeError::InvalidLength { expected, actual });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum TxCodeError {
+    #[error("transaction code is empty")]
+    Empty,
+    #[error("transaction code must only contain digits")]
+    NotNumeric,
+    #[error("transaction code must be {expected} characters long, found {actual}")]
+    InvalidLength { expected: usize, actual: usize },
 }
 
 #[cfg(test)]
 mod test {
     use serde_json::json;
 
+    use crate::http_utils::MIME_TYPE_JWK_SET;
+
     use super::*;
 
+    #[test]
+    fn pre_authorized_code_grant_polling_interval() {
+        let grant: PreAuthorizedCodeGrant = serde_json::from_value(json!({
+            "pre-authorized_code": "adhjhdjajkdkhjhdj"
+        }))
+        .unwrap();
+        assert_eq!(grant.polling_interval(), Duration::from_secs(5));
+
+        let grant = grant.set_interval(Some(10));
+        assert_eq!(grant.polling_interval(), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn credential_configuration_ids_one_or_many() {
+        for credential_configuration_ids in [
+            json!("UniversityDegreeCredential"),
+            json!(["UniversityDegreeCredential"]),
+        ] {
+            let offer: CredentialOfferParameters = serde_json::from_value(json!({
+                "credential_issuer": "https://credential-issuer.example.com",
+                "credential_configuration_ids": credential_configuration_ids
+            }))
+            .unwrap();
+            assert_eq!(
+                offer.credential_configuration_ids(),
+                [CredentialConfigurationId::new(
+                    "UniversityDegreeCredential".into()
+                )]
+            );
+            assert_json_diff::assert_json_eq!(
+                serde_json::to_value(&offer).unwrap(),
+                json!({
+                    "credential_issuer": "https://credential-issuer.example.com",
+                    "credential_configuration_ids": ["UniversityDegreeCredential"]
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn validate_against_metadata() {
+        let metadata: crate::profiles::core::metadata::CredentialIssuerMetadata =
+            serde_json::from_value(json!({
+                "credential_issuer": "https://credential-issuer.example.com",
+                "credential_endpoint": "https://credential-issuer.example.com/credential",
+                "credential_configurations_supported": {
+                    "UniversityDegreeCredential": {
+                        "format": "jwt_vc_json",
+                        "credential_definition": {
+                            "type": ["VerifiableCredential", "UniversityDegreeCredential"]
+                        }
+                    },
+                    "org.iso.18013.5.1.mDL": {
+                        "format": "mso_mdoc",
+                        "doctype": "org.iso.18013.5.1.mDL"
+                    }
+                }
+            }))
+            .unwrap();
+        let offer = |ids: &[&str]| {
+            CredentialOfferParameters::new(
+                IssuerUrl::new("https://credential-issuer.example.com".into()).unwrap(),
+                ids.iter()
+                    .map(|id| CredentialConfigurationId::new(id.to_string()))
+                    .collect(),
+                None,
+            )
+        };
+
+        offer(&["UniversityDegreeCredential", "org.iso.18013.5.1.mDL"])
+            .validate_against_metadata(&metadata)
+            .unwrap();
+
+        let UnknownCredentialConfigurations(unknown) =
+            offer(&["UniversityDegreeCredential", "EmployeeBadge"])
+                .validate_against_metadata(&metadata)
+                .unwrap_err();
+        assert_eq!(
+            unknown,
+            vec![CredentialConfigurationId::new("EmployeeBadge".into())]
+        );
+    }
+
+    #[test]
+    fn inline_credential_configuration() {
+        let offer: CredentialOfferParameters = serde_json::from_value(json!({
+            "credential_issuer": "https://credential-issuer.example.com",
+            "credential_configuration_ids": ["UniversityDegreeCredential"],
+            "credential_configurations": {
+                "UniversityDegreeCredential": {
+                    "format": "jwt_vc_json",
+                    "credential_definition": {
+                        "type": ["VerifiableCredential", "UniversityDegreeCredential"]
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let configurations = offer.configurations_for_offer(None).unwrap();
+        assert_eq!(configurations.len(), 1);
+        assert_eq!(
+            configurations[0].id().as_str(),
+            "UniversityDegreeCredential"
+        );
+        assert!(matches!(
+            configurations[0].profile_specific_fields(),
+            CoreProfilesCredentialConfiguration::JwtVcJson(_)
+        ));
+
+        // The inlined configurations take precedence over those of the issuer metadata.
+        let metadata: crate::profiles::core::metadata::CredentialIssuerMetadata =
+            serde_json::from_value(json!({
+                "credential_issuer": "https://credential-issuer.example.com",
+                "credential_endpoint": "https://credential-issuer.example.com/credential",
+                "credential_configurations_supported": {
+                    "UniversityDegreeCredential": {
+                        "format": "mso_mdoc",
+                        "doctype": "org.iso.18013.5.1.mDL"
+                    }
+                }
+            }))
+            .unwrap();
+        let configurations = offer.configurations_for_offer(Some(&metadata)).unwrap();
+        assert!(matches!(
+            configurations[0].profile_specific_fields(),
+            CoreProfilesCredentialConfiguration::JwtVcJson(_)
+        ));
+
+        let offer = offer.set_credential_configurations(None);
+        offer.configurations_for_offer(None).unwrap_err();
+        let configurations = offer.configurations_for_offer(Some(&metadata)).unwrap();
+        assert!(matches!(
+            configurations[0].profile_specific_fields(),
+            CoreProfilesCredentialConfiguration::MsoMdoc(_)
+        ));
+    }
+
+    #[test]
+    fn inline_credential_configuration_unknown_format() {
+        let offer: CredentialOfferParameters = serde_json::from_value(json!({
+            "credential_issuer": "https://credential-issuer.example.com",
+            "credential_configuration_ids": ["UniversityDegreeCredential", "EmployeeBadge"],
+            "credential_configurations": {
+                "UniversityDegreeCredential": {
+                    "format": "jwt_vc_json",
+                    "credential_definition": {
+                        "type": ["VerifiableCredential", "UniversityDegreeCredential"]
+                    }
+                },
+                "EmployeeBadge": {
+                    "format": "example_format",
+                    "badge_type": "employee"
+                }
+            }
+        }))
+        .unwrap();
+
+        assert!(offer
+            .inline_configuration(&CredentialConfigurationId::new(
+                "UniversityDegreeCredential".into()
+            ))
+            .is_some());
+        assert!(offer
+            .inline_configuration(&CredentialConfigurationId::new("EmployeeBadge".into()))
+            .is_none());
+        offer.configurations_for_offer(None).unwrap_err();
+    }
+
     #[test]
     fn example_credential_offer_object() {
         let _: CredentialOfferParameters = serde_json::from_value(json!({
@@ -316,4 +1208,483 @@ mod test {
         }))
         .unwrap();
     }
+
+    #[test]
+    fn example_credential_offer_object_previous_drafts() {
+        let offer: CredentialOfferParameters = serde_json::from_value(json!({
+           "credential_issuer": "https://credential-issuer.example.com",
+           "credentials": [
+              "UniversityDegreeCredential"
+           ],
+           "grants": {
+              "urn:ietf:params:oauth:grant-type:pre-authorized_code": {
+                 "pre-authorized_code": "adhjhdjajkdkhjhdj",
+                 "user_pin_required": true
+              }
+           }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            offer.credential_configuration_ids(),
+            [CredentialConfigurationId::new(
+                "UniversityDegreeCredential".into()
+            )]
+        );
+        assert!(offer
+            .pre_authorized_code_grant()
+            .unwrap()
+            .tx_code()
+            .is_some());
+
+        let offer: CredentialOfferParameters = serde_json::from_value(json!({
+           "credential_issuer": "https://credential-issuer.example.com",
+           "credentials": [
+              "UniversityDegreeCredential"
+           ],
+           "grants": {
+              "urn:ietf:params:oauth:grant-type:pre-authorized_code": {
+                 "pre-authorized_code": "adhjhdjajkdkhjhdj",
+                 "user_pin_required": false
+              }
+           }
+        }))
+        .unwrap();
+
+        assert!(offer
+            .pre_authorized_code_grant()
+            .unwrap()
+            .tx_code()
+            .is_none());
+    }
+
+    fn signed_credential_offer(issuer: &str) -> String {
+        sign_credential_offer(json!({
+            "iss": issuer,
+            "credential_issuer": "https://credential-issuer.example.com",
+            "credential_configuration_ids": ["UniversityDegreeCredential"]
+        }))
+    }
+
+    const ISSUER_KID: &str = "key-1";
+
+    fn issuer_jwk() -> JWK {
+        serde_json::from_value(json!({"kty":"OKP","crv":"Ed25519","x":"h3GzIK3pU8oTspVBKstiPSHR3VH_USS2FA0NrAOZ51s","d":"pfYMFvJ-LlMO4-EBBsrjpfAVz5UEYNVgbTphLPZypbE"})).unwrap()
+    }
+
+    fn sign_credential_offer(payload: Value) -> String {
+        let jwk = issuer_jwk();
+        let header = Header {
+            algorithm: jwk.get_algorithm().unwrap(),
+            key_id: Some(ISSUER_KID.to_string()),
+            ..Default::default()
+        };
+        jws::encode_sign_custom_header(&payload.to_string(), &jwk, &header).unwrap()
+    }
+
+    /// Serves the signed credential offer `jwt`, and the JWT VC issuer metadata and JWK Set of
+    /// the credential issuer.
+    fn credential_issuer_response(
+        request: &http::Request<Vec<u8>>,
+        jwt: &str,
+    ) -> http::Response<Vec<u8>> {
+        let mut jwk = issuer_jwk().to_public();
+        jwk.key_id = Some(ISSUER_KID.to_string());
+        let (content_type, body) = match request.uri().to_string().as_str() {
+            "https://credential-issuer.example.com/credential-offer/1" => {
+                (MIME_TYPE_JWT, jwt.as_bytes().to_vec())
+            }
+            "https://credential-issuer.example.com/.well-known/jwt-vc-issuer" => (
+                MIME_TYPE_JSON,
+                serde_json::to_vec(&json!({
+                    "issuer": "https://credential-issuer.example.com",
+                    "jwks_uri": "https://credential-issuer.example.com/jwks.json"
+                }))
+                .unwrap(),
+            ),
+            "https://credential-issuer.example.com/jwks.json" => (
+                MIME_TYPE_JWK_SET,
+                serde_json::to_vec(&json!({ "keys": [jwk] })).unwrap(),
+            ),
+            _ => return http::Response::builder().status(404).body(vec![]).unwrap(),
+        };
+        http::Response::builder()
+            .status(200)
+            .header(CONTENT_TYPE, content_type)
+            .body(body)
+            .unwrap()
+    }
+
+    fn credential_offer_reference() -> CredentialOffer {
+        CredentialOffer::Reference {
+            credential_offer_uri: "https://credential-issuer.example.com/credential-offer/1"
+                .parse()
+                .unwrap(),
+        }
+    }
+
+    async fn resolve_signed(jwt: String) -> Result<CredentialOfferParameters> {
+        resolve_signed_with_options(jwt, VerificationOptions::default()).await
+    }
+
+    async fn resolve_signed_with_options(
+        jwt: String,
+        options: VerificationOptions,
+    ) -> Result<CredentialOfferParameters> {
+        let http_client = move |request: http::Request<Vec<u8>>| {
+            let response: Result<_, std::io::Error> =
+                Ok(credential_issuer_response(&request, &jwt));
+            async move { response }
+        };
+
+        credential_offer_reference()
+            .resolve_signed_async_with_options(&http_client, options)
+            .await
+    }
+
+    #[tokio::test]
+    async fn resolve_signed_credential_offer() {
+        let credential_offer = resolve_signed(signed_credential_offer(
+            "https://credential-issuer.example.com",
+        ))
+        .await
+        .unwrap();
+
+        assert_eq!(
+            credential_offer.issuer().as_str(),
+            "https://credential-issuer.example.com"
+        );
+        assert_eq!(
+            credential_offer.credential_configuration_ids(),
+            [CredentialConfigurationId::new(
+                "UniversityDegreeCredential".into()
+            )]
+        );
+    }
+
+    #[test]
+    fn resolve_signed_credential_offer_sync() {
+        let jwt = signed_credential_offer("https://credential-issuer.example.com");
+        let http_client = |request: http::Request<Vec<u8>>| -> Result<_, std::io::Error> {
+            Ok(credential_issuer_response(&request, &jwt))
+        };
+
+        let credential_offer = credential_offer_reference()
+            .resolve_signed(&http_client)
+            .unwrap();
+        assert_eq!(
+            credential_offer.issuer().as_str(),
+            "https://credential-issuer.example.com"
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_signed_credential_offer_issuer_mismatch() {
+        let err = resolve_signed(signed_credential_offer("https://attacker.example.com"))
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("does not match credential issuer"));
+    }
+
+    #[tokio::test]
+    async fn resolve_signed_credential_offer_attacker_key() {
+        let payload = json!({
+            "iss": "https://credential-issuer.example.com",
+            "credential_issuer": "https://credential-issuer.example.com",
+            "credential_configuration_ids": ["UniversityDegreeCredential"]
+        })
+        .to_string();
+        let attacker_jwk = JWK::generate_ed25519().unwrap();
+        let algorithm = attacker_jwk.get_algorithm().unwrap();
+
+        let embedded = Header {
+            algorithm,
+            jwk: Some(attacker_jwk.to_public()),
+            ..Default::default()
+        };
+        let jwt = jws::encode_sign_custom_header(&payload, &attacker_jwk, &embedded).unwrap();
+        let err = resolve_signed(jwt).await.unwrap_err();
+        assert!(err.to_string().contains("`jwk` header"));
+
+        // The key must be published by the credential issuer, whatever it resolves to.
+        let did_jwk = Header {
+            algorithm,
+            key_id: Some(
+                ssi::dids::jwk::DIDJWK::generate_url(&attacker_jwk.to_public()).to_string(),
+            ),
+            ..Default::default()
+        };
+        let jwt = jws::encode_sign_custom_header(&payload, &attacker_jwk, &did_jwk).unwrap();
+        let err = resolve_signed(jwt).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("is not published by credential issuer"));
+
+        let issuer_kid = Header {
+            algorithm,
+            key_id: Some(ISSUER_KID.to_string()),
+            ..Default::default()
+        };
+        let jwt = jws::encode_sign_custom_header(&payload, &attacker_jwk, &issuer_kid).unwrap();
+        let err = resolve_signed(jwt).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("failed to verify credential offer JWT"));
+    }
+
+    #[tokio::test]
+    async fn resolve_signed_credential_offer_leeway() {
+        let expired = || {
+            sign_credential_offer(json!({
+                "iss": "https://credential-issuer.example.com",
+                "credential_issuer": "https://credential-issuer.example.com",
+                "credential_configuration_ids": ["UniversityDegreeCredential"],
+                "exp": (OffsetDateTime::now_utc() - time::Duration::seconds(30)).unix_timestamp()
+            }))
+        };
+
+        let err = resolve_signed(expired()).await.unwrap_err();
+        assert!(err.to_string().contains("expired"));
+
+        resolve_signed_with_options(
+            expired(),
+            VerificationOptions::new(time::Duration::seconds(60)),
+        )
+        .await
+        .unwrap();
+
+        let err = resolve_signed_with_options(
+            expired(),
+            VerificationOptions::new(time::Duration::seconds(10)),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("expired"));
+    }
+
+    #[test]
+    fn verification_options_not_before() {
+        let now = OffsetDateTime::now_utc();
+        let options = VerificationOptions::new(time::Duration::seconds(60));
+        let not_before = Some(now + time::Duration::seconds(30));
+
+        assert!(VerificationOptions::default()
+            .check_validity(now, not_before, None, None)
+            .is_err());
+        assert!(options.check_validity(now, not_before, None, None).is_ok());
+        assert!(options.check_validity(now, None, not_before, None).is_ok());
+        assert!(options
+            .check_validity(now, Some(now + time::Duration::minutes(2)), None, None)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn signed_credential_offer_request() {
+        let mut jwk = issuer_jwk();
+        jwk.key_id = Some(ISSUER_KID.to_string());
+        let credential_offer: CredentialOfferParameters = serde_json::from_value(json!({
+            "credential_issuer": "https://credential-issuer.example.com",
+            "credential_configuration_ids": ["UniversityDegreeCredential"],
+            "grants": {
+                "urn:ietf:params:oauth:grant-type:pre-authorized_code": {
+                    "pre-authorized_code": "adhjhdjajkdkhjhdj"
+                }
+            }
+        }))
+        .unwrap();
+
+        let request = credential_offer.to_signed_request(&jwk).unwrap();
+        assert!(request
+            .url()
+            .as_str()
+            .starts_with("openid-credential-offer://?credential_offer=ey"));
+        assert!(
+            CredentialOffer::<CoreProfilesCredentialConfiguration>::from_request(request.clone())
+                .is_err()
+        );
+
+        let http_client = |request: http::Request<Vec<u8>>| -> Result<_, std::io::Error> {
+            Ok(credential_issuer_response(&request, ""))
+        };
+        let CredentialOffer::Value {
+            credential_offer: verified,
+        } = CredentialOffer::<CoreProfilesCredentialConfiguration>::from_request_signed(
+            request.clone(),
+            &http_client,
+        )
+        .unwrap()
+        else {
+            panic!("expected a credential offer by value");
+        };
+        assert_eq!(verified.issuer(), credential_offer.issuer());
+        assert_eq!(
+            verified
+                .pre_authorized_code_grant()
+                .unwrap()
+                .pre_authorized_code()
+                .secret(),
+            "adhjhdjajkdkhjhdj"
+        );
+
+        let async_http_client = |request: http::Request<Vec<u8>>| {
+            let response: Result<_, std::io::Error> = Ok(credential_issuer_response(&request, ""));
+            async move { response }
+        };
+        let verified =
+            CredentialOffer::<CoreProfilesCredentialConfiguration>::from_request_signed_async(
+                request,
+                &async_http_client,
+            )
+            .await
+            .unwrap();
+        assert!(matches!(verified, CredentialOffer::Value { .. }));
+
+        jwk.key_id = None;
+        assert!(credential_offer.to_signed_request(&jwk).is_err());
+    }
+
+    #[test]
+    fn unsigned_credential_offer_request_rejected() {
+        let request = CredentialOfferRequest::from_url(
+            Url::parse(
+                "openid-credential-offer://?credential_offer=%7B%22credential_issuer%22:%22https://credential-issuer.example.com%22,%22credential_configuration_ids%22:%5B%22UniversityDegreeCredential%22%5D%7D",
+            )
+            .unwrap(),
+        );
+        assert!(
+            CredentialOffer::<CoreProfilesCredentialConfiguration>::from_request(request.clone())
+                .is_ok()
+        );
+
+        let no_request =
+            |_: http::Request<Vec<u8>>| -> Result<http::Response<Vec<u8>>, std::io::Error> {
+                panic!("an unsigned credential offer must be rejected without any request")
+            };
+        let err = CredentialOffer::<CoreProfilesCredentialConfiguration>::from_request_signed(
+            request,
+            &no_request,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("not a signed JWT"));
+    }
+
+    #[test]
+    fn available_grant_types() {
+        let offer: CredentialOfferParameters = serde_json::from_value(json!({
+            "credential_issuer": "https://credential-issuer.example.com",
+            "credential_configuration_ids": ["UniversityDegreeCredential"],
+            "grants": {
+                "authorization_code": {
+                    "issuer_state": "eyJhbGciOiJSU0Et...FYUaBy"
+                },
+                "urn:ietf:params:oauth:grant-type:pre-authorized_code": {
+                    "pre-authorized_code": "adhjhdjajkdkhjhdj",
+                    "tx_code": {
+                        "length": 4
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        let grants = offer.grants().unwrap();
+
+        assert_eq!(
+            grants.available_grant_types(),
+            [GrantType::AuthorizationCode, GrantType::PreAuthorizedCode]
+        );
+        assert!(grants.requires_tx_code());
+
+        let grants = grants.clone().set_pre_authorized_code(None);
+        assert_eq!(
+            grants.available_grant_types(),
+            [GrantType::AuthorizationCode]
+        );
+        assert!(!grants.requires_tx_code());
+    }
+
+    #[test]
+    fn tx_code_prompt_numeric() {
+        let tx_code: TxCodeDefinition = serde_json::from_value(json!({
+            "length": 4,
+            "input_mode": "numeric",
+            "description": "Please provide the one-time code that was sent via e-mail"
+        }))
+        .unwrap();
+
+        let prompt = tx_code.prompt();
+        assert_eq!(
+            prompt,
+            TxCodePrompt {
+                numeric: true,
+                expected_length: Some(4),
+                description: Some("Please provide the one-time code that was sent via e-mail"),
+            }
+        );
+        assert_eq!(prompt.validate("1234"), Ok(()));
+        assert_eq!(prompt.validate("12a4"), Err(TxCodeError::NotNumeric));
+        assert_eq!(
+            prompt.validate("12345"),
+            Err(TxCodeError::InvalidLength {
+                expected: 4,
+                actual: 5
+            })
+        );
+        assert_eq!(prompt.validate(""), Err(TxCodeError::Empty));
+    }
+
+    #[test]
+    fn tx_code_prompt_text() {
+        let tx_code: TxCodeDefinition = serde_json::from_value(json!({
+            "input_mode": "text"
+        }))
+        .unwrap();
+
+        let prompt = tx_code.prompt();
+        assert!(!prompt.numeric);
+        assert_eq!(prompt.expected_length, None);
+        assert_eq!(prompt.description, None);
+        assert_eq!(prompt.validate("a1-B2"), Ok(()));
+    }
+
+    #[test]
+    fn tx_code_prompt_default_input_mode() {
+        let prompt = TxCodeDefinition::new(None, Some(6), None).prompt();
+        assert!(prompt.numeric);
+        assert_eq!(prompt.validate("123456"), Ok(()));
+    }
+
+    #[test]
+    fn from_query_str() {
+        let credential_offer: CredentialOffer = CredentialOffer::from_query_str(
+            "credential_offer=%7B%22credential_issuer%22%3A%22https%3A%2F%2Fcredential-issuer.example.com%22%2C%22credential_configuration_ids%22%3A%5B%22UniversityDegree_JWT%22%5D%7D",
+        )
+        .unwrap();
+        let CredentialOffer::Value { credential_offer } = credential_offer else {
+            panic!("expected a credential offer by value");
+        };
+        assert_eq!(
+            credential_offer.issuer().as_str(),
+            "https://credential-issuer.example.com"
+        );
+        assert_eq!(
+            credential_offer.credential_configuration_ids()[0].as_str(),
+            "UniversityDegree_JWT"
+        );
+
+        let credential_offer: CredentialOffer = CredentialOffer::from_query_str(
+            "?credential_offer_uri=https%3A%2F%2Fserver.example.com%2Fcredential-offer%2F1",
+        )
+        .unwrap();
+        assert!(matches!(
+            credential_offer,
+            CredentialOffer::Reference { credential_offer_uri }
+                if credential_offer_uri.as_str() == "https://server.example.com/credential-offer/1"
+        ));
+
+        assert!(
+            CredentialOffer::<CoreProfilesCredentialConfiguration>::from_query_str("tenant=1")
+                .is_err()
+        );
+    }
 }
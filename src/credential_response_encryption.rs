@@ -1,5 +1,8 @@
+use std::io::{Read, Write};
+
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
 use serde::{Deserialize, Serialize};
-use ssi::jwk::JWK;
+use ssi::jwk::{Params, JWK};
 
 pub use crate::types::{BatchCredentialUrl, CredentialUrl, DeferredCredentialUrl, ParUrl};
 
@@ -7,14 +10,188 @@ pub use crate::types::{BatchCredentialUrl, CredentialUrl, DeferredCredentialUrl,
 pub struct CredentialResponseEncryptionMetadata {
     alg_values_supported: Vec<Alg>,
     enc_values_supported: Vec<Enc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    zip_values_supported: Option<Vec<Zip>>,
     encryption_required: bool,
 }
 
+impl CredentialResponseEncryptionMetadata {
+    pub fn new(
+        alg_values_supported: Vec<Alg>,
+        enc_values_supported: Vec<Enc>,
+        encryption_required: bool,
+    ) -> Self {
+        Self {
+            alg_values_supported,
+            enc_values_supported,
+            zip_values_supported: None,
+            encryption_required,
+        }
+    }
+
+    field_getters_setters![
+        pub self [self] ["credential response encryption metadata value"] {
+            set_alg_values_supported -> alg_values_supported[Vec<Alg>],
+            set_enc_values_supported -> enc_values_supported[Vec<Enc>],
+            set_zip_values_supported -> zip_values_supported[Option<Vec<Zip>>],
+            set_encryption_required -> encryption_required[bool],
+        }
+    ];
+
+    /// Selects the `alg` and `enc` values to use for credential response encryption.
+    ///
+    /// The wallet's preference order is honored, `alg` taking precedence over `enc`. Returns
+    /// `None` if no preferred value is supported by the credential issuer.
+    pub fn select(&self, preferred_algs: &[Alg], preferred_encs: &[Enc]) -> Option<(Alg, Enc)> {
+        let alg = preferred_algs
+            .iter()
+            .find(|alg| self.alg_values_supported.contains(alg))?;
+        let enc = preferred_encs
+            .iter()
+            .find(|enc| self.enc_values_supported.contains(enc))?;
+        Some((alg.clone(), enc.clone()))
+    }
+
+    /// Checks that the credential issuer supports the given `alg` and `enc` values.
+    pub fn check_supported(&self, alg: &Alg, enc: &Enc) -> Result<(), ResponseEncryptionError> {
+        if !self.alg_values_supported.contains(alg) {
+            return Err(ResponseEncryptionError::UnsupportedAlg(alg.clone()));
+        }
+        if !self.enc_values_supported.contains(enc) {
+            return Err(ResponseEncryptionError::UnsupportedEnc(enc.clone()));
+        }
+        Ok(())
+    }
+
+    /// Checks the response encryption parameters of a credential request, which must be set if
+    /// the credential issuer requires encryption.
+    pub fn check_request(
+        &self,
+        encryption: Option<&CredentialResponseEncryption>,
+    ) -> Result<(), ResponseEncryptionError> {
+        match encryption {
+            Some(encryption) => {
+                self.check_supported(&encryption.alg, &encryption.enc)?;
+                match &encryption.zip {
+                    Some(zip)
+                        if !self
+                            .zip_values_supported
+                            .as_ref()
+                            .is_some_and(|supported| supported.contains(zip)) =>
+                    {
+                        Err(ResponseEncryptionError::UnsupportedZip(zip.clone()))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            None if self.encryption_required => Err(ResponseEncryptionError::Required),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ResponseEncryptionError {
+    #[error("credential issuer requires credential response encryption")]
+    Required,
+    #[error(
+        "credential response encryption alg `{0:?}` is not supported by the credential issuer"
+    )]
+    UnsupportedAlg(Alg),
+    #[error(
+        "credential response encryption enc `{0:?}` is not supported by the credential issuer"
+    )]
+    UnsupportedEnc(Enc),
+    #[error(
+        "credential response compression zip `{0:?}` is not supported by the credential issuer"
+    )]
+    UnsupportedZip(Zip),
+    #[error("failed to compress or decompress the credential response")]
+    Compression(#[source] std::io::Error),
+    #[error("decompressed credential response exceeds {0} bytes")]
+    DecompressedTooLarge(usize),
+    #[error("credential response encryption key is not an encryption key: {0}")]
+    InvalidJwk(&'static str),
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct CredentialResponseEncryption {
     jwk: JWK,
     alg: Alg,
     enc: Enc,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    zip: Option<Zip>,
+}
+
+impl CredentialResponseEncryption {
+    pub fn new(jwk: JWK, alg: Alg, enc: Enc) -> Self {
+        Self {
+            jwk,
+            alg,
+            enc,
+            zip: None,
+        }
+    }
+
+    /// Same as [`CredentialResponseEncryption::new`], but checks that `jwk` is suitable for
+    /// encryption, see [`CredentialResponseEncryption::check_jwk`].
+    pub fn try_new(jwk: JWK, alg: Alg, enc: Enc) -> Result<Self, ResponseEncryptionError> {
+        let encryption = Self::new(jwk, alg, enc);
+        encryption.check_jwk()?;
+        Ok(encryption)
+    }
+
+    /// Checks that the key is an asymmetric key usable for key agreement or key encryption,
+    /// rejecting keys restricted to signing by their `use` or `key_ops` parameters.
+    pub fn check_jwk(&self) -> Result<(), ResponseEncryptionError> {
+        match &self.jwk.params {
+            Params::EC(_) | Params::RSA(_) => {}
+            Params::OKP(params) if params.curve.starts_with('X') => {}
+            Params::OKP(_) => {
+                return Err(ResponseEncryptionError::InvalidJwk(
+                    "OKP key must be on an X25519 or X448 curve",
+                ))
+            }
+            Params::Symmetric(_) => {
+                return Err(ResponseEncryptionError::InvalidJwk(
+                    "symmetric keys are not supported",
+                ))
+            }
+        }
+
+        if self
+            .jwk
+            .public_key_use
+            .as_deref()
+            .is_some_and(|key_use| key_use != "enc")
+        {
+            return Err(ResponseEncryptionError::InvalidJwk("`use` must be `enc`"));
+        }
+
+        if self.jwk.key_operations.as_ref().is_some_and(|key_ops| {
+            !key_ops.iter().any(|op| {
+                matches!(
+                    op.as_str(),
+                    "encrypt" | "wrapKey" | "deriveKey" | "deriveBits"
+                )
+            })
+        }) {
+            return Err(ResponseEncryptionError::InvalidJwk(
+                "`key_ops` does not allow encryption",
+            ));
+        }
+
+        Ok(())
+    }
+
+    field_getters_setters![
+        pub self [self] ["credential response encryption value"] {
+            set_jwk -> jwk[JWK],
+            set_alg -> alg[Alg],
+            set_enc -> enc[Enc],
+            set_zip -> zip[Option<Zip>],
+        }
+    ];
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -28,3 +205,220 @@ pub enum Enc {
     #[serde(untagged)]
     Other(String),
 }
+
+/// Compression algorithm applied to the plaintext of the encrypted credential response, i.e.
+/// the JWE `zip` header.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Zip {
+    /// Raw DEFLATE, see [RFC1951](https://www.rfc-editor.org/rfc/rfc1951).
+    #[serde(rename = "DEF")]
+    Deflate,
+    #[serde(untagged)]
+    Other(String),
+}
+
+impl Zip {
+    /// Size limit of a decompressed credential response, see [`Zip::decompress`].
+    pub const MAX_DECOMPRESSED_SIZE: usize = 16 * 1024 * 1024;
+
+    /// Compresses the plaintext of the credential response before encryption.
+    pub fn compress(&self, plaintext: &[u8]) -> Result<Vec<u8>, ResponseEncryptionError> {
+        match self {
+            Self::Deflate => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder
+                    .write_all(plaintext)
+                    .map_err(ResponseEncryptionError::Compression)?;
+                encoder
+                    .finish()
+                    .map_err(ResponseEncryptionError::Compression)
+            }
+            Self::Other(_) => Err(ResponseEncryptionError::UnsupportedZip(self.clone())),
+        }
+    }
+
+    /// Decompresses the decrypted plaintext of a credential response whose JWE `zip` header is
+    /// this algorithm.
+    ///
+    /// Fails if the plaintext exceeds [`Zip::MAX_DECOMPRESSED_SIZE`], to guard against
+    /// decompression bombs.
+    pub fn decompress(&self, compressed: &[u8]) -> Result<Vec<u8>, ResponseEncryptionError> {
+        self.decompress_with_limit(compressed, Self::MAX_DECOMPRESSED_SIZE)
+    }
+
+    /// Same as [`Zip::decompress`], with a custom size limit, in bytes.
+    pub fn decompress_with_limit(
+        &self,
+        compressed: &[u8],
+        limit: usize,
+    ) -> Result<Vec<u8>, ResponseEncryptionError> {
+        match self {
+            Self::Deflate => {
+                let mut plaintext = Vec::new();
+                // One byte past the limit tells a plaintext at the limit from a larger one.
+                DeflateDecoder::new(compressed)
+                    .take(limit as u64 + 1)
+                    .read_to_end(&mut plaintext)
+                    .map_err(ResponseEncryptionError::Compression)?;
+                if plaintext.len() > limit {
+                    return Err(ResponseEncryptionError::DecompressedTooLarge(limit));
+                }
+                Ok(plaintext)
+            }
+            Self::Other(_) => Err(ResponseEncryptionError::UnsupportedZip(self.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn check_request() {
+        let metadata: CredentialResponseEncryptionMetadata = serde_json::from_value(json!({
+            "alg_values_supported": ["ECDH-ES"],
+            "enc_values_supported": ["A128GCM"],
+            "encryption_required": true
+        }))
+        .unwrap();
+        let jwk = JWK::generate_p256();
+        let encryption = |alg: &str, enc: &str| {
+            CredentialResponseEncryption::new(
+                jwk.to_public(),
+                Alg::Other(alg.into()),
+                Enc::Other(enc.into()),
+            )
+        };
+
+        assert!(matches!(
+            metadata.check_request(None),
+            Err(ResponseEncryptionError::Required)
+        ));
+        assert!(matches!(
+            metadata.check_request(Some(&encryption("RSA-OAEP-256", "A128GCM"))),
+            Err(ResponseEncryptionError::UnsupportedAlg(_))
+        ));
+        assert!(matches!(
+            metadata.check_request(Some(&encryption("ECDH-ES", "A256GCM"))),
+            Err(ResponseEncryptionError::UnsupportedEnc(_))
+        ));
+        metadata
+            .check_request(Some(&encryption("ECDH-ES", "A128GCM")))
+            .unwrap();
+
+        metadata
+            .set_encryption_required(false)
+            .check_request(None)
+            .unwrap();
+    }
+
+    #[test]
+    fn check_jwk() {
+        let encryption = |jwk: JWK| {
+            CredentialResponseEncryption::try_new(
+                jwk,
+                Alg::Other("ECDH-ES".into()),
+                Enc::Other("A128GCM".into()),
+            )
+        };
+        let mut jwk = JWK::generate_p256().to_public();
+
+        jwk.public_key_use = Some("enc".into());
+        encryption(jwk.clone()).unwrap();
+
+        jwk.public_key_use = Some("sig".into());
+        assert!(matches!(
+            encryption(jwk.clone()),
+            Err(ResponseEncryptionError::InvalidJwk(_))
+        ));
+
+        jwk.public_key_use = None;
+        jwk.key_operations = Some(vec!["verify".into()]);
+        assert!(matches!(
+            encryption(jwk),
+            Err(ResponseEncryptionError::InvalidJwk(_))
+        ));
+
+        assert!(matches!(
+            encryption(JWK::generate_ed25519().unwrap().to_public()),
+            Err(ResponseEncryptionError::InvalidJwk(_))
+        ));
+    }
+
+    #[test]
+    fn zip() {
+        let metadata: CredentialResponseEncryptionMetadata = serde_json::from_value(json!({
+            "alg_values_supported": ["ECDH-ES"],
+            "enc_values_supported": ["A128GCM"],
+            "zip_values_supported": ["DEF"],
+            "encryption_required": true
+        }))
+        .unwrap();
+        assert_eq!(metadata.zip_values_supported(), Some(&vec![Zip::Deflate]));
+
+        let encryption = CredentialResponseEncryption::new(
+            JWK::generate_p256().to_public(),
+            Alg::Other("ECDH-ES".into()),
+            Enc::Other("A128GCM".into()),
+        );
+        metadata
+            .check_request(Some(&encryption.clone().set_zip(Some(Zip::Deflate))))
+            .unwrap();
+        assert!(matches!(
+            metadata.check_request(Some(&encryption.set_zip(Some(Zip::Other("GZIP".into()))))),
+            Err(ResponseEncryptionError::UnsupportedZip(_))
+        ));
+
+        let response = serde_json::to_vec(&json!({
+            "credential": "LUpixVCWJk0eOt4CXQe1NXK....WZwmhmn9OQp6YxX0a2L",
+            "notification_id": "3fwe98js"
+        }))
+        .unwrap();
+        let compressed = Zip::Deflate.compress(&response).unwrap();
+        assert_ne!(compressed, response);
+        assert_eq!(Zip::Deflate.decompress(&compressed).unwrap(), response);
+
+        assert_eq!(
+            Zip::Deflate
+                .decompress_with_limit(&compressed, response.len())
+                .unwrap(),
+            response
+        );
+        assert!(matches!(
+            Zip::Deflate.decompress_with_limit(&compressed, response.len() - 1),
+            Err(ResponseEncryptionError::DecompressedTooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn select() {
+        let metadata: CredentialResponseEncryptionMetadata = serde_json::from_value(json!({
+            "alg_values_supported": ["ECDH-ES", "RSA-OAEP-256"],
+            "enc_values_supported": ["A128GCM", "A256GCM"],
+            "encryption_required": true
+        }))
+        .unwrap();
+
+        let alg = |alg: &str| Alg::Other(alg.into());
+        let enc = |enc: &str| Enc::Other(enc.into());
+
+        assert_eq!(
+            metadata.select(
+                &[alg("ECDH-ES+A256KW"), alg("RSA-OAEP-256"), alg("ECDH-ES")],
+                &[enc("A256GCM"), enc("A128GCM")]
+            ),
+            Some((alg("RSA-OAEP-256"), enc("A256GCM")))
+        );
+        assert_eq!(
+            metadata.select(&[alg("ECDH-ES+A256KW")], &[enc("A256GCM")]),
+            None
+        );
+        assert_eq!(
+            metadata.select(&[alg("ECDH-ES")], &[enc("A128CBC-HS256")]),
+            None
+        );
+    }
+}
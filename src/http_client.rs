@@ -0,0 +1,252 @@
+use std::{future::Future, pin::Pin, time::Duration};
+
+use oauth2::{
+    http::{self, Method},
+    AsyncHttpClient, HttpRequest, HttpResponse, SyncHttpClient,
+};
+
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Function awaited by [`RetryHttpClient`] between two attempts of an asynchronous request, so
+/// that the caller can use the timer of its runtime, e.g. `tokio::time::sleep`.
+pub trait AsyncSleep {
+    type Future: Future<Output = ()>;
+
+    fn sleep(&self, duration: Duration) -> Self::Future;
+}
+
+impl<S, F> AsyncSleep for S
+where
+    S: Fn(Duration) -> F,
+    F: Future<Output = ()>,
+{
+    type Future = F;
+
+    fn sleep(&self, duration: Duration) -> F {
+        self(duration)
+    }
+}
+
+/// HTTP client wrapper retrying failed requests with an exponential backoff.
+///
+/// Only `GET` requests, such as metadata discovery, are retried, when the inner client fails or
+/// when the response has a `5xx` status code. Other requests, in particular token and credential
+/// requests, are never retried as they are not idempotent.
+///
+/// Synchronous requests wait with [`std::thread::sleep`]. As this crate does not depend on an
+/// async runtime, the client is only an [`AsyncHttpClient`] once a `sleep` function is set with
+/// [`RetryHttpClient::set_sleep`].
+#[derive(Clone, Debug)]
+pub struct RetryHttpClient<C, S = ()> {
+    inner: C,
+    max_retries: u32,
+    initial_backoff: Duration,
+    sleep: S,
+}
+
+impl<C> RetryHttpClient<C> {
+    pub fn new(inner: C, max_retries: u32) -> Self {
+        Self {
+            inner,
+            max_retries,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            sleep: (),
+        }
+    }
+}
+
+impl<C, S> RetryHttpClient<C, S> {
+    field_getters_setters![
+        pub self [self] ["retry HTTP client value"] {
+            set_max_retries -> max_retries[u32],
+            set_initial_backoff -> initial_backoff[Duration],
+        }
+    ];
+
+    /// Sets the function awaited between two attempts of an asynchronous request.
+    pub fn set_sleep<T>(self, sleep: T) -> RetryHttpClient<C, T>
+    where
+        T: AsyncSleep,
+    {
+        RetryHttpClient {
+            inner: self.inner,
+            max_retries: self.max_retries,
+            initial_backoff: self.initial_backoff,
+            sleep,
+        }
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Returns the delay before the given retry, starting at 1, doubling at each retry.
+    fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+    }
+}
+
+fn is_retryable(request: &HttpRequest) -> bool {
+    request.method() == Method::GET
+}
+
+fn should_retry<E>(result: &Result<HttpResponse, E>) -> bool {
+    match result {
+        Ok(response) => response.status().is_server_error(),
+        Err(_) => true,
+    }
+}
+
+fn clone_request(request: &HttpRequest) -> HttpRequest {
+    let mut clone = http::Request::new(request.body().clone());
+    *clone.method_mut() = request.method().clone();
+    *clone.uri_mut() = request.uri().clone();
+    *clone.version_mut() = request.version();
+    *clone.headers_mut() = request.headers().clone();
+    clone
+}
+
+impl<C, S> SyncHttpClient for RetryHttpClient<C, S>
+where
+    C: SyncHttpClient,
+{
+    type Error = C::Error;
+
+    fn call(&self, request: HttpRequest) -> Result<HttpResponse, Self::Error> {
+        if !is_retryable(&request) {
+            return self.inner.call(request);
+        }
+
+        let mut result = self.inner.call(clone_request(&request));
+        for retry in 1..=self.max_retries {
+            if !should_retry(&result) {
+                break;
+            }
+            std::thread::sleep(self.backoff(retry));
+            result = self.inner.call(clone_request(&request));
+        }
+        result
+    }
+}
+
+impl<'c, C, S> AsyncHttpClient<'c> for RetryHttpClient<C, S>
+where
+    C: AsyncHttpClient<'c>,
+    S: AsyncSleep + 'c,
+    S::Future: 'c,
+{
+    type Error = C::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<HttpResponse, Self::Error>> + 'c>>;
+
+    fn call(&'c self, request: HttpRequest) -> Self::Future {
+        Box::pin(async move {
+            if !is_retryable(&request) {
+                return self.inner.call(request).await;
+            }
+
+            let mut result = self.inner.call(clone_request(&request)).await;
+            for retry in 1..=self.max_retries {
+                if !should_retry(&result) {
+                    break;
+                }
+                self.sleep.sleep(self.backoff(retry)).await;
+                result = self.inner.call(clone_request(&request)).await;
+            }
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        cell::{Cell, RefCell},
+        future::ready,
+    };
+
+    use oauth2::http::StatusCode;
+
+    use super::*;
+
+    fn response(status: u16) -> HttpResponse {
+        http::Response::builder()
+            .status(status)
+            .body(vec![])
+            .unwrap()
+    }
+
+    fn request(method: Method) -> HttpRequest {
+        http::Request::builder()
+            .method(method)
+            .uri("https://example.com/.well-known/openid-credential-issuer")
+            .body(vec![])
+            .unwrap()
+    }
+
+    #[test]
+    fn retry_discovery() {
+        let attempts = Cell::new(0);
+        let http_client = |_: HttpRequest| -> Result<HttpResponse, std::io::Error> {
+            attempts.set(attempts.get() + 1);
+            match attempts.get() {
+                1 => Err(std::io::ErrorKind::TimedOut.into()),
+                2 => Ok(response(503)),
+                _ => Ok(response(200)),
+            }
+        };
+        let http_client =
+            RetryHttpClient::new(http_client, 3).set_initial_backoff(Duration::from_millis(1));
+
+        let response = SyncHttpClient::call(&http_client, request(Method::GET)).unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(attempts.get(), 3);
+
+        attempts.set(0);
+        let http_client = http_client.set_max_retries(1);
+        let response = SyncHttpClient::call(&http_client, request(Method::GET)).unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(attempts.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn retry_discovery_async() {
+        let attempts = Cell::new(0);
+        let http_client = |_: HttpRequest| {
+            attempts.set(attempts.get() + 1);
+            ready(match attempts.get() {
+                1 | 2 => Ok(response(500)),
+                _ => Ok::<_, std::io::Error>(response(200)),
+            })
+        };
+        let sleeps = RefCell::new(Vec::new());
+        let http_client = RetryHttpClient::new(http_client, 3).set_sleep(|delay: Duration| {
+            sleeps.borrow_mut().push(delay);
+            ready(())
+        });
+
+        let response = AsyncHttpClient::call(&http_client, request(Method::GET))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(attempts.get(), 3);
+        assert_eq!(
+            *sleeps.borrow(),
+            [DEFAULT_INITIAL_BACKOFF, DEFAULT_INITIAL_BACKOFF * 2]
+        );
+    }
+
+    #[test]
+    fn no_retry_token_request() {
+        let attempts = Cell::new(0);
+        let http_client = |_: HttpRequest| -> Result<HttpResponse, std::io::Error> {
+            attempts.set(attempts.get() + 1);
+            Ok(response(503))
+        };
+        let http_client = RetryHttpClient::new(http_client, 3);
+
+        let response = SyncHttpClient::call(&http_client, request(Method::POST)).unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(attempts.get(), 1);
+    }
+}
@@ -1,17 +1,25 @@
+use std::time::Duration;
+
 use anyhow::{bail, Result};
 use oauth2::{
     http::{
-        header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE},
+        self,
+        header::{HeaderMap, HeaderValue, AUTHORIZATION, CACHE_CONTROL, CONTENT_TYPE, PRAGMA},
         HeaderName,
     },
     AccessToken,
 };
 
 pub const MIME_TYPE_JSON: &str = "application/json";
+pub const MIME_TYPE_JWT: &str = "application/jwt";
+pub const MIME_TYPE_JWK_SET: &str = "application/jwk-set+json";
 pub const MIME_TYPE_FORM_URLENCODED: &str = "application/x-www-form-urlencoded";
 
 pub const BEARER: &str = "Bearer";
 
+pub const NO_STORE: &str = "no-store";
+pub const NO_CACHE: &str = "no-cache";
+
 // The [essence](https://mimesniff.spec.whatwg.org/#mime-type-essence) is the <type>/<subtype>
 // representation.
 pub fn content_type_has_essence(content_type: &HeaderValue, expected_essence: &str) -> bool {
@@ -44,6 +52,48 @@ pub fn check_content_type(headers: &HeaderMap, expected_content_type: &str) -> R
         )
 }
 
+/// Returns the freshness lifetime of a response from its `Cache-Control: max-age` directive.
+///
+/// Returns `None` if the response must not be cached, i.e. if there is no `max-age` directive or
+/// if `no-store` or `no-cache` are present.
+/// See https://www.rfc-editor.org/rfc/rfc9111#section-5.2.2.
+pub fn cache_max_age(headers: &HeaderMap) -> Option<Duration> {
+    let mut max_age = None;
+    for value in headers.get_all(CACHE_CONTROL) {
+        for directive in value.to_str().ok()?.split(',') {
+            let directive = directive.trim().to_lowercase();
+            if directive == NO_STORE || directive == NO_CACHE {
+                return None;
+            }
+            if let Some(seconds) = directive.strip_prefix("max-age=") {
+                max_age = seconds
+                    .trim_matches('"')
+                    .parse()
+                    .ok()
+                    .map(Duration::from_secs);
+            }
+        }
+    }
+    max_age.filter(|max_age| !max_age.is_zero())
+}
+
+/// Adds the `Cache-Control: no-store` and `Pragma: no-cache` headers to a request.
+///
+/// Token and credential requests and responses carry secrets (access tokens, nonces, credentials)
+/// and must not be persisted by HTTP caches.
+/// See https://www.rfc-editor.org/rfc/rfc6749#section-5.1.
+pub fn no_store(builder: http::request::Builder) -> http::request::Builder {
+    builder
+        .header(CACHE_CONTROL, HeaderValue::from_static(NO_STORE))
+        .header(PRAGMA, HeaderValue::from_static(NO_CACHE))
+}
+
+/// Same as [`no_store`], for an already built request.
+pub fn set_no_store(headers: &mut HeaderMap) {
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static(NO_STORE));
+    headers.insert(PRAGMA, HeaderValue::from_static(NO_CACHE));
+}
+
 pub fn auth_bearer(access_token: &AccessToken) -> (HeaderName, HeaderValue) {
     (
         AUTHORIZATION,
@@ -2,36 +2,44 @@
 mod macros;
 
 pub mod authorization;
+pub mod authorization_code;
 pub mod client;
 pub mod credential;
 pub mod credential_offer;
 pub mod credential_response_encryption;
 mod deny_field;
+pub mod http_client;
 mod http_utils;
 pub mod metadata;
 pub mod notification;
 pub mod pre_authorized_code;
+pub mod presentation;
 pub mod profiles;
 pub mod proof_of_possession;
 pub mod pushed_authorization;
 pub mod token;
 pub mod types;
+pub mod verify;
 
 pub use oauth2;
 
 #[cfg(test)]
 mod test {
+    use std::collections::HashMap;
+
+    use crate::authorization::AuthorizationDetailsObject;
     use crate::credential_offer::CredentialOffer;
     use crate::metadata::authorization_server::GrantType;
     use crate::metadata::credential_issuer::CredentialConfiguration;
     use crate::metadata::{AuthorizationServerMetadata, MetadataDiscovery};
     use crate::profiles::core::profiles::{
-        jwt_vc_json_ld, ldp_vc, CoreProfilesCredentialConfiguration, CoreProfilesCredentialRequest,
-        CredentialRequestWithFormat,
+        CoreProfilesAuthorizationDetailsObject, CoreProfilesCredentialConfiguration,
     };
     use crate::profiles::core::{client::Client, metadata::CredentialIssuerMetadata};
     use crate::types::CredentialOfferRequest;
-    use oauth2::{ClientId, RedirectUrl, TokenResponse};
+    use oauth2::{
+        AuthorizationCode, ClientId, CsrfToken, PkceCodeChallenge, RedirectUrl, TokenResponse,
+    };
     use url::Url;
 
     #[tokio::test]
@@ -103,36 +111,130 @@ mod test {
             .await
             .unwrap();
 
-        let credential_configuration = &targeted_credentials[0];
-        let request_inner = match credential_configuration.profile_specific_fields() {
-            CoreProfilesCredentialConfiguration::LdpVc(config) => {
-                let credential_definition =
-                    ldp_vc::authorization_detail::CredentialDefinition::default()
-                        .set_context(config.credential_definition().context().clone())
-                        .set_type(config.credential_definition().r#type().clone());
-                CredentialRequestWithFormat::LdpVc(ldp_vc::CredentialRequestWithFormat::new(
-                    credential_definition,
-                ))
-            }
-            CoreProfilesCredentialConfiguration::JwtVcJsonLd(config) => {
-                let credential_definition =
-                    ldp_vc::authorization_detail::CredentialDefinition::default()
-                        .set_context(config.credential_definition().context().clone())
-                        .set_type(config.credential_definition().r#type().clone());
-                CredentialRequestWithFormat::JwtVcJsonLd(
-                    jwt_vc_json_ld::CredentialRequestWithFormat::new(credential_definition),
-                )
-            }
-            x => unimplemented!("{x:?}"),
-        };
+        let credential_response = client
+            .request_credential(
+                token_response.access_token().clone(),
+                targeted_credentials[0]
+                    .profile_specific_fields()
+                    .build_request(),
+            )
+            .request_async(&http_client)
+            .await
+            .unwrap();
+
+        println!("{credential_response:?}")
+    }
+
+    /// Issue a credential through the authorization code flow.
+    ///
+    /// Run with `OID4VCI_CREDENTIAL_OFFER` set to a credential offer URL containing an
+    /// `authorization_code` grant, and optionally `OID4VCI_CLIENT_ID` and `OID4VCI_REDIRECT_URI`.
+    /// The authorization URL is printed, and the URL the browser is redirected to after
+    /// authorization must be pasted on stdin.
+    #[tokio::test]
+    #[ignore]
+    async fn manual_authorization_code() {
+        let http_client = oauth2::reqwest::Client::new();
+
+        let credential_offer_request: Url = std::env::var("OID4VCI_CREDENTIAL_OFFER")
+            .expect("OID4VCI_CREDENTIAL_OFFER must be set")
+            .parse()
+            .unwrap();
+        let client_id = std::env::var("OID4VCI_CLIENT_ID").unwrap_or_else(|_| "test".to_owned());
+        let redirect_uri = std::env::var("OID4VCI_REDIRECT_URI")
+            .unwrap_or_else(|_| "http://localhost:8080/callback".to_owned());
+
+        let credential_offer = CredentialOffer::from_request(
+            CredentialOfferRequest::from_url_checked(credential_offer_request).unwrap(),
+        )
+        .unwrap()
+        .resolve_async(&http_client)
+        .await
+        .unwrap();
+
+        let credential_issuer_metadata =
+            CredentialIssuerMetadata::discover_async(credential_offer.issuer(), &http_client)
+                .await
+                .unwrap();
+
+        let targeted_credentials: Vec<
+            CredentialConfiguration<CoreProfilesCredentialConfiguration>,
+        > = credential_issuer_metadata
+            .credential_configurations_supported()
+            .iter()
+            .filter(|configuration| {
+                credential_offer
+                    .credential_configuration_ids()
+                    .contains(configuration.id())
+            })
+            .cloned()
+            .collect();
+
+        assert_eq!(targeted_credentials.len(), 1);
+
+        let grant = credential_offer.authorization_code_grant().unwrap();
+
+        let authorization_server_metadata =
+            AuthorizationServerMetadata::discover_from_credential_issuer_metadata_async(
+                &http_client,
+                &credential_issuer_metadata,
+                Some(&GrantType::AuthorizationCode),
+                grant.authorization_server(),
+            )
+            .await
+            .unwrap();
+
+        let client = Client::from_issuer_metadata(
+            ClientId::new(client_id),
+            RedirectUrl::new(redirect_uri).unwrap(),
+            credential_issuer_metadata,
+            authorization_server_metadata,
+        );
+
+        let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+        let authorization_details = vec![AuthorizationDetailsObject::new(
+            CoreProfilesAuthorizationDetailsObject::WithIdAndUnresolvedProfile {
+                credential_configuration_id: targeted_credentials[0].id().clone(),
+                inner: HashMap::new(),
+                _format: (),
+            },
+        )];
+
+        let mut authorization_request = client
+            .authorize_url(CsrfToken::new_random)
+            .unwrap()
+            .set_pkce_challenge(pkce_challenge)
+            .unwrap()
+            .set_authorization_details(authorization_details)
+            .unwrap();
+        if let Some(issuer_state) = grant.issuer_state() {
+            authorization_request = authorization_request.set_issuer_state(issuer_state);
+        }
+        let (authorization_url, state) = authorization_request.url();
+
+        println!(
+            "Authorize at the following URL, then paste the redirect URL:\n{authorization_url}"
+        );
+        let mut redirect = String::new();
+        std::io::stdin().read_line(&mut redirect).unwrap();
+        let redirect: Url = redirect.trim().parse().unwrap();
+        let params: HashMap<String, String> = redirect.query_pairs().into_owned().collect();
+
+        assert_eq!(params.get("state"), Some(state.secret()));
+
+        let token_response = client
+            .exchange_code(AuthorizationCode::new(params["code"].clone()))
+            .set_pkce_verifier(pkce_verifier)
+            .request_async(&http_client)
+            .await
+            .unwrap();
 
         let credential_response = client
             .request_credential(
                 token_response.access_token().clone(),
-                CoreProfilesCredentialRequest::WithFormat {
-                    inner: request_inner,
-                    _credential_identifier: (),
-                },
+                targeted_credentials[0]
+                    .profile_specific_fields()
+                    .build_request(),
             )
             .request_async(&http_client)
             .await
@@ -1,18 +1,24 @@
 use anyhow::{bail, Result};
 use oauth2::{
-    AsyncHttpClient, AuthUrl, IntrospectionUrl, PkceCodeChallengeMethod, ResponseType,
-    RevocationUrl, Scope, SyncHttpClient, TokenUrl,
+    http::{header::CONTENT_TYPE, StatusCode},
+    AsyncHttpClient, AuthUrl, HttpResponse, IntrospectionUrl, PkceCodeChallengeMethod,
+    ResponseType, RevocationUrl, Scope, SyncHttpClient, TokenUrl,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value as Json};
+use serde_with::skip_serializing_none;
 use tracing::{info, warn};
 
 use crate::{
+    http_utils::{content_type_has_essence, MIME_TYPE_JSON, MIME_TYPE_JWK_SET},
     profiles::CredentialConfigurationProfile,
-    types::{IssuerUrl, JsonWebKeySetUrl, ParUrl, RegistrationUrl, ResponseMode},
+    types::{IssuerUrl, JsonWebKeySet, JsonWebKeySetUrl, ParUrl, RegistrationUrl, ResponseMode},
 };
 
-use super::{CredentialIssuerMetadata, MetadataDiscovery};
+use super::{
+    discovery_request, CredentialIssuerMetadata, DiscoveryError, MetadataDiscovery,
+    WellKnownPlacement,
+};
 
 /// Authorization Server Metadata according to
 /// [RFC8414](https://datatracker.ietf.org/doc/html/rfc8414) with the following modifications:
@@ -35,12 +41,15 @@ use super::{CredentialIssuerMetadata, MetadataDiscovery};
 ///   * `introspection_endpoint_auth_methods_supported`
 ///   * `introspection_endpoint_auth_singing_alg_values_supported`
 ///   
+#[skip_serializing_none]
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct AuthorizationServerMetadata {
     issuer: IssuerUrl,
     authorization_endpoint: Option<AuthUrl>,
     token_endpoint: TokenUrl,
     jwks_uri: Option<JsonWebKeySetUrl>,
+    /// JWK Set embedded in the metadata by some authorization servers instead of `jwks_uri`.
+    jwks: Option<JsonWebKeySet>,
     registration_endpoint: Option<RegistrationUrl>,
     scopes_supported: Option<Vec<Scope>>,
     response_types_supported: Option<Vec<ResponseType>>,
@@ -61,12 +70,15 @@ pub struct AuthorizationServerMetadata {
 }
 
 impl AuthorizationServerMetadata {
+    /// Creates metadata with the required parameters, e.g. for an issuer serving its own
+    /// authorization server metadata. Other parameters are set with the corresponding setters.
     pub fn new(issuer: IssuerUrl, token_endpoint: TokenUrl) -> Self {
         Self {
             issuer,
             authorization_endpoint: Default::default(),
             token_endpoint,
             jwks_uri: Default::default(),
+            jwks: Default::default(),
             registration_endpoint: Default::default(),
             scopes_supported: Default::default(),
             response_types_supported: Default::default(),
@@ -88,6 +100,7 @@ impl AuthorizationServerMetadata {
             set_authorization_endpoint -> authorization_endpoint[Option<AuthUrl>],
             set_token_endpoint -> token_endpoint[TokenUrl],
             set_jwks_uri -> jwks_uri[Option<JsonWebKeySetUrl>],
+            set_jwks -> jwks[Option<JsonWebKeySet>],
             set_registration_endpoint -> registration_endpoint[Option<RegistrationUrl>],
             set_scopes_supported -> scopes_supported[Option<Vec<Scope>>],
             set_response_types_supported -> response_types_supported[Option<Vec<ResponseType>>],
@@ -110,17 +123,72 @@ impl AuthorizationServerMetadata {
         &mut self.additional_fields
     }
 
+    /// Whether the authorization server advertises the `S256` PKCE code challenge method.
+    pub fn supports_pkce_s256(&self) -> bool {
+        self.code_challenge_methods_supported
+            .as_ref()
+            .is_some_and(|methods| methods.iter().any(|method| method.as_str() == "S256"))
+    }
+
+    /// Whether the authorization server supports the grant type.
+    pub fn supports_grant_type(&self, grant_type: &GrantType) -> bool {
+        self.grant_types_supported.contains(grant_type)
+    }
+
+    /// Returns the JWK Set of the authorization server, i.e. the inline `jwks` if present, or the
+    /// document fetched from `jwks_uri`, or `None` if the metadata has neither.
+    pub fn resolve_jwks<C>(&self, http_client: &C) -> Result<Option<JsonWebKeySet>, DiscoveryError>
+    where
+        C: SyncHttpClient,
+        C::Error: Send + Sync,
+    {
+        if let Some(jwks) = &self.jwks {
+            return Ok(Some(jwks.clone()));
+        }
+        let Some(jwks_uri) = &self.jwks_uri else {
+            return Ok(None);
+        };
+        let http_response = http_client
+            .call(discovery_request(jwks_uri.url())?)
+            .map_err(|e| DiscoveryError::Network(e.into()))?;
+        jwks_response(jwks_uri, http_response).map(Some)
+    }
+
+    /// Async version of [`AuthorizationServerMetadata::resolve_jwks`].
+    pub async fn resolve_jwks_async<'c, C>(
+        &self,
+        http_client: &'c C,
+    ) -> Result<Option<JsonWebKeySet>, DiscoveryError>
+    where
+        C: AsyncHttpClient<'c>,
+        C::Error: Send + Sync,
+    {
+        if let Some(jwks) = &self.jwks {
+            return Ok(Some(jwks.clone()));
+        }
+        let Some(jwks_uri) = &self.jwks_uri else {
+            return Ok(None);
+        };
+        let http_response = http_client
+            .call(discovery_request(jwks_uri.url())?)
+            .await
+            .map_err(|e| DiscoveryError::Network(e.into()))?;
+        jwks_response(jwks_uri, http_response).map(Some)
+    }
+
     /// Discover the authorization server metadata, potentially from a list of authorization
     /// servers in the credential issuer metadata.
     ///
     /// Optionally the grant type and authorization server (i.e. from the credential offer) can be
-    /// provided to help select the correct authorization server.
+    /// provided to help select the correct authorization server. When the credential issuer
+    /// lists authorization servers and none of them supports the grant type,
+    /// [`DiscoveryError::UnsupportedGrantType`] is returned.
     pub fn discover_from_credential_issuer_metadata<C, CM>(
         http_client: &C,
         credential_issuer_metadata: &CredentialIssuerMetadata<CM>,
         grant_type: Option<&GrantType>,
         authorization_server: Option<&IssuerUrl>,
-    ) -> Result<Self, anyhow::Error>
+    ) -> Result<Self, DiscoveryError>
     where
         C: SyncHttpClient,
         C::Error: Send + Sync,
@@ -137,6 +205,7 @@ impl AuthorizationServerMetadata {
         };
 
         if let Some(servers) = credential_issuer_metadata.authorization_servers() {
+            let mut grant_type_unsupported = false;
             // the Wallet can use to identify the Authorization Server to use with this grant type
             // when authorization_servers parameter in the Credential Issuer metadata has multiple
             // entries. It MUST NOT be used otherwise.
@@ -150,15 +219,11 @@ impl AuthorizationServerMetadata {
                 let response = Self::discover(auth_server, http_client);
                 match response {
                     Ok(response) => {
-                        if response
-                            .grant_types_supported()
-                            .0
-                            .iter()
-                            .any(|gt| gt == grant_type)
-                        {
+                        if response.supports_grant_type(grant_type) {
                             return Ok(response);
                         } else {
                             info!("Auth server not supporting grant type, trying the next one");
+                            grant_type_unsupported = true;
                         }
                     }
                     Err(e) => {
@@ -166,9 +231,15 @@ impl AuthorizationServerMetadata {
                     }
                 }
             }
+
+            // Fallback to credential issuer authorization server.
+            return match credential_issuer_authorization_server_metadata {
+                Ok(metadata) if metadata.supports_grant_type(grant_type) => Ok(metadata),
+                Err(e) if !grant_type_unsupported => Err(e),
+                _ => Err(DiscoveryError::UnsupportedGrantType(grant_type.clone())),
+            };
         }
 
-        // Fallback to credential issuer authorization server.
         credential_issuer_authorization_server_metadata
     }
 
@@ -176,13 +247,15 @@ impl AuthorizationServerMetadata {
     /// servers in the credential issuer metadata.
     ///
     /// Optionally the grant type and authorization server (i.e. from the credential offer) can be
-    /// provided to help select the correct authorization server.
+    /// provided to help select the correct authorization server. When the credential issuer
+    /// lists authorization servers and none of them supports the grant type,
+    /// [`DiscoveryError::UnsupportedGrantType`] is returned.
     pub async fn discover_from_credential_issuer_metadata_async<'c, C, CM>(
         http_client: &'c C,
         credential_issuer_metadata: &CredentialIssuerMetadata<CM>,
         grant_type: Option<&GrantType>,
         authorization_server: Option<&IssuerUrl>,
-    ) -> Result<Self, anyhow::Error>
+    ) -> Result<Self, DiscoveryError>
     where
         C: AsyncHttpClient<'c>,
         C::Error: Send + Sync,
@@ -199,6 +272,7 @@ impl AuthorizationServerMetadata {
         };
 
         if let Some(servers) = credential_issuer_metadata.authorization_servers() {
+            let mut grant_type_unsupported = false;
             // the Wallet can use to identify the Authorization Server to use with this grant type
             // when authorization_servers parameter in the Credential Issuer metadata has multiple
             // entries. It MUST NOT be used otherwise.
@@ -212,15 +286,11 @@ impl AuthorizationServerMetadata {
                 let response = Self::discover_async(auth_server, http_client).await;
                 match response {
                     Ok(response) => {
-                        if response
-                            .grant_types_supported()
-                            .0
-                            .iter()
-                            .any(|gt| gt == grant_type)
-                        {
+                        if response.supports_grant_type(grant_type) {
                             return Ok(response);
                         } else {
                             info!("Auth server not supporting grant type, trying the next one");
+                            grant_type_unsupported = true;
                         }
                     }
                     Err(e) => {
@@ -228,15 +298,22 @@ impl AuthorizationServerMetadata {
                     }
                 }
             }
+
+            // Fallback to credential issuer authorization server.
+            return match credential_issuer_authorization_server_metadata {
+                Ok(metadata) if metadata.supports_grant_type(grant_type) => Ok(metadata),
+                Err(e) if !grant_type_unsupported => Err(e),
+                _ => Err(DiscoveryError::UnsupportedGrantType(grant_type.clone())),
+            };
         }
 
-        // Fallback to credential issuer authorization server.
         credential_issuer_authorization_server_metadata
     }
 }
 
 impl MetadataDiscovery for AuthorizationServerMetadata {
     const METADATA_URL_SUFFIX: &'static str = ".well-known/oauth-authorization-server";
+    const PREFERRED_WELL_KNOWN_PLACEMENT: WellKnownPlacement = WellKnownPlacement::Inserted;
 
     fn validate(&self, issuer: &IssuerUrl) -> Result<()> {
         if self.issuer() != issuer {
@@ -250,6 +327,36 @@ impl MetadataDiscovery for AuthorizationServerMetadata {
     }
 }
 
+pub(super) fn jwks_response(
+    jwks_uri: &JsonWebKeySetUrl,
+    http_response: HttpResponse,
+) -> Result<JsonWebKeySet, DiscoveryError> {
+    let url = jwks_uri.url();
+    if http_response.status() != StatusCode::OK {
+        return Err(DiscoveryError::Status {
+            status: http_response.status(),
+            url: url.clone(),
+        });
+    }
+    if let Some(content_type) = http_response.headers().get(CONTENT_TYPE) {
+        if !content_type_has_essence(content_type, MIME_TYPE_JSON)
+            && !content_type_has_essence(content_type, MIME_TYPE_JWK_SET)
+        {
+            return Err(DiscoveryError::Validation {
+                url: url.clone(),
+                source: anyhow::anyhow!("Unexpected response Content-Type: {content_type:?}"),
+            });
+        }
+    }
+    serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_slice(
+        http_response.body(),
+    ))
+    .map_err(|e| DiscoveryError::Validation {
+        url: url.clone(),
+        source: e.into(),
+    })
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ResponseModes(pub Vec<ResponseMode>);
 
@@ -271,6 +378,19 @@ impl Default for GrantTypesSupported {
     }
 }
 
+impl GrantTypesSupported {
+    /// Whether the grant type is supported.
+    ///
+    /// Grant types are compared by their identifier, so a [`GrantType::Extension`] holding a
+    /// known identifier matches the corresponding variant.
+    pub fn contains(&self, grant_type: &GrantType) -> bool {
+        self.0.iter().any(|g| g.as_str() == grant_type.as_str())
+    }
+}
+
+/// Grant type identifier.
+///
+/// Identifiers not known by this library are kept verbatim as [`GrantType::Extension`].
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum GrantType {
@@ -281,3 +401,220 @@ pub enum GrantType {
     #[serde(untagged)]
     Extension(String),
 }
+
+impl GrantType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::AuthorizationCode => "authorization_code",
+            Self::Implicit => "implicit",
+            Self::PreAuthorizedCode => "urn:ietf:params:oauth:grant-type:pre-authorized_code",
+            Self::Extension(s) => s,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use oauth2::{
+        http::{self, header::CONTENT_TYPE},
+        HttpRequest, HttpResponse,
+    };
+    use serde_json::json;
+
+    use crate::{
+        profiles::core::metadata::CredentialIssuerMetadata as CoreCredentialIssuerMetadata,
+        types::CredentialUrl,
+    };
+
+    use super::*;
+
+    fn http_client(
+        pre_authorized_server: Option<&'static str>,
+    ) -> impl Fn(HttpRequest) -> Result<HttpResponse, std::io::Error> {
+        move |request: HttpRequest| {
+            let uri = request.uri().to_string();
+            let Some(issuer) = uri.strip_suffix("/.well-known/oauth-authorization-server") else {
+                panic!("unexpected request to {uri}");
+            };
+            if !issuer.starts_with("https://server") {
+                return Ok(http::Response::builder().status(404).body(vec![]).unwrap());
+            }
+            let grant_types = if Some(issuer) == pre_authorized_server {
+                json!(["urn:ietf:params:oauth:grant-type:pre-authorized_code"])
+            } else {
+                json!(["authorization_code"])
+            };
+            Ok(http::Response::builder()
+                .status(200)
+                .header(CONTENT_TYPE, "application/json")
+                .body(
+                    serde_json::to_vec(&json!({
+                        "issuer": issuer,
+                        "token_endpoint": format!("{issuer}/token"),
+                        "grant_types_supported": grant_types
+                    }))
+                    .unwrap(),
+                )
+                .unwrap())
+        }
+    }
+
+    fn credential_issuer_metadata() -> CoreCredentialIssuerMetadata {
+        CoreCredentialIssuerMetadata::new(
+            IssuerUrl::new("https://credential-issuer.example.com".into()).unwrap(),
+            CredentialUrl::new("https://credential-issuer.example.com/credential".into()).unwrap(),
+        )
+        .set_authorization_servers(Some(vec![
+            IssuerUrl::new("https://server1.example.com".into()).unwrap(),
+            IssuerUrl::new("https://server2.example.com".into()).unwrap(),
+        ]))
+    }
+
+    #[test]
+    fn supports_pkce_s256() {
+        let metadata = AuthorizationServerMetadata::new(
+            IssuerUrl::new("https://server.example.com".into()).unwrap(),
+            TokenUrl::new("https://server.example.com/token".into()).unwrap(),
+        );
+        assert!(!metadata.supports_pkce_s256());
+
+        let metadata = metadata.set_code_challenge_methods_supported(Some(vec![
+            PkceCodeChallengeMethod::new("plain".into()),
+        ]));
+        assert!(!metadata.supports_pkce_s256());
+
+        let metadata = metadata.set_code_challenge_methods_supported(Some(vec![
+            PkceCodeChallengeMethod::new("plain".into()),
+            PkceCodeChallengeMethod::new("S256".into()),
+        ]));
+        assert!(metadata.supports_pkce_s256());
+    }
+
+    #[test]
+    fn serve_metadata() {
+        let issuer = IssuerUrl::new("https://server.example.com".into()).unwrap();
+        let metadata = AuthorizationServerMetadata::new(
+            issuer.clone(),
+            TokenUrl::new("https://server.example.com/token".into()).unwrap(),
+        )
+        .set_grant_types_supported(GrantTypesSupported(vec![GrantType::PreAuthorizedCode]))
+        .set_pre_authorized_grant_anonymous_access_supported(true);
+
+        let expected_json = json!({
+            "issuer": "https://server.example.com",
+            "token_endpoint": "https://server.example.com/token",
+            "response_modes_supported": ["query", "fragment"],
+            "grant_types_supported": ["urn:ietf:params:oauth:grant-type:pre-authorized_code"],
+            "pre-authorized_grant_anonymous_access_supported": true,
+            "require_pushed_authorization_requests": false
+        });
+        let served = serde_json::to_value(&metadata).unwrap();
+        assert_json_diff::assert_json_eq!(expected_json, served);
+
+        let discovered: AuthorizationServerMetadata = serde_json::from_value(served).unwrap();
+        discovered.validate(&issuer).unwrap();
+    }
+
+    #[test]
+    fn grant_types_supported_extensions_roundtrip() {
+        let json = r#"["authorization_code","urn:ietf:params:oauth:grant-type:device_code","urn:example:vendor:grant-type:custom","urn:ietf:params:oauth:grant-type:pre-authorized_code"]"#;
+        let grant_types: GrantTypesSupported = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            grant_types,
+            GrantTypesSupported(vec![
+                GrantType::AuthorizationCode,
+                GrantType::Extension("urn:ietf:params:oauth:grant-type:device_code".into()),
+                GrantType::Extension("urn:example:vendor:grant-type:custom".into()),
+                GrantType::PreAuthorizedCode,
+            ])
+        );
+        assert_eq!(serde_json::to_string(&grant_types).unwrap(), json);
+    }
+
+    #[test]
+    fn grant_types_supported_contains() {
+        let grant_types = GrantTypesSupported(vec![
+            GrantType::Extension("urn:ietf:params:oauth:grant-type:device_code".into()),
+            GrantType::Extension("urn:example:vendor:grant-type:custom".into()),
+            GrantType::Extension("urn:ietf:params:oauth:grant-type:pre-authorized_code".into()),
+        ]);
+        assert!(grant_types.contains(&GrantType::Extension(
+            "urn:ietf:params:oauth:grant-type:device_code".into()
+        )));
+        assert!(grant_types.contains(&GrantType::Extension(
+            "urn:example:vendor:grant-type:custom".into()
+        )));
+        assert!(grant_types.contains(&GrantType::PreAuthorizedCode));
+        assert!(!grant_types.contains(&GrantType::AuthorizationCode));
+    }
+
+    #[test]
+    fn discover_no_matching_grant_type() {
+        let err = AuthorizationServerMetadata::discover_from_credential_issuer_metadata(
+            &http_client(None),
+            &credential_issuer_metadata(),
+            Some(&GrantType::PreAuthorizedCode),
+            None,
+        )
+        .unwrap_err();
+
+        assert!(matches!(
+            err,
+            DiscoveryError::UnsupportedGrantType(GrantType::PreAuthorizedCode)
+        ));
+    }
+
+    #[test]
+    fn discover_matching_grant_type() {
+        let metadata = AuthorizationServerMetadata::discover_from_credential_issuer_metadata(
+            &http_client(Some("https://server2.example.com")),
+            &credential_issuer_metadata(),
+            Some(&GrantType::PreAuthorizedCode),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(metadata.issuer().as_str(), "https://server2.example.com");
+    }
+
+    #[test]
+    fn inline_jwks() {
+        let jwk = json!({
+            "kty": "EC",
+            "crv": "P-256",
+            "kid": "as-key-1",
+            "x": "MKBCTNIcKUSDii11ySs3526iDZ8AiTo7Tu6KPAqv7D4",
+            "y": "4Etl6SRW2YiLUrN5vfvVHuhp7x8PxltmWWlbbM4IFyM"
+        });
+        let metadata: AuthorizationServerMetadata = serde_json::from_value(json!({
+            "issuer": "https://server.example.com",
+            "token_endpoint": "https://server.example.com/token",
+            "jwks": { "keys": [jwk] }
+        }))
+        .unwrap();
+        let no_request = |_: HttpRequest| -> Result<HttpResponse, std::io::Error> {
+            panic!("the inline JWK Set must be used")
+        };
+
+        let jwks = metadata.resolve_jwks(&no_request).unwrap().unwrap();
+        assert_eq!(jwks.keys().len(), 1);
+        assert!(jwks.find("as-key-1").is_some());
+
+        let metadata = metadata.set_jwks(None).set_jwks_uri(Some(
+            JsonWebKeySetUrl::new("https://server.example.com/jwks.json".into()).unwrap(),
+        ));
+        let http_client = |request: HttpRequest| -> Result<HttpResponse, std::io::Error> {
+            assert_eq!(request.uri(), "https://server.example.com/jwks.json");
+            Ok(http::Response::builder()
+                .status(200)
+                .header(CONTENT_TYPE, "application/jwk-set+json")
+                .body(serde_json::to_vec(&json!({ "keys": [jwk] })).unwrap())
+                .unwrap())
+        };
+        let fetched = metadata.resolve_jwks(&http_client).unwrap().unwrap();
+        assert_eq!(fetched, jwks);
+
+        let metadata = metadata.set_jwks_uri(None);
+        assert!(metadata.resolve_jwks(&no_request).unwrap().is_none());
+    }
+}
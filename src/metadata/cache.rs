@@ -0,0 +1,197 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde_json::Value;
+use url::Url;
+
+/// A store for metadata documents obtained through discovery.
+///
+/// Entries are keyed by the discovery URL, which is derived from the issuer URL and the kind of
+/// metadata, so a single cache can hold both credential issuer and authorization server metadata.
+pub trait MetadataCache {
+    /// Returns the cached metadata document, if present and still fresh.
+    fn get(&self, discovery_url: &Url) -> Option<Value>;
+
+    /// Stores a metadata document, which stays fresh for `max_age`.
+    fn insert(&self, discovery_url: Url, metadata: Value, max_age: Duration);
+}
+
+/// A simple in-memory [`MetadataCache`].
+#[derive(Debug, Default)]
+pub struct InMemoryMetadataCache {
+    entries: Mutex<HashMap<Url, (Value, Instant)>>,
+}
+
+impl InMemoryMetadataCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Removes all entries from the cache.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+impl MetadataCache for InMemoryMetadataCache {
+    fn get(&self, discovery_url: &Url) -> Option<Value> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(discovery_url) {
+            Some((metadata, expires_at)) if *expires_at > Instant::now() => Some(metadata.clone()),
+            Some(_) => {
+                entries.remove(discovery_url);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert(&self, discovery_url: Url, metadata: Value, max_age: Duration) {
+        let Some(expires_at) = Instant::now().checked_add(max_age) else {
+            return;
+        };
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(discovery_url, (metadata, expires_at));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use oauth2::{
+        http::{
+            self,
+            header::{CACHE_CONTROL, CONTENT_TYPE},
+        },
+        HttpRequest, HttpResponse,
+    };
+    use serde_json::json;
+
+    use crate::{
+        http_utils::MIME_TYPE_JSON,
+        metadata::{CredentialIssuerMetadata, MetadataDiscovery},
+        profiles::core::profiles::CoreProfilesCredentialConfiguration,
+        types::IssuerUrl,
+    };
+
+    use super::*;
+
+    fn http_client<'a>(
+        calls: &'a Cell<usize>,
+        cache_control: &'a str,
+    ) -> impl Fn(HttpRequest) -> Result<HttpResponse, std::io::Error> + 'a {
+        move |request: HttpRequest| {
+            calls.set(calls.get() + 1);
+            assert_eq!(
+                request.uri(),
+                "https://credential-issuer.example.com/.well-known/openid-credential-issuer"
+            );
+            Ok(http::Response::builder()
+                .status(200)
+                .header(CONTENT_TYPE, MIME_TYPE_JSON)
+                .header(CACHE_CONTROL, cache_control)
+                .body(
+                    serde_json::to_vec(&json!({
+                        "credential_issuer": "https://credential-issuer.example.com",
+                        "credential_endpoint": "https://credential-issuer.example.com/credential"
+                    }))
+                    .unwrap(),
+                )
+                .unwrap())
+        }
+    }
+
+    #[test]
+    fn discover_cached_within_max_age() {
+        let issuer = IssuerUrl::new("https://credential-issuer.example.com".into()).unwrap();
+        let cache = InMemoryMetadataCache::new();
+        let calls = Cell::new(0);
+        let http_client = http_client(&calls, "public, max-age=3600");
+
+        let first =
+            CredentialIssuerMetadata::<CoreProfilesCredentialConfiguration>::discover_cached(
+                &issuer,
+                &http_client,
+                &cache,
+            )
+            .unwrap();
+        let second =
+            CredentialIssuerMetadata::<CoreProfilesCredentialConfiguration>::discover_cached(
+                &issuer,
+                &http_client,
+                &cache,
+            )
+            .unwrap();
+
+        assert_eq!(calls.get(), 1);
+        assert_eq!(first, second);
+
+        cache.clear();
+        CredentialIssuerMetadata::<CoreProfilesCredentialConfiguration>::discover_cached(
+            &issuer,
+            &http_client,
+            &cache,
+        )
+        .unwrap();
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn discover_cached_async_within_max_age() {
+        let issuer = IssuerUrl::new("https://credential-issuer.example.com".into()).unwrap();
+        let cache = InMemoryMetadataCache::new();
+        let calls = Cell::new(0);
+        let http_client = http_client(&calls, "max-age=60");
+        let async_http_client = |request: HttpRequest| {
+            let response = http_client(request);
+            async move { response }
+        };
+
+        for _ in 0..2 {
+            CredentialIssuerMetadata::<CoreProfilesCredentialConfiguration>::discover_cached_async(
+                &issuer,
+                &async_http_client,
+                &cache,
+            )
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn discover_cached_no_store() {
+        let issuer = IssuerUrl::new("https://credential-issuer.example.com".into()).unwrap();
+        let cache = InMemoryMetadataCache::new();
+        let calls = Cell::new(0);
+        let http_client = http_client(&calls, "max-age=3600, no-store");
+
+        for _ in 0..2 {
+            CredentialIssuerMetadata::<CoreProfilesCredentialConfiguration>::discover_cached(
+                &issuer,
+                &http_client,
+                &cache,
+            )
+            .unwrap();
+        }
+
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn expired_entries_are_not_returned() {
+        let cache = InMemoryMetadataCache::new();
+        let url = Url::parse("https://credential-issuer.example.com").unwrap();
+        cache.insert(url.clone(), json!({}), Duration::ZERO);
+        assert!(cache.get(&url).is_none());
+        cache.insert(url.clone(), json!({}), Duration::from_secs(60));
+        assert_eq!(cache.get(&url), Some(json!({})));
+    }
+}
@@ -1,19 +1,27 @@
+use std::{collections::HashMap, fmt, marker::PhantomData};
+
 use anyhow::bail;
 use oauth2::Scope;
-use serde::{Deserialize, Serialize};
+use serde::{
+    de::{self, DeserializeSeed, IgnoredAny, MapAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+use serde_json::{Map, Value};
 use serde_with::{serde_as, skip_serializing_none, KeyValueMap};
+use ssi::jwk::Algorithm;
+use tracing::warn;
 
 use crate::{
     credential_response_encryption::CredentialResponseEncryptionMetadata,
     profiles::CredentialConfigurationProfile,
-    proof_of_possession::KeyProofTypesSupported,
+    proof_of_possession::{KeyProofType, KeyProofTypesSupported},
     types::{
         BatchCredentialUrl, CredentialConfigurationId, CredentialUrl, DeferredCredentialUrl,
         IssuerUrl, LanguageTag, LogoUri, NotificationUrl,
     },
 };
 
-use super::MetadataDiscovery;
+use super::{MetadataDiscovery, WellKnownPlacement};
 
 #[serde_as]
 #[skip_serializing_none]
@@ -29,12 +37,19 @@ where
     deferred_credential_endpoint: Option<DeferredCredentialUrl>,
     notification_endpoint: Option<NotificationUrl>,
     credential_response_encryption: Option<CredentialResponseEncryptionMetadata>,
+    batch_credential_issuance: Option<BatchCredentialIssuance>,
     credential_identifiers_supported: Option<bool>,
     signed_metadata: Option<String>,
     display: Option<Vec<CredentialIssuerMetadataDisplay>>,
-    #[serde(default = "Vec::new", bound = "CM: CredentialConfigurationProfile")]
+    #[serde(
+        default = "Vec::new",
+        bound = "CM: CredentialConfigurationProfile",
+        alias = "credentials_supported"
+    )]
     #[serde_as(as = "KeyValueMap<_>")]
     credential_configurations_supported: Vec<CredentialConfiguration<CM>>,
+    #[serde(flatten)]
+    additional_fields: Map<String, Value>,
 }
 
 impl<CM> MetadataDiscovery for CredentialIssuerMetadata<CM>
@@ -42,6 +57,7 @@ where
     CM: CredentialConfigurationProfile,
 {
     const METADATA_URL_SUFFIX: &'static str = ".well-known/openid-credential-issuer";
+    const PREFERRED_WELL_KNOWN_PLACEMENT: WellKnownPlacement = WellKnownPlacement::Appended;
 
     fn validate(&self, issuer: &IssuerUrl) -> anyhow::Result<()> {
         if self.credential_issuer() != issuer {
@@ -51,8 +67,158 @@ where
                 issuer.as_str()
             )
         }
+        match self.check_batch_issuance() {
+            Err(e @ BatchIssuanceError::InvalidBatchSize(_)) => bail!(e),
+            Err(e @ BatchIssuanceError::Ambiguous) => warn!("{e}"),
+            Ok(()) => {}
+        }
         Ok(())
     }
+
+    /// Parses credential issuer metadata, skipping the credential configurations that fail to
+    /// parse, e.g. because their format is not supported by the profile, instead of failing.
+    ///
+    /// The skipped configurations are returned as warnings.
+    fn from_json_lenient(
+        value: Value,
+    ) -> Result<(Self, Vec<ConfigurationWarning>), serde_json::Error> {
+        let Value::Object(mut object) = value else {
+            return Ok((serde_json::from_value(value)?, Vec::new()));
+        };
+
+        let mut configurations = Vec::new();
+        let mut warnings = Vec::new();
+        for key in [
+            "credential_configurations_supported",
+            "credentials_supported",
+        ] {
+            // Other values are left in place, so that the error surfaces below.
+            let Some(Value::Object(entries)) = object.get_mut(key) else {
+                continue;
+            };
+            let entries = std::mem::take(entries);
+            for (id, mut entry) in entries {
+                if let Value::Object(entry) = &mut entry {
+                    // The identifier is the map key, see `KeyValueMap`.
+                    entry.insert("$key$".to_string(), Value::String(id.clone()));
+                }
+                match serde_json::from_value(entry) {
+                    Ok(configuration) => configurations.push(configuration),
+                    Err(error) => {
+                        warn!("skipping credential configuration `{id}`: {error}");
+                        warnings.push(ConfigurationWarning { id, error });
+                    }
+                }
+            }
+        }
+
+        let metadata: Self = serde_json::from_value(Value::Object(object))?;
+        Ok((
+            metadata.set_credential_configurations_supported(configurations),
+            warnings,
+        ))
+    }
+}
+
+/// Visits the credential issuer metadata, and then its `credential_configurations_supported`
+/// map, looking for a single configuration.
+struct FindConfiguration<'a, CM> {
+    id: &'a CredentialConfigurationId,
+    _profile: PhantomData<CM>,
+}
+
+impl<'de, CM> Visitor<'de> for FindConfiguration<'_, CM>
+where
+    CM: CredentialConfigurationProfile,
+{
+    type Value = Option<CredentialConfiguration<CM>>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON object")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut configuration = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "credential_configurations_supported" | "credentials_supported" => {
+                    configuration = map.next_value_seed(FindConfiguration {
+                        id: self.id,
+                        _profile: PhantomData,
+                    })?;
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(configuration)
+    }
+}
+
+impl<'de, CM> DeserializeSeed<'de> for FindConfiguration<'_, CM>
+where
+    CM: CredentialConfigurationProfile,
+{
+    type Value = Option<CredentialConfiguration<CM>>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(FindConfigurationEntry(self))
+    }
+}
+
+struct FindConfigurationEntry<'a, CM>(FindConfiguration<'a, CM>);
+
+impl<'de, CM> Visitor<'de> for FindConfigurationEntry<'_, CM>
+where
+    CM: CredentialConfigurationProfile,
+{
+    type Value = Option<CredentialConfiguration<CM>>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map of credential configurations")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut configuration = None;
+        while let Some(key) = map.next_key::<String>()? {
+            if configuration.is_none() && key == **self.0.id {
+                let mut value: Map<String, Value> = map.next_value()?;
+                // The identifier is the map key, see `KeyValueMap`.
+                value.insert("$key$".to_string(), Value::String(key));
+                configuration =
+                    Some(serde_json::from_value(Value::Object(value)).map_err(de::Error::custom)?);
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(configuration)
+    }
+}
+
+/// A credential configuration that was skipped by lenient parsing of the credential issuer
+/// metadata, see [`MetadataDiscovery::discover_with_warnings`].
+#[derive(Debug)]
+pub struct ConfigurationWarning {
+    pub id: String,
+    pub error: serde_json::Error,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BatchIssuanceError {
+    #[error("both `batch_credential_endpoint` and `batch_credential_issuance` are present")]
+    Ambiguous,
+    #[error("`batch_credential_issuance` has a `batch_size` of {0}, it must be at least 2")]
+    InvalidBatchSize(usize),
 }
 
 impl<CM> CredentialIssuerMetadata<CM>
@@ -68,10 +234,12 @@ where
             deferred_credential_endpoint: None,
             notification_endpoint: None,
             credential_response_encryption: None,
+            batch_credential_issuance: None,
             credential_identifiers_supported: None,
             signed_metadata: None,
             display: None,
             credential_configurations_supported: vec![],
+            additional_fields: Map::new(),
         }
     }
 
@@ -84,12 +252,112 @@ where
             set_deferred_credential_endpoint -> deferred_credential_endpoint[Option<DeferredCredentialUrl>],
             set_notification_endpoint -> notification_endpoint[Option<NotificationUrl>],
             set_credential_response_encryption -> credential_response_encryption[Option<CredentialResponseEncryptionMetadata>],
+            set_batch_credential_issuance -> batch_credential_issuance[Option<BatchCredentialIssuance>],
             set_credential_identifiers_supported -> credential_identifiers_supported[Option<bool>],
             set_signed_metadata -> signed_metadata[Option<String>],
             set_display -> display[Option<Vec<CredentialIssuerMetadataDisplay>>],
             set_credential_configurations_supported -> credential_configurations_supported[Vec<CredentialConfiguration<CM>>],
         }
     ];
+
+    /// Parses the configuration `id` out of a serialized credential issuer metadata document,
+    /// skipping over the other configurations and metadata parameters without materializing
+    /// them.
+    ///
+    /// This is meant for wallets that only need one configuration from an issuer exposing a
+    /// large number of them. The document is not otherwise validated.
+    pub fn find_configuration_streaming(
+        body: &[u8],
+        id: &CredentialConfigurationId,
+    ) -> Result<Option<CredentialConfiguration<CM>>, serde_json::Error> {
+        let mut deserializer = serde_json::Deserializer::from_slice(body);
+        let configuration = deserializer.deserialize_map(FindConfiguration {
+            id,
+            _profile: PhantomData,
+        })?;
+        deserializer.end()?;
+        Ok(configuration)
+    }
+
+    /// Checks that the batch issuance metadata is consistent.
+    ///
+    /// Issuers may advertise batch issuance through the legacy `batch_credential_endpoint` or
+    /// through `batch_credential_issuance`, in which case several `proofs` are sent to the
+    /// credential endpoint. Discovery rejects an invalid `batch_size`, but only logs a warning
+    /// when both are present.
+    pub fn check_batch_issuance(&self) -> Result<(), BatchIssuanceError> {
+        let Some(batch_credential_issuance) = &self.batch_credential_issuance else {
+            return Ok(());
+        };
+        if *batch_credential_issuance.batch_size() < 2 {
+            return Err(BatchIssuanceError::InvalidBatchSize(
+                *batch_credential_issuance.batch_size(),
+            ));
+        }
+        if self.batch_credential_endpoint.is_some() {
+            return Err(BatchIssuanceError::Ambiguous);
+        }
+        Ok(())
+    }
+
+    /// Metadata parameters that are not defined by the specification, such as issuer-specific
+    /// extensions.
+    pub fn additional_fields(&self) -> &Map<String, Value> {
+        &self.additional_fields
+    }
+
+    pub fn additional_fields_mut(&mut self) -> &mut Map<String, Value> {
+        &mut self.additional_fields
+    }
+
+    /// Returns the supported credential configurations, indexed by their identifier.
+    pub fn configurations_by_id(
+        &self,
+    ) -> HashMap<&CredentialConfigurationId, &CredentialConfiguration<CM>> {
+        self.credential_configurations_supported
+            .iter()
+            .map(|configuration| (configuration.id(), configuration))
+            .collect()
+    }
+
+    /// Returns the credential configurations that can be bound to at least one of the given
+    /// cryptographic binding methods.
+    ///
+    /// Configurations that do not specify `cryptographic_binding_methods_supported` are not bound
+    /// to a key and are always returned.
+    pub fn configurations_supporting_binding<'a>(
+        &'a self,
+        binding_methods: &'a [CryptographicBindingMethod],
+    ) -> impl Iterator<Item = &'a CredentialConfiguration<CM>> {
+        self.credential_configurations_supported
+            .iter()
+            .filter(|configuration| {
+                binding_methods
+                    .iter()
+                    .any(|binding_method| configuration.supports_binding(binding_method))
+            })
+    }
+}
+
+/// Support for issuing several credentials in a single credential request, using the `proofs`
+/// parameter.
+///
+/// This supersedes the batch credential endpoint in recent drafts.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct BatchCredentialIssuance {
+    batch_size: usize,
+}
+
+impl BatchCredentialIssuance {
+    pub fn new(batch_size: usize) -> Self {
+        Self { batch_size }
+    }
+
+    field_getters_setters![
+        pub self [self] ["batch credential issuance value"] {
+            set_batch_size -> batch_size[usize],
+        }
+    ];
 }
 
 #[serde_as]
@@ -123,6 +391,8 @@ impl CredentialIssuerMetadataDisplay {
 #[skip_serializing_none]
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct MetadataDisplayLogo {
+    /// Serialized as `uri`, but also accepted as `url`, used by previous drafts.
+    #[serde(alias = "url")]
     uri: LogoUri,
     alt_text: Option<String>,
 }
@@ -184,8 +454,64 @@ where
             set_profile_specific_fields -> profile_specific_fields[CM],
         }
     ];
+
+    /// Returns whether the issued credential can be bound with the given cryptographic binding
+    /// method.
+    ///
+    /// Configurations that do not specify `cryptographic_binding_methods_supported` are not bound
+    /// to a key and support any binding method.
+    pub fn supports_binding(&self, binding_method: &CryptographicBindingMethod) -> bool {
+        match &self.cryptographic_binding_methods_supported {
+            None => true,
+            Some(supported) => supported.iter().any(|s| s.matches(binding_method)),
+        }
+    }
+
+    /// Returns the names of the DID methods supported for binding, e.g. `key` for `did:key`.
+    ///
+    /// `did:`, which allows any DID method, has no name and is not returned.
+    pub fn did_methods_supported(&self) -> Vec<&str> {
+        self.cryptographic_binding_methods_supported
+            .iter()
+            .flatten()
+            .filter_map(CryptographicBindingMethod::did_method)
+            .collect()
+    }
+
+    /// Returns the first proof type supported by the issuer, in the order of
+    /// [`PROOF_TYPE_PREFERENCE`], along with the first of `key_algs` it accepts as a proof signing
+    /// algorithm.
+    ///
+    /// `key_algs` are the algorithms the wallet key can sign with, by order of preference.
+    /// Returns `None` if no proof type accepts any of them, or if the configuration does not
+    /// specify `proof_types_supported`.
+    pub fn select_proof_alg(&self, key_algs: &[Algorithm]) -> Option<(KeyProofType, Algorithm)> {
+        let proof_types_supported = self.proof_types_supported.as_deref()?;
+        PROOF_TYPE_PREFERENCE.iter().find_map(|key| {
+            let proof_type = proof_types_supported
+                .iter()
+                .find(|proof_type| proof_type.key() == key)?;
+            key_algs
+                .iter()
+                .find(|alg| {
+                    proof_type
+                        .proof_signing_alg_values_supported()
+                        .contains(alg)
+                })
+                .map(|alg| (key.clone(), *alg))
+        })
+    }
 }
 
+/// Proof types by order of preference of [`CredentialConfiguration::select_proof_alg`], so that the
+/// selection does not depend on the order of the issuer's `proof_types_supported`.
+pub const PROOF_TYPE_PREFERENCE: [KeyProofType; 4] = [
+    KeyProofType::Jwt,
+    KeyProofType::Cwt,
+    KeyProofType::LdpVp,
+    KeyProofType::Attestation,
+];
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub enum CryptographicBindingMethod {
     #[serde(rename = "jwk")]
@@ -203,6 +529,36 @@ pub enum CryptographicBindingMethod {
     Extension(String),
 }
 
+impl CryptographicBindingMethod {
+    /// Returns whether this is a DID method, i.e. `did:` or `did:<method>`.
+    pub fn is_did(&self) -> bool {
+        match self {
+            Self::Did => true,
+            #[cfg(test)]
+            Self::DidExample => true,
+            Self::Extension(method) => method.starts_with("did:"),
+            _ => false,
+        }
+    }
+
+    /// Returns the name of the DID method, e.g. `key` for `did:key`.
+    pub fn did_method(&self) -> Option<&str> {
+        match self {
+            #[cfg(test)]
+            Self::DidExample => Some("example"),
+            Self::Extension(method) => method.strip_prefix("did:").filter(|m| !m.is_empty()),
+            _ => None,
+        }
+    }
+
+    /// Returns whether both binding methods are compatible, `did:` matching any DID method.
+    pub fn matches(&self, other: &Self) -> bool {
+        self == other
+            || (*self == Self::Did && other.is_did())
+            || (*other == Self::Did && self.is_did())
+    }
+}
+
 #[serde_as]
 #[skip_serializing_none]
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -269,7 +625,10 @@ impl MetadataBackgroundImage {
 
 #[cfg(test)]
 mod test {
-    use crate::profiles::core::profiles::CoreProfilesCredentialConfiguration;
+    use crate::{
+        metadata::DiscoveryError, profiles::core::profiles::CoreProfilesCredentialConfiguration,
+    };
+    use oauth2::HttpResponse;
     use serde_json::json;
 
     use super::*;
@@ -372,6 +731,352 @@ mod test {
         })).unwrap();
     }
 
+    #[test]
+    fn configurations_supporting_binding() {
+        let metadata: CredentialIssuerMetadata<CoreProfilesCredentialConfiguration> =
+            serde_json::from_value(json!({
+                "credential_issuer": "https://credential-issuer.example.com",
+                "credential_endpoint": "https://credential-issuer.example.com",
+                "credential_configurations_supported": {
+                    "UniversityDegreeCredential": {
+                        "format": "jwt_vc_json",
+                        "cryptographic_binding_methods_supported": [
+                            "did:key",
+                            "did:jwk"
+                        ],
+                        "credential_definition":{
+                            "type": [
+                                "VerifiableCredential",
+                                "UniversityDegreeCredential"
+                            ]
+                        }
+                    },
+                    "org.iso.18013.5.1.mDL": {
+                        "format": "mso_mdoc",
+                        "doctype": "org.iso.18013.5.1.mDL",
+                        "cryptographic_binding_methods_supported": [
+                            "cose_key",
+                            "mso"
+                        ]
+                    }
+                }
+            }))
+            .unwrap();
+
+        let ids = |methods: &[CryptographicBindingMethod]| {
+            metadata
+                .configurations_supporting_binding(methods)
+                .map(|c| c.id().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            ids(&[CryptographicBindingMethod::Extension("did:key".into())]),
+            vec!["UniversityDegreeCredential"]
+        );
+        assert_eq!(
+            ids(&[CryptographicBindingMethod::Did]),
+            vec!["UniversityDegreeCredential"]
+        );
+        assert_eq!(
+            ids(&[CryptographicBindingMethod::Cose]),
+            vec!["org.iso.18013.5.1.mDL"]
+        );
+        assert!(ids(&[CryptographicBindingMethod::Jwk]).is_empty());
+    }
+
+    #[test]
+    fn configurations_by_id() {
+        let metadata: CredentialIssuerMetadata<CoreProfilesCredentialConfiguration> =
+            serde_json::from_value(json!({
+                "credential_issuer": "https://credential-issuer.example.com",
+                "credential_endpoint": "https://credential-issuer.example.com",
+                "credential_configurations_supported": {
+                    "UniversityDegreeCredential": {
+                        "format": "jwt_vc_json",
+                        "credential_definition":{
+                            "type": [
+                                "VerifiableCredential",
+                                "UniversityDegreeCredential"
+                            ]
+                        }
+                    },
+                    "org.iso.18013.5.1.mDL": {
+                        "format": "mso_mdoc",
+                        "doctype": "org.iso.18013.5.1.mDL"
+                    }
+                }
+            }))
+            .unwrap();
+
+        let configurations = metadata.configurations_by_id();
+        assert_eq!(configurations.len(), 2);
+
+        let id = CredentialConfigurationId::new("org.iso.18013.5.1.mDL".into());
+        assert_eq!(configurations[&id].id(), &id);
+        assert!(!configurations
+            .contains_key(&CredentialConfigurationId::new("UnknownCredential".into())));
+    }
+
+    #[test]
+    fn discover_with_warnings() {
+        let http_client = |request: oauth2::HttpRequest| -> Result<HttpResponse, std::io::Error> {
+            assert_eq!(
+                request.uri(),
+                "https://credential-issuer.example.com/.well-known/openid-credential-issuer"
+            );
+            Ok(oauth2::http::Response::builder()
+                .status(200)
+                .header(oauth2::http::header::CONTENT_TYPE, "application/json")
+                .body(
+                    serde_json::to_vec(&json!({
+                        "credential_issuer": "https://credential-issuer.example.com",
+                        "credential_endpoint": "https://credential-issuer.example.com/credential",
+                        "credential_configurations_supported": {
+                            "UniversityDegreeCredential": {
+                                "format": "jwt_vc_json",
+                                "credential_definition": {
+                                    "type": ["VerifiableCredential", "UniversityDegreeCredential"]
+                                }
+                            },
+                            "UnknownCredential": {
+                                "format": "unknown_format"
+                            }
+                        }
+                    }))
+                    .unwrap(),
+                )
+                .unwrap())
+        };
+        let issuer = IssuerUrl::new("https://credential-issuer.example.com".into()).unwrap();
+
+        assert!(matches!(
+            CredentialIssuerMetadata::<CoreProfilesCredentialConfiguration>::discover(
+                &issuer,
+                &http_client
+            ),
+            Err(DiscoveryError::Validation { .. })
+        ));
+
+        let (metadata, warnings) =
+            CredentialIssuerMetadata::<CoreProfilesCredentialConfiguration>::discover_with_warnings(
+                &issuer,
+                &http_client,
+                true,
+            )
+            .unwrap();
+        assert_eq!(metadata.credential_configurations_supported().len(), 1);
+        assert_eq!(
+            metadata.credential_configurations_supported()[0]
+                .id()
+                .as_str(),
+            "UniversityDegreeCredential"
+        );
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].id, "UnknownCredential");
+    }
+
+    #[test]
+    fn display_logo_uri_or_url() {
+        let uri: MetadataDisplayLogo = serde_json::from_value(json!({
+            "uri": "https://university.example.edu/public/logo.png",
+            "alt_text": "a square logo of a university"
+        }))
+        .unwrap();
+        let url: MetadataDisplayLogo = serde_json::from_value(json!({
+            "url": "https://university.example.edu/public/logo.png",
+            "alt_text": "a square logo of a university"
+        }))
+        .unwrap();
+        assert_eq!(uri, url);
+
+        assert_json_diff::assert_json_eq!(
+            serde_json::to_value(&url).unwrap(),
+            json!({
+                "uri": "https://university.example.edu/public/logo.png",
+                "alt_text": "a square logo of a university"
+            })
+        );
+    }
+
+    #[test]
+    fn configuration_binding_methods() {
+        let configuration: CredentialConfiguration<CoreProfilesCredentialConfiguration> =
+            serde_json::from_value(json!({
+                "$key$": "UniversityDegreeCredential",
+                "format": "jwt_vc_json",
+                "cryptographic_binding_methods_supported": ["did:key", "jwk", "mso"],
+                "credential_definition": {
+                    "type": ["VerifiableCredential", "UniversityDegreeCredential"]
+                }
+            }))
+            .unwrap();
+
+        assert!(configuration
+            .supports_binding(&CryptographicBindingMethod::Extension("did:key".into())));
+        assert!(configuration.supports_binding(&CryptographicBindingMethod::Did));
+        assert!(configuration.supports_binding(&CryptographicBindingMethod::Jwk));
+        assert!(configuration.supports_binding(&CryptographicBindingMethod::MSO));
+        assert!(!configuration.supports_binding(&CryptographicBindingMethod::Cose));
+        assert!(!configuration
+            .supports_binding(&CryptographicBindingMethod::Extension("did:jwk".into())));
+        assert_eq!(configuration.did_methods_supported(), vec!["key"]);
+    }
+
+    #[test]
+    fn example_credential_issuer_metadata_previous_drafts() {
+        let previous: CredentialIssuerMetadata<CoreProfilesCredentialConfiguration> =
+            serde_json::from_value(json!({
+                "credential_issuer": "https://credential-issuer.example.com",
+                "credential_endpoint": "https://credential-issuer.example.com",
+                "credentials_supported": {
+                    "UniversityDegreeCredential": {
+                        "format": "jwt_vc_json",
+                        "cryptographic_suites_supported": ["ES256"],
+                        "credential_definition": {
+                            "types": [
+                                "VerifiableCredential",
+                                "UniversityDegreeCredential"
+                            ]
+                        }
+                    }
+                }
+            }))
+            .unwrap();
+        let current: CredentialIssuerMetadata<CoreProfilesCredentialConfiguration> =
+            serde_json::from_value(json!({
+                "credential_issuer": "https://credential-issuer.example.com",
+                "credential_endpoint": "https://credential-issuer.example.com",
+                "credential_configurations_supported": {
+                    "UniversityDegreeCredential": {
+                        "format": "jwt_vc_json",
+                        "credential_signing_alg_values_supported": ["ES256"],
+                        "credential_definition": {
+                            "type": [
+                                "VerifiableCredential",
+                                "UniversityDegreeCredential"
+                            ]
+                        }
+                    }
+                }
+            }))
+            .unwrap();
+
+        assert_eq!(previous, current);
+    }
+
+    #[test]
+    fn check_batch_issuance() {
+        let issuer = IssuerUrl::new("https://credential-issuer.example.com".into()).unwrap();
+        let metadata = |value| {
+            serde_json::from_value::<CredentialIssuerMetadata<CoreProfilesCredentialConfiguration>>(
+                value,
+            )
+            .unwrap()
+        };
+
+        let batch_endpoint = metadata(json!({
+            "credential_issuer": "https://credential-issuer.example.com",
+            "credential_endpoint": "https://credential-issuer.example.com/credential",
+            "batch_credential_endpoint": "https://credential-issuer.example.com/batch_credential"
+        }));
+        batch_endpoint.check_batch_issuance().unwrap();
+
+        let both = metadata(json!({
+            "credential_issuer": "https://credential-issuer.example.com",
+            "credential_endpoint": "https://credential-issuer.example.com/credential",
+            "batch_credential_endpoint": "https://credential-issuer.example.com/batch_credential",
+            "batch_credential_issuance": { "batch_size": 10 }
+        }));
+        assert!(matches!(
+            both.check_batch_issuance(),
+            Err(BatchIssuanceError::Ambiguous)
+        ));
+        both.validate(&issuer).unwrap();
+
+        let invalid_batch_size = metadata(json!({
+            "credential_issuer": "https://credential-issuer.example.com",
+            "credential_endpoint": "https://credential-issuer.example.com/credential",
+            "batch_credential_issuance": { "batch_size": 1 }
+        }));
+        assert!(matches!(
+            invalid_batch_size.check_batch_issuance(),
+            Err(BatchIssuanceError::InvalidBatchSize(1))
+        ));
+        invalid_batch_size.validate(&issuer).unwrap_err();
+    }
+
+    #[test]
+    fn find_configuration_streaming() {
+        let configurations: Map<String, Value> = (0..500)
+            .map(|i| {
+                (
+                    format!("Credential{i}"),
+                    json!({
+                        "format": "jwt_vc_json",
+                        "credential_signing_alg_values_supported": ["ES256"],
+                        "credential_definition": {
+                            "type": ["VerifiableCredential", format!("Credential{i}")]
+                        },
+                        "display": [{ "name": format!("Credential {i}"), "locale": "en-US" }]
+                    }),
+                )
+            })
+            .collect();
+        let body = serde_json::to_vec(&json!({
+            "credential_issuer": "https://credential-issuer.example.com",
+            "credential_endpoint": "https://credential-issuer.example.com/credential",
+            "credential_configurations_supported": configurations
+        }))
+        .unwrap();
+        let id = CredentialConfigurationId::new("Credential250".into());
+
+        let metadata: CredentialIssuerMetadata<CoreProfilesCredentialConfiguration> =
+            serde_json::from_slice(&body).unwrap();
+        let eager = metadata
+            .credential_configurations_supported()
+            .iter()
+            .find(|configuration| configuration.id() == &id)
+            .cloned();
+
+        let streaming = CredentialIssuerMetadata::<CoreProfilesCredentialConfiguration>::find_configuration_streaming(
+            &body, &id,
+        )
+        .unwrap();
+        assert!(streaming.is_some());
+        assert_eq!(streaming, eager);
+
+        let missing =
+            CredentialIssuerMetadata::<CoreProfilesCredentialConfiguration>::find_configuration_streaming(
+                &body,
+                &CredentialConfigurationId::new("Credential500".into()),
+            )
+            .unwrap();
+        assert!(missing.is_none());
+    }
+
+    #[test]
+    fn additional_fields_roundtrip() {
+        let expected_json = json!({
+            "credential_issuer": "https://credential-issuer.example.com",
+            "credential_endpoint": "https://credential-issuer.example.com/credential",
+            "credential_configurations_supported": {},
+            "logo": {
+                "uri": "https://credential-issuer.example.com/logo.png"
+            }
+        });
+
+        let metadata: CredentialIssuerMetadata<CoreProfilesCredentialConfiguration> =
+            serde_json::from_value(expected_json.clone()).unwrap();
+        assert_eq!(
+            metadata.additional_fields()["logo"]["uri"],
+            "https://credential-issuer.example.com/logo.png"
+        );
+
+        let roundtripped = serde_json::to_value(metadata).unwrap();
+        assert_json_diff::assert_json_eq!(expected_json, roundtripped);
+    }
+
     #[test]
     fn example_credential_metadata_jwt() {
         let _: CredentialConfiguration<CoreProfilesCredentialConfiguration> =
@@ -443,6 +1148,132 @@ mod test {
             .unwrap();
     }
 
+    #[test]
+    fn example_credential_metadata_attestation_proof_type() {
+        let configuration: CredentialConfiguration<CoreProfilesCredentialConfiguration> =
+            serde_json::from_value(json!({
+                "$key$": "name", // purely for test reason, you cannot really deserialize CredentialMetadata on its own
+                "format": "jwt_vc_json",
+                "credential_definition":{
+                    "type": [
+                        "VerifiableCredential",
+                        "UniversityDegreeCredential"
+                    ]
+                },
+                "proof_types_supported": {
+                    "jwt": {
+                        "proof_signing_alg_values_supported": [
+                            "ES256"
+                        ]
+                    },
+                    "attestation": {
+                        "proof_signing_alg_values_supported": [
+                            "ES256"
+                        ],
+                        "key_attestations_required": {
+                            "key_storage": [ "iso_18045_moderate" ],
+                            "user_authentication": [ "iso_18045_moderate" ]
+                        }
+                    }
+                }
+            }))
+            .unwrap();
+
+        let proof_types = configuration.proof_types_supported().unwrap();
+        assert_eq!(proof_types.len(), 2);
+        assert!(proof_types
+            .iter()
+            .any(|proof_type| proof_type.key() == &KeyProofType::Attestation
+                && proof_type.key_attestations_required().is_some()));
+    }
+
+    #[test]
+    fn select_proof_alg() {
+        let configuration: CredentialConfiguration<CoreProfilesCredentialConfiguration> =
+            serde_json::from_value(json!({
+                "$key$": "name",
+                "format": "jwt_vc_json",
+                "credential_definition":{
+                    "type": [
+                        "VerifiableCredential",
+                        "UniversityDegreeCredential"
+                    ]
+                },
+                "proof_types_supported": {
+                    "jwt": {
+                        "proof_signing_alg_values_supported": [
+                            "ES256",
+                            "EdDSA"
+                        ]
+                    },
+                    "attestation": {
+                        "proof_signing_alg_values_supported": [
+                            "ES384"
+                        ]
+                    }
+                }
+            }))
+            .unwrap();
+
+        assert_eq!(configuration.select_proof_alg(&[Algorithm::RS256]), None);
+        assert_eq!(
+            configuration.select_proof_alg(&[Algorithm::ES384]),
+            Some((KeyProofType::Attestation, Algorithm::ES384))
+        );
+        assert_eq!(
+            configuration.select_proof_alg(&[Algorithm::ES384, Algorithm::EdDSA, Algorithm::ES256]),
+            Some((KeyProofType::Jwt, Algorithm::EdDSA))
+        );
+        assert_eq!(
+            configuration
+                .clone()
+                .set_proof_types_supported(None)
+                .select_proof_alg(&[Algorithm::ES256]),
+            None
+        );
+
+        // Proof types accepting the same algorithm are selected by preference, whatever the order
+        // in which the issuer lists them.
+        let configuration: CredentialConfiguration<CoreProfilesCredentialConfiguration> =
+            serde_json::from_value(json!({
+                "$key$": "name",
+                "format": "jwt_vc_json",
+                "credential_definition":{
+                    "type": [
+                        "VerifiableCredential",
+                        "UniversityDegreeCredential"
+                    ]
+                },
+                "proof_types_supported": {
+                    "attestation": {
+                        "proof_signing_alg_values_supported": [
+                            "ES256"
+                        ]
+                    },
+                    "jwt": {
+                        "proof_signing_alg_values_supported": [
+                            "ES256"
+                        ]
+                    }
+                }
+            }))
+            .unwrap();
+        assert_eq!(
+            configuration.select_proof_alg(&[Algorithm::ES256]),
+            Some((KeyProofType::Jwt, Algorithm::ES256))
+        );
+        let reversed = configuration.clone().set_proof_types_supported(
+            configuration
+                .proof_types_supported()
+                .clone()
+                .map(|proof_types| proof_types.into_iter().rev().collect()),
+        );
+        assert_eq!(
+            reversed.select_proof_alg(&[Algorithm::ES256]),
+            Some((KeyProofType::Jwt, Algorithm::ES256))
+        );
+    }
+
     #[test]
     fn example_credential_metadata_ldp() {
         let _: CredentialConfiguration<CoreProfilesCredentialConfiguration> =
@@ -0,0 +1,97 @@
+use anyhow::{bail, Result};
+use oauth2::{AsyncHttpClient, SyncHttpClient};
+use serde::{Deserialize, Serialize};
+use serde_with::skip_serializing_none;
+
+use crate::types::{IssuerUrl, JsonWebKeySet, JsonWebKeySetUrl};
+
+use super::{
+    authorization_server::jwks_response, discovery_request, DiscoveryError, MetadataDiscovery,
+    WellKnownPlacement,
+};
+
+/// JWT VC Issuer Metadata, as defined by
+/// [SD-JWT VC](https://datatracker.ietf.org/doc/html/draft-ietf-oauth-sd-jwt-vc#section-5),
+/// publishing the keys the issuer signs JWTs with.
+#[skip_serializing_none]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JwtVcIssuerMetadata {
+    issuer: IssuerUrl,
+    jwks_uri: Option<JsonWebKeySetUrl>,
+    jwks: Option<JsonWebKeySet>,
+}
+
+impl JwtVcIssuerMetadata {
+    pub fn new(issuer: IssuerUrl) -> Self {
+        Self {
+            issuer,
+            jwks_uri: None,
+            jwks: None,
+        }
+    }
+
+    field_getters_setters![
+        pub self [self] ["JWT VC issuer metadata value"] {
+            set_issuer -> issuer[IssuerUrl],
+            set_jwks_uri -> jwks_uri[Option<JsonWebKeySetUrl>],
+            set_jwks -> jwks[Option<JsonWebKeySet>],
+        }
+    ];
+
+    /// Returns the JWK Set of the issuer, i.e. the inline `jwks` if present, or the document
+    /// fetched from `jwks_uri`, or `None` if the metadata has neither.
+    pub fn resolve_jwks<C>(&self, http_client: &C) -> Result<Option<JsonWebKeySet>, DiscoveryError>
+    where
+        C: SyncHttpClient,
+        C::Error: Send + Sync,
+    {
+        if let Some(jwks) = &self.jwks {
+            return Ok(Some(jwks.clone()));
+        }
+        let Some(jwks_uri) = &self.jwks_uri else {
+            return Ok(None);
+        };
+        let http_response = http_client
+            .call(discovery_request(jwks_uri.url())?)
+            .map_err(|e| DiscoveryError::Network(e.into()))?;
+        jwks_response(jwks_uri, http_response).map(Some)
+    }
+
+    /// Async version of [`JwtVcIssuerMetadata::resolve_jwks`].
+    pub async fn resolve_jwks_async<'c, C>(
+        &self,
+        http_client: &'c C,
+    ) -> Result<Option<JsonWebKeySet>, DiscoveryError>
+    where
+        C: AsyncHttpClient<'c>,
+        C::Error: Send + Sync,
+    {
+        if let Some(jwks) = &self.jwks {
+            return Ok(Some(jwks.clone()));
+        }
+        let Some(jwks_uri) = &self.jwks_uri else {
+            return Ok(None);
+        };
+        let http_response = http_client
+            .call(discovery_request(jwks_uri.url())?)
+            .await
+            .map_err(|e| DiscoveryError::Network(e.into()))?;
+        jwks_response(jwks_uri, http_response).map(Some)
+    }
+}
+
+impl MetadataDiscovery for JwtVcIssuerMetadata {
+    const METADATA_URL_SUFFIX: &'static str = ".well-known/jwt-vc-issuer";
+    const PREFERRED_WELL_KNOWN_PLACEMENT: WellKnownPlacement = WellKnownPlacement::Inserted;
+
+    fn validate(&self, issuer: &IssuerUrl) -> Result<()> {
+        if self.issuer() != issuer {
+            bail!(
+                "unexpected issuer URI `{}` (expected `{}`)",
+                self.issuer().as_str(),
+                issuer.as_str()
+            )
+        }
+        Ok(())
+    }
+}
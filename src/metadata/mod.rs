@@ -2,99 +2,405 @@
 
 use std::future::Future;
 
-use anyhow::{bail, Context, Result};
+use anyhow::{Context, Result};
 use oauth2::{
     http::{self, header::ACCEPT, HeaderValue, Method, StatusCode},
     AsyncHttpClient, HttpRequest, HttpResponse, SyncHttpClient,
 };
 use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
 use url::Url;
 
 use crate::{
-    http_utils::{check_content_type, MIME_TYPE_JSON},
+    http_utils::{cache_max_age, check_content_type, MIME_TYPE_JSON},
     types::IssuerUrl,
 };
 
 pub mod authorization_server;
+pub mod cache;
 pub mod credential_issuer;
+pub mod jwt_vc_issuer;
 
 pub use authorization_server::AuthorizationServerMetadata;
-pub use credential_issuer::CredentialIssuerMetadata;
+pub use cache::{InMemoryMetadataCache, MetadataCache};
+pub use credential_issuer::{ConfigurationWarning, CredentialIssuerMetadata};
+pub use jwt_vc_issuer::JwtVcIssuerMetadata;
+
+/// Errors that can occur when discovering metadata.
+#[derive(Debug, thiserror::Error)]
+pub enum DiscoveryError {
+    #[error("failed to prepare discovery request")]
+    Request(#[source] anyhow::Error),
+    #[error("failed to fetch metadata")]
+    Network(#[source] anyhow::Error),
+    #[error("HTTP status code {status} at {url}")]
+    Status { status: StatusCode, url: Url },
+    #[error("invalid metadata at {url}")]
+    Validation {
+        url: Url,
+        #[source]
+        source: anyhow::Error,
+    },
+    #[error("no authorization server supports the {0:?} grant type")]
+    UnsupportedGrantType(authorization_server::GrantType),
+}
+
+/// Where the metadata URL suffix is placed for issuers hosted under a path.
+///
+/// Specifications disagree on this, so discovery tries the preferred placement first, and falls
+/// back to the other one if the metadata is not found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WellKnownPlacement {
+    /// Appended to the path, see [`IssuerUrl::join`].
+    Appended,
+    /// Inserted between the host and the path, see [`IssuerUrl::join_well_known`].
+    Inserted,
+}
 
 pub trait MetadataDiscovery: DeserializeOwned + Serialize {
     const METADATA_URL_SUFFIX: &'static str;
+    const PREFERRED_WELL_KNOWN_PLACEMENT: WellKnownPlacement;
 
     fn validate(&self, issuer: &IssuerUrl) -> Result<()>;
 
-    fn discover<C>(issuer: &IssuerUrl, http_client: &C) -> Result<Self>
+    /// Parses the metadata, skipping the parts that fail to parse instead of failing, see
+    /// [`MetadataDiscovery::discover_with_warnings`].
+    ///
+    /// By default, nothing is skipped.
+    fn from_json_lenient(
+        value: Value,
+    ) -> Result<(Self, Vec<ConfigurationWarning>), serde_json::Error> {
+        Ok((serde_json::from_value(value)?, Vec::new()))
+    }
+
+    fn discover<C>(issuer: &IssuerUrl, http_client: &C) -> Result<Self, DiscoveryError>
+    where
+        C: SyncHttpClient,
+        C::Error: Send + Sync,
+    {
+        Self::discover_with_warnings(issuer, http_client, false).map(|(metadata, _)| metadata)
+    }
+
+    fn discover_async<'c, C>(
+        issuer: &IssuerUrl,
+        http_client: &'c C,
+    ) -> impl Future<Output = Result<Self, DiscoveryError>>
+    where
+        C: AsyncHttpClient<'c>,
+        C::Error: Send + Sync,
+    {
+        Box::pin(async move {
+            Self::discover_with_warnings_async(issuer, http_client, false)
+                .await
+                .map(|(metadata, _)| metadata)
+        })
+    }
+
+    /// Same as [`MetadataDiscovery::discover`], but if `lenient` is set, the parts of the
+    /// metadata that fail to parse are skipped, see [`MetadataDiscovery::from_json_lenient`], and
+    /// returned as warnings.
+    fn discover_with_warnings<C>(
+        issuer: &IssuerUrl,
+        http_client: &C,
+        lenient: bool,
+    ) -> Result<(Self, Vec<ConfigurationWarning>), DiscoveryError>
     where
         C: SyncHttpClient,
         C::Error: Send + Sync,
     {
-        let discovery_url = discovery_url::<Self>(issuer)?;
+        let (mut discovery_url, fallback_url) = discovery_urls::<Self>(issuer)?;
 
-        let discovery_request = discovery_request(&discovery_url)?;
+        let call = |discovery_url: &Url| -> Result<HttpResponse, DiscoveryError> {
+            http_client
+                .call(discovery_request(discovery_url)?)
+                .map_err(|e| DiscoveryError::Network(e.into()))
+        };
 
-        let http_response = http_client.call(discovery_request)?;
+        let mut http_response = call(&discovery_url)?;
+        if let Some(fallback_url) = fallback_url.filter(|_| is_not_found(&http_response)) {
+            http_response = call(&fallback_url)?;
+            discovery_url = fallback_url;
+        }
 
-        discovery_response(issuer, &discovery_url, http_response)
+        discovery_response(issuer, &discovery_url, http_response, lenient)
     }
 
-    fn discover_async<'c, C>(
+    /// Same as [`MetadataDiscovery::discover_async`], but if `lenient` is set, the parts of the
+    /// metadata that fail to parse are skipped, see [`MetadataDiscovery::from_json_lenient`], and
+    /// returned as warnings.
+    fn discover_with_warnings_async<'c, C>(
         issuer: &IssuerUrl,
         http_client: &'c C,
-    ) -> impl Future<Output = Result<Self>>
+        lenient: bool,
+    ) -> impl Future<Output = Result<(Self, Vec<ConfigurationWarning>), DiscoveryError>>
     where
         C: AsyncHttpClient<'c>,
         C::Error: Send + Sync,
     {
         Box::pin(async move {
-            let discovery_url = discovery_url::<Self>(issuer)?;
+            let (mut discovery_url, fallback_url) = discovery_urls::<Self>(issuer)?;
 
-            let discovery_request = discovery_request(&discovery_url)?;
+            let mut http_response = http_client
+                .call(discovery_request(&discovery_url)?)
+                .await
+                .map_err(|e| DiscoveryError::Network(e.into()))?;
+            if let Some(fallback_url) = fallback_url.filter(|_| is_not_found(&http_response)) {
+                http_response = http_client
+                    .call(discovery_request(&fallback_url)?)
+                    .await
+                    .map_err(|e| DiscoveryError::Network(e.into()))?;
+                discovery_url = fallback_url;
+            }
 
-            let http_response = http_client.call(discovery_request).await?;
+            discovery_response(issuer, &discovery_url, http_response, lenient)
+        })
+    }
 
-            discovery_response(issuer, &discovery_url, http_response)
+    /// Same as [`MetadataDiscovery::discover`], but first looks up the metadata in `cache`.
+    ///
+    /// Fetched metadata is stored in `cache` for the duration of the `Cache-Control: max-age`
+    /// directive of the discovery response, if any.
+    fn discover_cached<C, MC>(
+        issuer: &IssuerUrl,
+        http_client: &C,
+        cache: &MC,
+    ) -> Result<Self, DiscoveryError>
+    where
+        C: SyncHttpClient,
+        C::Error: Send + Sync,
+        MC: MetadataCache + ?Sized,
+    {
+        let (mut discovery_url, fallback_url) = discovery_urls::<Self>(issuer)?;
+
+        if let Some(metadata) = cached_metadata(&discovery_url, fallback_url.as_ref(), cache) {
+            return Ok(metadata);
+        }
+
+        let call = |discovery_url: &Url| -> Result<HttpResponse, DiscoveryError> {
+            http_client
+                .call(discovery_request(discovery_url)?)
+                .map_err(|e| DiscoveryError::Network(e.into()))
+        };
+
+        let mut http_response = call(&discovery_url)?;
+        if let Some(fallback_url) = fallback_url.filter(|_| is_not_found(&http_response)) {
+            http_response = call(&fallback_url)?;
+            discovery_url = fallback_url;
+        }
+
+        cache_discovery_response(issuer, discovery_url, http_response, cache)
+    }
+
+    /// Same as [`MetadataDiscovery::discover_async`], but first looks up the metadata in `cache`.
+    ///
+    /// Fetched metadata is stored in `cache` for the duration of the `Cache-Control: max-age`
+    /// directive of the discovery response, if any.
+    fn discover_cached_async<'c, C, MC>(
+        issuer: &IssuerUrl,
+        http_client: &'c C,
+        cache: &MC,
+    ) -> impl Future<Output = Result<Self, DiscoveryError>>
+    where
+        C: AsyncHttpClient<'c>,
+        C::Error: Send + Sync,
+        MC: MetadataCache + ?Sized,
+    {
+        Box::pin(async move {
+            let (mut discovery_url, fallback_url) = discovery_urls::<Self>(issuer)?;
+
+            if let Some(metadata) = cached_metadata(&discovery_url, fallback_url.as_ref(), cache) {
+                return Ok(metadata);
+            }
+
+            let mut http_response = http_client
+                .call(discovery_request(&discovery_url)?)
+                .await
+                .map_err(|e| DiscoveryError::Network(e.into()))?;
+            if let Some(fallback_url) = fallback_url.filter(|_| is_not_found(&http_response)) {
+                http_response = http_client
+                    .call(discovery_request(&fallback_url)?)
+                    .await
+                    .map_err(|e| DiscoveryError::Network(e.into()))?;
+                discovery_url = fallback_url;
+            }
+
+            cache_discovery_response(issuer, discovery_url, http_response, cache)
         })
     }
 }
 
-fn discovery_url<M: MetadataDiscovery>(issuer: &IssuerUrl) -> Result<Url> {
-    issuer
-        .join(M::METADATA_URL_SUFFIX)
+/// Returns the metadata URL with the preferred placement of the suffix, and the URL with the
+/// other placement if it differs, i.e. if the issuer URL has a path.
+fn discovery_urls<M: MetadataDiscovery>(
+    issuer: &IssuerUrl,
+) -> Result<(Url, Option<Url>), DiscoveryError> {
+    let appended = issuer.join(M::METADATA_URL_SUFFIX);
+    let inserted = issuer.join_well_known(M::METADATA_URL_SUFFIX);
+    let (preferred, fallback) = match M::PREFERRED_WELL_KNOWN_PLACEMENT {
+        WellKnownPlacement::Appended => (appended, inserted),
+        WellKnownPlacement::Inserted => (inserted, appended),
+    };
+    let (preferred, fallback) = preferred
+        .and_then(|preferred| Ok((preferred, fallback?)))
         .context("failed to construct metadata URL")
+        .map_err(DiscoveryError::Request)?;
+    if preferred == fallback {
+        Ok((preferred, None))
+    } else {
+        Ok((preferred, Some(fallback)))
+    }
 }
 
-fn discovery_request(discovery_url: &Url) -> Result<HttpRequest> {
+fn is_not_found(http_response: &HttpResponse) -> bool {
+    http_response.status() == StatusCode::NOT_FOUND
+}
+
+fn discovery_request(discovery_url: &Url) -> Result<HttpRequest, DiscoveryError> {
     http::Request::builder()
         .uri(discovery_url.to_string())
         .method(Method::GET)
         .header(ACCEPT, HeaderValue::from_static(MIME_TYPE_JSON))
         .body(Vec::new())
         .context("failed to prepare request")
+        .map_err(DiscoveryError::Request)
+}
+
+fn cached_metadata<M: MetadataDiscovery, MC: MetadataCache + ?Sized>(
+    discovery_url: &Url,
+    fallback_url: Option<&Url>,
+    cache: &MC,
+) -> Option<M> {
+    // Entries that no longer deserialize are treated as cache misses.
+    std::iter::once(discovery_url)
+        .chain(fallback_url)
+        .find_map(|url| serde_json::from_value(cache.get(url)?).ok())
+}
+
+fn cache_discovery_response<M: MetadataDiscovery, MC: MetadataCache + ?Sized>(
+    issuer: &IssuerUrl,
+    discovery_url: Url,
+    http_response: HttpResponse,
+    cache: &MC,
+) -> Result<M, DiscoveryError> {
+    let max_age = cache_max_age(http_response.headers());
+
+    let (metadata, _): (M, _) = discovery_response(issuer, &discovery_url, http_response, false)?;
+
+    if let Some(max_age) = max_age {
+        let value = serde_json::to_value(&metadata).map_err(|e| DiscoveryError::Validation {
+            url: discovery_url.clone(),
+            source: e.into(),
+        })?;
+        cache.insert(discovery_url, value, max_age);
+    }
+
+    Ok(metadata)
+}
+
+fn check_discovery_response(
+    discovery_url: &Url,
+    discovery_response: &HttpResponse,
+) -> Result<(), DiscoveryError> {
+    if discovery_response.status() != StatusCode::OK {
+        return Err(DiscoveryError::Status {
+            status: discovery_response.status(),
+            url: discovery_url.clone(),
+        });
+    }
+
+    check_content_type(discovery_response.headers(), MIME_TYPE_JSON).map_err(|source| {
+        DiscoveryError::Validation {
+            url: discovery_url.clone(),
+            source,
+        }
+    })
 }
 
 fn discovery_response<M: MetadataDiscovery>(
     issuer: &IssuerUrl,
     discovery_url: &Url,
     discovery_response: HttpResponse,
-) -> Result<M> {
-    if discovery_response.status() != StatusCode::OK {
-        bail!(
-            "HTTP status code {} at {}",
-            discovery_response.status(),
-            discovery_url
+    lenient: bool,
+) -> Result<(M, Vec<ConfigurationWarning>), DiscoveryError> {
+    check_discovery_response(discovery_url, &discovery_response)?;
+
+    let validation_error = |source| DiscoveryError::Validation {
+        url: discovery_url.clone(),
+        source,
+    };
+
+    let (metadata, warnings) = if lenient {
+        serde_json::from_slice(discovery_response.body())
+            .and_then(M::from_json_lenient)
+            .map_err(|e| validation_error(e.into()))?
+    } else {
+        let metadata = serde_path_to_error::deserialize::<_, M>(
+            &mut serde_json::Deserializer::from_slice(discovery_response.body()),
         )
+        .map_err(|e| validation_error(e.into()))?;
+        (metadata, Vec::new())
+    };
+
+    metadata.validate(issuer).map_err(validation_error)?;
+
+    Ok((metadata, warnings))
+}
+
+#[cfg(test)]
+mod test {
+    use oauth2::http::header::CONTENT_TYPE;
+    use serde_json::json;
+
+    use crate::profiles::core::metadata::CredentialIssuerMetadata;
+
+    use super::*;
+
+    fn http_client(
+        served_url: &'static str,
+    ) -> impl Fn(HttpRequest) -> Result<HttpResponse, std::io::Error> {
+        move |request: HttpRequest| {
+            if request.uri() != served_url {
+                return Ok(http::Response::builder().status(404).body(vec![]).unwrap());
+            }
+            Ok(http::Response::builder()
+                .status(200)
+                .header(CONTENT_TYPE, MIME_TYPE_JSON)
+                .body(
+                    serde_json::to_vec(&json!({
+                        "credential_issuer": "https://example.com/issuer1",
+                        "credential_endpoint": "https://example.com/issuer1/credential"
+                    }))
+                    .unwrap(),
+                )
+                .unwrap())
+        }
     }
 
-    check_content_type(discovery_response.headers(), MIME_TYPE_JSON)?;
+    #[test]
+    fn discover_subpath_issuer() {
+        let issuer = IssuerUrl::new("https://example.com/issuer1".into()).unwrap();
 
-    let metadata = serde_path_to_error::deserialize::<_, M>(
-        &mut serde_json::Deserializer::from_slice(discovery_response.body()),
-    )?;
+        for served_url in [
+            "https://example.com/issuer1/.well-known/openid-credential-issuer",
+            "https://example.com/.well-known/openid-credential-issuer/issuer1",
+        ] {
+            let metadata =
+                CredentialIssuerMetadata::discover(&issuer, &http_client(served_url)).unwrap();
+            assert_eq!(metadata.credential_issuer(), &issuer);
+        }
+    }
 
-    metadata.validate(issuer)?;
+    #[test]
+    fn discover_root_issuer_no_fallback() {
+        let issuer = IssuerUrl::new("https://example.com".into()).unwrap();
+        let (_, fallback_url) = discovery_urls::<CredentialIssuerMetadata>(&issuer).unwrap();
+        assert!(fallback_url.is_none());
 
-    Ok(metadata)
+        let err = CredentialIssuerMetadata::discover(
+            &issuer,
+            &http_client("https://example.com/issuer1/.well-known/openid-credential-issuer"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, DiscoveryError::Status { .. }));
+    }
 }
@@ -14,12 +14,91 @@ pub enum NotificationRequestEvent {
     CredentialDeleted,
 }
 
+/// Notification event, serialized as the `event` and `event_description` parameters.
+///
+/// An `event_description` sent with other events than a failure is ignored.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(
+    from = "NotificationEventParameters",
+    into = "NotificationEventParameters"
+)]
+pub enum NotificationEvent {
+    Accepted,
+    Failure { description: Option<String> },
+    Deleted,
+}
+
+impl NotificationEvent {
+    /// Failure event, with a human-readable `description` of the failure.
+    pub fn failure(description: impl Into<String>) -> Self {
+        Self::Failure {
+            description: Some(description.into()),
+        }
+    }
+
+    pub fn event_type(&self) -> NotificationRequestEvent {
+        match self {
+            Self::Accepted => NotificationRequestEvent::CredentialAccepted,
+            Self::Failure { .. } => NotificationRequestEvent::CredentialFailure,
+            Self::Deleted => NotificationRequestEvent::CredentialDeleted,
+        }
+    }
+}
+
 #[skip_serializing_none]
+#[derive(Deserialize, Serialize)]
+struct NotificationEventParameters {
+    event: NotificationRequestEvent,
+    event_description: Option<String>,
+}
+
+impl From<NotificationEventParameters> for NotificationEvent {
+    fn from(parameters: NotificationEventParameters) -> Self {
+        match parameters.event {
+            NotificationRequestEvent::CredentialAccepted => Self::Accepted,
+            NotificationRequestEvent::CredentialFailure => Self::Failure {
+                description: parameters.event_description,
+            },
+            NotificationRequestEvent::CredentialDeleted => Self::Deleted,
+        }
+    }
+}
+
+impl From<NotificationEvent> for NotificationEventParameters {
+    fn from(event: NotificationEvent) -> Self {
+        let event_type = event.event_type();
+        let event_description = match event {
+            NotificationEvent::Failure { description } => description,
+            NotificationEvent::Accepted | NotificationEvent::Deleted => None,
+        };
+        Self {
+            event: event_type,
+            event_description,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct NotificationRequest {
     notification_id: String,
-    event: NotificationRequestEvent,
-    event_description: Option<String>,
+    #[serde(flatten)]
+    event: NotificationEvent,
+}
+
+impl NotificationRequest {
+    pub fn new(notification_id: String, event: NotificationEvent) -> Self {
+        Self {
+            notification_id,
+            event,
+        }
+    }
+
+    field_getters_setters![
+        pub self [self] ["notification request value"] {
+            set_notification_id -> notification_id[String],
+            set_event -> event[NotificationEvent],
+        }
+    ];
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -49,12 +128,59 @@ mod test {
 
     #[test]
     fn example_notification_request_with_description() {
-        let _: NotificationRequest = serde_json::from_value(json!({
+        let request: NotificationRequest = serde_json::from_value(json!({
             "notification_id": "3fwe98js",
             "event": "credential_failure",
             "event_description": "Could not store the Credential. Out of storage."
         }))
         .unwrap();
+        assert_eq!(
+            request.event(),
+            &NotificationEvent::failure("Could not store the Credential. Out of storage.")
+        );
+    }
+
+    #[test]
+    fn notification_event_serialization() {
+        for (event, expected) in [
+            (
+                NotificationEvent::Accepted,
+                json!({
+                    "notification_id": "3fwe98js",
+                    "event": "credential_accepted"
+                }),
+            ),
+            (
+                NotificationEvent::Failure { description: None },
+                json!({
+                    "notification_id": "3fwe98js",
+                    "event": "credential_failure"
+                }),
+            ),
+            (
+                NotificationEvent::failure("Could not store the Credential. Out of storage."),
+                json!({
+                    "notification_id": "3fwe98js",
+                    "event": "credential_failure",
+                    "event_description": "Could not store the Credential. Out of storage."
+                }),
+            ),
+            (
+                NotificationEvent::Deleted,
+                json!({
+                    "notification_id": "3fwe98js",
+                    "event": "credential_deleted"
+                }),
+            ),
+        ] {
+            let request = NotificationRequest::new("3fwe98js".into(), event);
+            let value = serde_json::to_value(&request).unwrap();
+            assert_json_diff::assert_json_eq!(value, expected);
+            assert_eq!(
+                serde_json::from_value::<NotificationRequest>(value).unwrap(),
+                request
+            );
+        }
     }
 
     #[test]
@@ -1,4 +1,10 @@
-use std::{borrow::Cow, error::Error, future::Future, marker::PhantomData};
+use std::{
+    borrow::Cow,
+    error::Error,
+    future::Future,
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
 
 use base64::prelude::*;
 use oauth2::{
@@ -10,15 +16,18 @@ use oauth2::{
     AsyncHttpClient, AuthType, ClientId, ClientSecret, ErrorResponse, HttpRequest, HttpResponse,
     RequestTokenError, Scope, SyncHttpClient, TokenResponse, TokenUrl,
 };
-use serde::de::DeserializeOwned;
+use serde::{de::DeserializeOwned, Deserialize};
 use url::Url;
 
 use crate::{
-    http_utils::{MIME_TYPE_FORM_URLENCODED, MIME_TYPE_JSON},
+    http_utils::{no_store, MIME_TYPE_FORM_URLENCODED, MIME_TYPE_JSON},
     types::{PreAuthorizedCode, TxCode},
 };
 
-/// A request to exchange an authorization code for an access token.
+/// A request to exchange a pre-authorized code for an access token.
+///
+/// The request is sent with the `Cache-Control: no-store` and `Pragma: no-cache` headers, as
+/// token responses must not be persisted by HTTP caches.
 ///
 /// See <https://tools.ietf.org/html/rfc6749#section-4.1.3>.
 #[derive(Debug)]
@@ -34,6 +43,7 @@ where
     pub(crate) extra_params: Vec<(Cow<'a, str>, Cow<'a, str>)>,
     pub(crate) token_url: &'a TokenUrl,
     pub(crate) tx_code: Option<&'a TxCode>,
+    pub(crate) legacy_user_pin: bool,
     pub(crate) _phantom: PhantomData<(TE, TR)>,
 }
 impl<'a, TE, TR> PreAuthorizedCodeTokenRequest<'a, TE, TR>
@@ -65,6 +75,22 @@ where
 
     pub fn set_tx_code(mut self, tx_code: &'a TxCode) -> Self {
         self.tx_code = Some(tx_code);
+        self.legacy_user_pin = false;
+        self
+    }
+
+    /// Same as [`PreAuthorizedCodeTokenRequest::set_tx_code`], but sends the code in the
+    /// `user_pin` parameter of draft 11, for credential issuers that do not support `tx_code`.
+    pub fn set_user_pin_legacy(mut self, user_pin: &'a TxCode) -> Self {
+        self.tx_code = Some(user_pin);
+        self.legacy_user_pin = true;
+        self
+    }
+
+    /// Authenticates the client with `client_secret`, passed as configured by
+    /// [`crate::client::Client::set_auth_type`].
+    pub fn set_client_secret(mut self, client_secret: &'a ClientSecret) -> Self {
+        self.client_secret = Some(client_secret);
         self
     }
 
@@ -73,7 +99,7 @@ where
         self
     }
 
-    fn prepare_request<RE>(self) -> Result<HttpRequest, RequestTokenError<RE, TE>>
+    pub(crate) fn prepare_request<RE>(&self) -> Result<HttpRequest, RequestTokenError<RE, TE>>
     where
         RE: Error + 'static,
     {
@@ -86,7 +112,11 @@ where
         ];
 
         if let Some(tx_code) = self.tx_code {
-            params.push(("tx_code", tx_code.secret()))
+            if self.legacy_user_pin {
+                params.push(("user_pin", tx_code.secret()))
+            } else {
+                params.push(("tx_code", tx_code.secret()))
+            }
         }
 
         endpoint_request(
@@ -123,6 +153,96 @@ where
     {
         Box::pin(async move { endpoint_response(http_client.call(self.prepare_request()?).await?) })
     }
+
+    /// Same as [`PreAuthorizedCodeTokenRequest::request`], but sends the request again while the
+    /// authorization server returns an `authorization_pending` or `slow_down` error, sleeping the
+    /// current thread between attempts.
+    ///
+    /// Requests are sent every `interval`, usually the
+    /// [`PreAuthorizedCodeGrant::polling_interval`](crate::credential_offer::PreAuthorizedCodeGrant::polling_interval)
+    /// of the credential offer, which is increased by 5 seconds on `slow_down`. Once `timeout`
+    /// would be exceeded by waiting, the last error is returned.
+    pub fn request_polling<C>(
+        self,
+        http_client: &C,
+        mut interval: Duration,
+        timeout: Duration,
+    ) -> Result<TR, RequestTokenError<<C as SyncHttpClient>::Error, TE>>
+    where
+        C: SyncHttpClient,
+    {
+        let started = Instant::now();
+        loop {
+            let http_response = http_client.call(self.prepare_request()?)?;
+            match next_polling_interval(&http_response, interval) {
+                Some(next) if started.elapsed() + next <= timeout => {
+                    interval = next;
+                    std::thread::sleep(interval);
+                }
+                _ => return endpoint_response(http_response),
+            }
+        }
+    }
+
+    /// Same as [`PreAuthorizedCodeTokenRequest::request_polling`], awaiting `sleep` between
+    /// attempts so that the caller can use the timer of its runtime.
+    ///
+    /// As `std::time::Instant` is not available on every async target, e.g. `wasm32`, `timeout`
+    /// is checked against the sum of the intervals slept, excluding the time spent on requests.
+    pub fn request_polling_async<'c, C, S, F>(
+        self,
+        http_client: &'c C,
+        mut interval: Duration,
+        timeout: Duration,
+        sleep: S,
+    ) -> impl Future<Output = Result<TR, RequestTokenError<<C as AsyncHttpClient<'c>>::Error, TE>>> + 'c
+    where
+        Self: 'c,
+        C: AsyncHttpClient<'c>,
+        S: Fn(Duration) -> F + 'c,
+        F: Future<Output = ()> + 'c,
+    {
+        Box::pin(async move {
+            let mut slept = Duration::ZERO;
+            loop {
+                let http_response = http_client.call(self.prepare_request()?).await?;
+                match next_polling_interval(&http_response, interval) {
+                    Some(next) if slept + next <= timeout => {
+                        interval = next;
+                        sleep(interval).await;
+                        slept += interval;
+                    }
+                    _ => return endpoint_response(http_response),
+                }
+            }
+        })
+    }
+}
+
+/// Interval increase requested by a `slow_down` error, see
+/// [RFC8628](https://datatracker.ietf.org/doc/html/rfc8628#section-3.5).
+const SLOW_DOWN_INCREMENT: Duration = Duration::from_secs(5);
+
+/// Returns the interval to wait before polling again if the token response is an
+/// `authorization_pending` or `slow_down` error.
+fn next_polling_interval(http_response: &HttpResponse, interval: Duration) -> Option<Duration> {
+    #[derive(Deserialize)]
+    struct PendingError {
+        error: String,
+    }
+
+    if http_response.status() == StatusCode::OK {
+        return None;
+    }
+    match serde_json::from_slice::<PendingError>(http_response.body())
+        .ok()?
+        .error
+        .as_str()
+    {
+        "authorization_pending" => Some(interval),
+        "slow_down" => Some(interval + SLOW_DOWN_INCREMENT),
+        _ => None,
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -135,7 +255,7 @@ fn endpoint_request<'a>(
     url: &'a Url,
     params: Vec<(&'a str, &'a str)>,
 ) -> Result<HttpRequest, http::Error> {
-    let mut builder = http::Request::builder()
+    let mut builder = no_store(http::Request::builder())
         .uri(url.to_string())
         .method(http::Method::POST)
         .header(ACCEPT, HeaderValue::from_static(MIME_TYPE_JSON))
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// The response type requesting both an authorization code and a Verifiable Presentation, used
+/// when the issuer requires an existing credential to be presented before issuing a new one.
+pub const RESPONSE_TYPE_CODE_VP_TOKEN: &str = "code vp_token";
+
+/// A DIF Presentation Exchange presentation definition, describing the credentials the wallet
+/// must present in the `vp_token`.
+///
+/// See <https://identity.foundation/presentation-exchange/spec/v2.0.0/#presentation-definition>.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct PresentationDefinition {
+    id: String,
+    input_descriptors: Vec<InputDescriptor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    purpose: Option<String>,
+    #[serde(flatten)]
+    additional_fields: Map<String, Value>,
+}
+
+impl PresentationDefinition {
+    pub fn new(id: String, input_descriptors: Vec<InputDescriptor>) -> Self {
+        Self {
+            id,
+            input_descriptors,
+            name: None,
+            purpose: None,
+            additional_fields: Map::new(),
+        }
+    }
+
+    field_getters_setters![
+        pub self [self] ["presentation definition value"] {
+            set_id -> id[String],
+            set_input_descriptors -> input_descriptors[Vec<InputDescriptor>],
+            set_name -> name[Option<String>],
+            set_purpose -> purpose[Option<String>],
+            set_additional_fields -> additional_fields[Map<String, Value>],
+        }
+    ];
+}
+
+/// See <https://identity.foundation/presentation-exchange/spec/v2.0.0/#input-descriptor-object>.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct InputDescriptor {
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    purpose: Option<String>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    format: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    constraints: Map<String, Value>,
+}
+
+impl InputDescriptor {
+    pub fn new(id: String) -> Self {
+        Self {
+            id,
+            name: None,
+            purpose: None,
+            format: Map::new(),
+            constraints: Map::new(),
+        }
+    }
+
+    field_getters_setters![
+        pub self [self] ["input descriptor value"] {
+            set_id -> id[String],
+            set_name -> name[Option<String>],
+            set_purpose -> purpose[Option<String>],
+            set_format -> format[Map<String, Value>],
+            set_constraints -> constraints[Map<String, Value>],
+        }
+    ];
+}
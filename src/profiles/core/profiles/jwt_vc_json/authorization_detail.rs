@@ -3,11 +3,13 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    profiles::core::profiles::AuthorizationDetailsObjectClaim,
+    profiles::core::profiles::{
+        claims_are_subset, retain_supported_claims, AuthorizationDetailsObjectClaim,
+    },
     profiles::AuthorizationDetailsObjectProfile,
 };
 
-use super::{CredentialSubjectClaims, Format};
+use super::{credential_configuration, CredentialSubjectClaims, Format};
 
 #[derive(Clone, Debug, Deserialize, Default, PartialEq, Serialize)]
 pub struct AuthorizationDetailsObjectWithFormat {
@@ -42,11 +44,13 @@ impl AuthorizationDetailsObjectProfile for AuthorizationDetailsObject {}
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 pub struct CredentialDefinition {
+    #[serde(alias = "types")]
     r#type: Vec<String>,
     #[serde(
         default,
         skip_serializing_if = "HashMap::is_empty",
-        rename = "credentialSubject"
+        rename = "credentialSubject",
+        alias = "credential_subject"
     )]
     credential_subject: CredentialSubjectClaims<AuthorizationDetailsObjectClaim>,
 }
@@ -58,6 +62,19 @@ impl CredentialDefinition {
             set_credential_subject -> credential_subject[CredentialSubjectClaims<AuthorizationDetailsObjectClaim>],
         }
     ];
+
+    /// Whether the requested types and claims are all offered by the credential configuration
+    /// definition `other`.
+    pub fn is_subset_of(&self, other: &credential_configuration::CredentialDefinition) -> bool {
+        self.r#type.iter().all(|t| other.r#type().contains(t))
+            && claims_are_subset(&self.credential_subject, other.credential_subject())
+    }
+
+    /// Drops the requested claims that are not offered by the credential configuration
+    /// definition `other`.
+    pub fn retain_supported(&mut self, other: &credential_configuration::CredentialDefinition) {
+        retain_supported_claims(&mut self.credential_subject, other.credential_subject());
+    }
 }
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
@@ -65,7 +82,8 @@ pub struct CredentialDefinitionWithoutType {
     #[serde(
         default,
         skip_serializing_if = "HashMap::is_empty",
-        rename = "credentialSubject"
+        rename = "credentialSubject",
+        alias = "credential_subject"
     )]
     credential_subject: CredentialSubjectClaims<AuthorizationDetailsObjectClaim>,
 }
@@ -12,7 +12,11 @@ use super::{CredentialSubjectClaims, Format};
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 pub struct CredentialConfiguration {
     format: Format,
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        alias = "cryptographic_suites_supported"
+    )]
     credential_signing_alg_values_supported: Vec<ssi::jwk::Algorithm>,
     credential_definition: CredentialDefinition,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -33,11 +37,13 @@ impl CredentialConfigurationProfile for CredentialConfiguration {}
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 pub struct CredentialDefinition {
+    #[serde(alias = "types")]
     r#type: Vec<String>,
     #[serde(
         default,
         skip_serializing_if = "HashMap::is_empty",
-        rename = "credentialSubject"
+        rename = "credentialSubject",
+        alias = "credential_subject"
     )]
     credential_subject: CredentialSubjectClaims<CredentialConfigurationClaim>,
 }
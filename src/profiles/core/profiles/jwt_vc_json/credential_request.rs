@@ -1,8 +1,13 @@
 use serde::{Deserialize, Serialize};
 
-use crate::profiles::CredentialRequestProfile;
+use crate::profiles::{
+    core::profiles::AuthorizationDetailsObjectClaim, CredentialRequestProfile, KnownFields,
+};
 
-use super::{authorization_detail::CredentialDefinition, CredentialResponse, Format};
+use super::{
+    authorization_detail::CredentialDefinition, CredentialConfiguration, CredentialResponse,
+    CredentialSubjectClaims, Format,
+};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct CredentialRequestWithFormat {
@@ -17,6 +22,19 @@ impl CredentialRequestWithFormat {
             credential_definition,
         }
     }
+
+    /// Requests the types of the credential `configuration`, with the `credential_subject`
+    /// claims it supports, other claims being dropped.
+    pub fn from_configuration(
+        configuration: &CredentialConfiguration,
+        credential_subject: CredentialSubjectClaims<AuthorizationDetailsObjectClaim>,
+    ) -> Self {
+        let mut credential_definition = CredentialDefinition::default()
+            .set_type(configuration.credential_definition().r#type().clone())
+            .set_credential_subject(credential_subject);
+        credential_definition.retain_supported(configuration.credential_definition());
+        Self::new(credential_definition)
+    }
     field_getters_setters![
         pub self [self] ["JWT VC request value"] {
             set_credential_definition -> credential_definition[CredentialDefinition],
@@ -26,6 +44,21 @@ impl CredentialRequestWithFormat {
 
 impl CredentialRequestProfile for CredentialRequestWithFormat {
     type Response = CredentialResponse;
+
+    fn known_fields(&self) -> KnownFields {
+        KnownFields::Object(&[
+            ("format", KnownFields::Any),
+            (
+                "credential_definition",
+                KnownFields::Object(&[
+                    ("type", KnownFields::Any),
+                    ("types", KnownFields::Any),
+                    ("credentialSubject", KnownFields::Any),
+                    ("credential_subject", KnownFields::Any),
+                ]),
+            ),
+        ])
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -51,7 +84,12 @@ impl CredentialRequestProfile for CredentialRequest {
 mod test {
     use serde_json::json;
 
-    use crate::{credential::Request, profiles::core::profiles::CoreProfilesCredentialRequest};
+    use crate::{
+        credential::Request,
+        profiles::core::profiles::{
+            jwt_vc_json::CredentialConfiguration, CoreProfilesCredentialRequest,
+        },
+    };
 
     #[test]
     fn roundtrip_with_format() {
@@ -114,4 +152,88 @@ mod test {
         let roundtripped = serde_json::to_value(credential_request).unwrap();
         assert_json_diff::assert_json_eq!(expected_json, roundtripped);
     }
+
+    #[test]
+    fn request_subset_of_configuration() {
+        let configuration: CredentialConfiguration = serde_json::from_value(json!({
+            "format": "jwt_vc_json",
+            "credential_definition": {
+                "type": ["VerifiableCredential", "UniversityDegreeCredential"],
+                "credentialSubject": {
+                    "given_name": {},
+                    "family_name": {},
+                    "degree": {}
+                }
+            }
+        }))
+        .unwrap();
+
+        let credential_definition: super::CredentialDefinition = serde_json::from_value(json!({
+            "type": ["VerifiableCredential", "UniversityDegreeCredential"],
+            "credentialSubject": {
+                "given_name": {},
+                "degree": {}
+            }
+        }))
+        .unwrap();
+        assert!(credential_definition.is_subset_of(configuration.credential_definition()));
+
+        let credential_definition: super::CredentialDefinition = serde_json::from_value(json!({
+            "type": ["VerifiableCredential", "UniversityDegreeCredential"],
+            "credentialSubject": {
+                "given_name": {},
+                "nationality": {}
+            }
+        }))
+        .unwrap();
+        assert!(!credential_definition.is_subset_of(configuration.credential_definition()));
+
+        let request = super::CredentialRequestWithFormat::from_configuration(
+            &configuration,
+            credential_definition.credential_subject().clone(),
+        );
+        assert!(request
+            .credential_definition()
+            .is_subset_of(configuration.credential_definition()));
+        assert_json_diff::assert_json_eq!(
+            serde_json::to_value(request).unwrap(),
+            json!({
+                "format": "jwt_vc_json",
+                "credential_definition": {
+                    "type": ["VerifiableCredential", "UniversityDegreeCredential"],
+                    "credentialSubject": {
+                        "given_name": {}
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn any_claim_of_configuration_without_credential_subject() {
+        let configuration: CredentialConfiguration = serde_json::from_value(json!({
+            "format": "jwt_vc_json",
+            "credential_definition": {
+                "type": ["VerifiableCredential", "UniversityDegreeCredential"]
+            }
+        }))
+        .unwrap();
+        let credential_definition: super::CredentialDefinition = serde_json::from_value(json!({
+            "type": ["VerifiableCredential", "UniversityDegreeCredential"],
+            "credentialSubject": {
+                "given_name": {},
+                "degree": {
+                    "type": {}
+                }
+            }
+        }))
+        .unwrap();
+        assert!(credential_definition.is_subset_of(configuration.credential_definition()));
+
+        let request = super::CredentialRequestWithFormat::from_configuration(
+            &configuration,
+            credential_definition.credential_subject().clone(),
+        );
+        assert_eq!(request.credential_definition(), &credential_definition);
+    }
 }
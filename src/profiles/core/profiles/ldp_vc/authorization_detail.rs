@@ -5,11 +5,13 @@ use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::{
-    profiles::core::profiles::AuthorizationDetailsObjectClaim,
+    profiles::core::profiles::{
+        claims_are_subset, retain_supported_claims, AuthorizationDetailsObjectClaim,
+    },
     profiles::AuthorizationDetailsObjectProfile,
 };
 
-use super::CredentialSubjectClaims;
+use super::{credential_configuration, CredentialSubjectClaims};
 
 #[derive(Clone, Debug, Deserialize, Default, PartialEq, Serialize)]
 pub struct AuthorizationDetailsObjectWithFormat<F> {
@@ -49,11 +51,13 @@ impl AuthorizationDetailsObjectProfile for AuthorizationDetailsObject {}
 pub struct CredentialDefinition {
     #[serde(rename = "@context")]
     context: Vec<Value>,
+    #[serde(alias = "types")]
     r#type: Vec<String>,
     #[serde(
         default,
         skip_serializing_if = "HashMap::is_empty",
-        rename = "credentialSubject"
+        rename = "credentialSubject",
+        alias = "credential_subject"
     )]
     credential_subject: CredentialSubjectClaims<AuthorizationDetailsObjectClaim>,
 }
@@ -66,6 +70,20 @@ impl CredentialDefinition {
             set_credential_subject -> credential_subject[CredentialSubjectClaims<AuthorizationDetailsObjectClaim>],
         }
     ];
+
+    /// Whether the requested contexts, types and claims are all offered by the credential
+    /// configuration definition `other`.
+    pub fn is_subset_of(&self, other: &credential_configuration::CredentialDefinition) -> bool {
+        self.context.iter().all(|c| other.context().contains(c))
+            && self.r#type.iter().all(|t| other.r#type().contains(t))
+            && claims_are_subset(&self.credential_subject, other.credential_subject())
+    }
+
+    /// Drops the requested claims that are not offered by the credential configuration
+    /// definition `other`.
+    pub fn retain_supported(&mut self, other: &credential_configuration::CredentialDefinition) {
+        retain_supported_claims(&mut self.credential_subject, other.credential_subject());
+    }
 }
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
@@ -73,7 +91,8 @@ pub struct CredentialDefinitionWithoutContext {
     #[serde(
         default,
         skip_serializing_if = "HashMap::is_empty",
-        rename = "credentialSubject"
+        rename = "credentialSubject",
+        alias = "credential_subject"
     )]
     credential_subject: CredentialSubjectClaims<AuthorizationDetailsObjectClaim>,
 }
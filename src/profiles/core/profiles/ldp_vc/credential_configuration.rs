@@ -17,6 +17,7 @@ pub struct CredentialConfiguration<F> {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     // TODO: Enumerate types from LD Suite Registry:
     // https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0-ID1.html#appendix-A.1.2.2-1
+    #[serde(alias = "cryptographic_suites_supported")]
     credential_signing_alg_values_supported: Vec<String>,
     credential_definition: CredentialDefinition,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -42,11 +43,13 @@ impl<F> CredentialConfigurationProfile for CredentialConfiguration<F> where
 pub struct CredentialDefinition {
     #[serde(rename = "@context")]
     context: Vec<Value>,
+    #[serde(alias = "types")]
     r#type: Vec<String>,
     #[serde(
         default,
         skip_serializing_if = "HashMap::is_empty",
-        rename = "credentialSubject"
+        rename = "credentialSubject",
+        alias = "credential_subject"
     )]
     credential_subject: CredentialSubjectClaims<CredentialConfigurationClaim>,
 }
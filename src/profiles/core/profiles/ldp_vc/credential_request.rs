@@ -2,9 +2,14 @@ use std::fmt::Debug;
 
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use crate::profiles::CredentialRequestProfile;
+use crate::profiles::{
+    core::profiles::AuthorizationDetailsObjectClaim, CredentialRequestProfile, KnownFields,
+};
 
-use super::{authorization_detail::CredentialDefinition, CredentialResponse};
+use super::{
+    authorization_detail::CredentialDefinition, credential_configuration::CredentialConfiguration,
+    CredentialResponse, CredentialSubjectClaims,
+};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct CredentialRequestWithFormat<F> {
@@ -22,6 +27,24 @@ impl<F> CredentialRequestWithFormat<F> {
             credential_definition,
         }
     }
+
+    /// Requests the contexts and types of the credential `configuration`, with the
+    /// `credential_subject` claims it supports, other claims being dropped.
+    pub fn from_configuration(
+        configuration: &CredentialConfiguration<F>,
+        credential_subject: CredentialSubjectClaims<AuthorizationDetailsObjectClaim>,
+    ) -> Self
+    where
+        F: Default,
+    {
+        let configuration_definition = configuration.credential_definition();
+        let mut credential_definition = CredentialDefinition::default()
+            .set_context(configuration_definition.context().clone())
+            .set_type(configuration_definition.r#type().clone())
+            .set_credential_subject(credential_subject);
+        credential_definition.retain_supported(configuration_definition);
+        Self::new(credential_definition)
+    }
     field_getters_setters![
         pub self [self] ["request value"] {
             set_credential_definition -> credential_definition[CredentialDefinition],
@@ -34,6 +57,22 @@ where
     F: DeserializeOwned + Serialize + Debug + Clone,
 {
     type Response = CredentialResponse;
+
+    fn known_fields(&self) -> KnownFields {
+        KnownFields::Object(&[
+            ("format", KnownFields::Any),
+            (
+                "credential_definition",
+                KnownFields::Object(&[
+                    ("@context", KnownFields::Any),
+                    ("type", KnownFields::Any),
+                    ("types", KnownFields::Any),
+                    ("credentialSubject", KnownFields::Any),
+                    ("credential_subject", KnownFields::Any),
+                ]),
+            ),
+        ])
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -1,18 +1,29 @@
-use serde::{Deserialize, Serialize};
+use std::{fmt::Debug, marker::PhantomData};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use ssi::prelude::{AnySuite, DataIntegrity, DataIntegrityDocument};
 
 use crate::profiles::CredentialResponseProfile;
 
+/// Credential response of the `ldp_vc` profile, generic over the type of the issued credential.
+///
+/// Defaults to a Data Integrity secured document using any supported cryptographic suite.
+/// Consumers that know which credential and suite to expect can use a more specific type, and
+/// avoid the [`AnySuite`] machinery.
 #[derive(Clone, Debug, Deserialize, Serialize)]
-pub struct CredentialResponse;
+pub struct CredentialResponse<V = DataIntegrity<DataIntegrityDocument, AnySuite>>(PhantomData<V>);
 
-impl CredentialResponseProfile for CredentialResponse {
-    type Type = DataIntegrity<DataIntegrityDocument, AnySuite>;
+impl<V> CredentialResponseProfile for CredentialResponse<V>
+where
+    V: Clone + Debug + DeserializeOwned + Serialize,
+{
+    type Type = V;
 }
 
 #[cfg(test)]
 mod test {
-    use serde_json::json;
+    use serde::{Deserialize, Serialize};
+    use serde_json::{json, Value};
 
     use crate::credential::Response;
 
@@ -62,4 +73,68 @@ mod test {
         let roundtripped = serde_json::to_value(credential_response).unwrap();
         assert_json_diff::assert_json_eq!(expected_json, roundtripped);
     }
+
+    #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct UniversityDegreeCredential {
+        #[serde(rename = "@context")]
+        context: Vec<String>,
+        id: String,
+        r#type: Vec<String>,
+        issuer: String,
+        issuance_date: String,
+        credential_subject: Value,
+        proof: Value,
+    }
+
+    #[test]
+    fn concrete_credential_type() {
+        let expected_json = json!(
+            {
+                "credential": {
+                    "@context": [
+                        "https://www.w3.org/2018/credentials/v1",
+                        "https://www.w3.org/2018/credentials/examples/v1"
+                    ],
+                    "id": "http://example.edu/credentials/3732",
+                    "type": [
+                        "VerifiableCredential",
+                        "UniversityDegreeCredential"
+                    ],
+                    "issuer": "https://example.edu/issuers/565049",
+                    "issuanceDate": "2010-01-01T00:00:00Z",
+                    "credentialSubject": {
+                        "id": "did:example:ebfeb1f712ebc6f1c276e12ec21",
+                        "degree": {
+                            "type": "BachelorDegree",
+                            "name": "Bachelor of Science and Arts"
+                        }
+                    },
+                    "proof": {
+                        "type": "Ed25519Signature2020",
+                        "created": "2022-02-25T14:58:43Z",
+                        "verificationMethod": "https://example.edu/issuers/565049#key-1",
+                        "proofPurpose": "assertionMethod",
+                        "proofValue": "zeEdUoM7m9cY8ZyTpey83yBKeBcmcvbyrEQzJ19rD2UXArU2U1jPGoEtrRvGYppdiK37GU4NBeoPakxpWhAvsVSt"
+                    }
+                },
+                "c_nonce": "fGFF7UkhLa",
+                "c_nonce_expires_in": 86400
+            }
+        );
+
+        let default: Response<super::CredentialResponse> =
+            serde_json::from_value(expected_json.clone()).unwrap();
+        let concrete: Response<super::CredentialResponse<UniversityDegreeCredential>> =
+            serde_json::from_value(expected_json.clone()).unwrap();
+
+        assert_json_diff::assert_json_eq!(
+            serde_json::to_value(default).unwrap(),
+            serde_json::to_value(&concrete).unwrap()
+        );
+
+        let roundtripped: Response<super::CredentialResponse<UniversityDegreeCredential>> =
+            serde_json::from_value(serde_json::to_value(&concrete).unwrap()).unwrap();
+        assert_eq!(concrete, roundtripped);
+    }
 }
@@ -4,9 +4,12 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::{
+    authorization::AuthorizationDetailsObject,
+    credential_offer::{CredentialOfferParameters, UnknownCredentialConfigurations},
+    metadata::CredentialIssuerMetadata,
     profiles::{
         AuthorizationDetailsObjectProfile, CredentialConfigurationProfile,
-        CredentialRequestProfile, CredentialResponseProfile, Profile,
+        CredentialRequestProfile, CredentialResponseProfile, KnownFields, Profile,
     },
     types::{ClaimValueType, CredentialConfigurationId, LanguageTag},
 };
@@ -33,7 +36,53 @@ pub enum CoreProfilesCredentialConfiguration {
     MsoMdoc(mso_mdoc::CredentialConfiguration),
 }
 
-impl CredentialConfigurationProfile for CoreProfilesCredentialConfiguration {}
+impl CoreProfilesCredentialConfiguration {
+    /// Builds a credential request for this configuration, with the format and the
+    /// format-specific parameters (`credential_definition` or `doctype`) copied from it.
+    pub fn build_request(&self) -> CoreProfilesCredentialRequest {
+        let inner = match self {
+            Self::JwtVcJson(config) => CredentialRequestWithFormat::JwtVcJson(
+                jwt_vc_json::CredentialRequestWithFormat::new(
+                    jwt_vc_json::authorization_detail::CredentialDefinition::default()
+                        .set_type(config.credential_definition().r#type().clone()),
+                ),
+            ),
+            Self::JwtVcJsonLd(config) => CredentialRequestWithFormat::JwtVcJsonLd(
+                jwt_vc_json_ld::CredentialRequestWithFormat::new(
+                    ldp_vc::authorization_detail::CredentialDefinition::default()
+                        .set_context(config.credential_definition().context().clone())
+                        .set_type(config.credential_definition().r#type().clone()),
+                ),
+            ),
+            Self::LdpVc(config) => {
+                CredentialRequestWithFormat::LdpVc(ldp_vc::CredentialRequestWithFormat::new(
+                    ldp_vc::authorization_detail::CredentialDefinition::default()
+                        .set_context(config.credential_definition().context().clone())
+                        .set_type(config.credential_definition().r#type().clone()),
+                ))
+            }
+            Self::MsoMdoc(config) => CredentialRequestWithFormat::MsoMdoc(
+                mso_mdoc::CredentialRequestWithFormat::new(config.doctype().clone()),
+            ),
+        };
+        CoreProfilesCredentialRequest::WithFormat {
+            inner,
+            _credential_identifier: (),
+            _credential_configuration_id: (),
+        }
+    }
+}
+
+impl CredentialConfigurationProfile for CoreProfilesCredentialConfiguration {
+    fn format_identifier(&self) -> Option<&str> {
+        Some(match self {
+            Self::JwtVcJson(_) => jwt_vc_json::FORMAT_IDENTIFIER,
+            Self::JwtVcJsonLd(_) => jwt_vc_json_ld::FORMAT_IDENTIFIER,
+            Self::LdpVc(_) => ldp_vc::FORMAT_IDENTIFIER,
+            Self::MsoMdoc(_) => mso_mdoc::FORMAT_IDENTIFIER,
+        })
+    }
+}
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(untagged)]
@@ -48,6 +97,13 @@ pub enum CoreProfilesAuthorizationDetailsObject {
             rename = "credential_identifier"
         )]
         _credential_identifier: (),
+        #[serde(
+            default,
+            skip_serializing,
+            deserialize_with = "crate::deny_field::deny_field",
+            rename = "credential_configuration_id"
+        )]
+        _credential_configuration_id: (),
     },
     WithIdAndUnresolvedProfile {
         credential_configuration_id: CredentialConfigurationId,
@@ -94,7 +150,91 @@ pub enum AuthorizationDetailsObjectWithCredentialConfigurationId {
     MsoMdoc(mso_mdoc::AuthorizationDetailsObject),
 }
 
-impl AuthorizationDetailsObjectProfile for CoreProfilesAuthorizationDetailsObject {}
+impl CoreProfilesAuthorizationDetailsObject {
+    /// Requests the credential configuration `credential_configuration_id`, without claims.
+    pub fn for_configuration(
+        credential_configuration_id: CredentialConfigurationId,
+        configuration: &CoreProfilesCredentialConfiguration,
+    ) -> Self {
+        let inner = match configuration {
+            CoreProfilesCredentialConfiguration::JwtVcJson(_) => {
+                AuthorizationDetailsObjectWithCredentialConfigurationId::JwtVcJson(
+                    Default::default(),
+                )
+            }
+            CoreProfilesCredentialConfiguration::JwtVcJsonLd(_) => {
+                AuthorizationDetailsObjectWithCredentialConfigurationId::JwtVcJsonLd(
+                    Default::default(),
+                )
+            }
+            CoreProfilesCredentialConfiguration::LdpVc(_) => {
+                AuthorizationDetailsObjectWithCredentialConfigurationId::LdpVc(Default::default())
+            }
+            CoreProfilesCredentialConfiguration::MsoMdoc(_) => {
+                AuthorizationDetailsObjectWithCredentialConfigurationId::MsoMdoc(
+                    mso_mdoc::AuthorizationDetailsObject::new(Default::default()),
+                )
+            }
+        };
+        Self::WithId {
+            credential_configuration_id,
+            inner,
+            _format: (),
+        }
+    }
+}
+
+impl AuthorizationDetailsObject<CoreProfilesAuthorizationDetailsObject> {
+    /// Returns one authorization detail per credential configuration of the offer, in the
+    /// `credential_configuration_id` form, typed after the configuration of the issuer metadata
+    /// or, failing that, inlined in the offer.
+    pub fn from_offer(
+        credential_offer: &CredentialOfferParameters,
+        metadata: &CredentialIssuerMetadata<CoreProfilesCredentialConfiguration>,
+    ) -> Result<Vec<Self>, UnknownCredentialConfigurations> {
+        let supported = metadata.configurations_by_id();
+        let mut unknown = Vec::new();
+        let authorization_details = credential_offer
+            .credential_configuration_ids()
+            .iter()
+            .filter_map(|id| {
+                let configuration = supported
+                    .get(id)
+                    .copied()
+                    .or_else(|| credential_offer.inline_configuration(id));
+                match configuration {
+                    Some(configuration) => Some(Self::new(
+                        CoreProfilesAuthorizationDetailsObject::for_configuration(
+                            id.clone(),
+                            configuration.profile_specific_fields(),
+                        ),
+                    )),
+                    None => {
+                        unknown.push(id.clone());
+                        None
+                    }
+                }
+            })
+            .collect();
+        if unknown.is_empty() {
+            Ok(authorization_details)
+        } else {
+            Err(UnknownCredentialConfigurations(unknown))
+        }
+    }
+}
+
+impl AuthorizationDetailsObjectProfile for CoreProfilesAuthorizationDetailsObject {
+    fn from_credential_configuration_id(
+        credential_configuration_id: CredentialConfigurationId,
+    ) -> Option<Self> {
+        Some(Self::WithIdAndUnresolvedProfile {
+            credential_configuration_id,
+            inner: HashMap::new(),
+            _format: (),
+        })
+    }
+}
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(untagged)]
@@ -109,6 +249,13 @@ pub enum CoreProfilesCredentialRequest {
             rename = "credential_identifier"
         )]
         _credential_identifier: (),
+        #[serde(
+            default,
+            skip_serializing,
+            deserialize_with = "crate::deny_field::deny_field",
+            rename = "credential_configuration_id"
+        )]
+        _credential_configuration_id: (),
     },
     WithIdAndUnresolvedProfile {
         credential_identifier: CredentialConfigurationId,
@@ -121,6 +268,31 @@ pub enum CoreProfilesCredentialRequest {
             rename = "format"
         )]
         _format: (),
+        #[serde(
+            default,
+            skip_serializing,
+            deserialize_with = "crate::deny_field::deny_field",
+            rename = "credential_configuration_id"
+        )]
+        _credential_configuration_id: (),
+    },
+    /// Request by credential configuration identifier, as allowed by later drafts.
+    WithConfigurationId {
+        credential_configuration_id: CredentialConfigurationId,
+        #[serde(
+            default,
+            skip_serializing,
+            deserialize_with = "crate::deny_field::deny_field",
+            rename = "format"
+        )]
+        _format: (),
+        #[serde(
+            default,
+            skip_serializing,
+            deserialize_with = "crate::deny_field::deny_field",
+            rename = "credential_identifier"
+        )]
+        _credential_identifier: (),
     },
     #[serde(skip_deserializing)]
     WithId {
@@ -137,8 +309,43 @@ pub enum CoreProfilesCredentialRequest {
     },
 }
 
+impl CoreProfilesCredentialRequest {
+    /// Build a request referencing a credential configuration of the issuer metadata.
+    pub fn from_credential_configuration_id(
+        credential_configuration_id: CredentialConfigurationId,
+    ) -> Self {
+        Self::WithConfigurationId {
+            credential_configuration_id,
+            _format: (),
+            _credential_identifier: (),
+        }
+    }
+}
+
 impl CredentialRequestProfile for CoreProfilesCredentialRequest {
     type Response = CoreProfilesCredentialResponse;
+
+    fn known_fields(&self) -> KnownFields {
+        match self {
+            Self::WithFormat { inner, .. } => inner.known_fields(),
+            Self::WithConfigurationId { .. } => {
+                KnownFields::Object(&[("credential_configuration_id", KnownFields::Any)])
+            }
+            // Members of unresolved profiles are all kept.
+            Self::WithIdAndUnresolvedProfile { .. } | Self::WithId { .. } => KnownFields::Any,
+        }
+    }
+
+    fn from_credential_identifier(
+        credential_identifier: CredentialConfigurationId,
+    ) -> Option<Self> {
+        Some(Self::WithIdAndUnresolvedProfile {
+            credential_identifier,
+            inner: HashMap::new(),
+            _format: (),
+            _credential_configuration_id: (),
+        })
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -150,6 +357,17 @@ pub enum CredentialRequestWithFormat {
     MsoMdoc(mso_mdoc::CredentialRequestWithFormat),
 }
 
+impl CredentialRequestWithFormat {
+    fn known_fields(&self) -> KnownFields {
+        match self {
+            Self::JwtVcJson(request) => request.known_fields(),
+            Self::JwtVcJsonLd(request) => request.known_fields(),
+            Self::LdpVc(request) => request.known_fields(),
+            Self::MsoMdoc(request) => request.known_fields(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(untagged)]
 pub enum CredentialRequestWithCredentialIdentifier {
@@ -204,3 +422,283 @@ pub struct ClaimDisplay {
     #[serde(flatten)]
     additional_fields: HashMap<String, Value>,
 }
+
+pub type CredentialSubjectClaims<T> = HashMap<String, Box<MaybeNestedClaims<T>>>;
+
+// Object containing a list of name/value pairs, where each name identifies a claim offered in the Credential.
+// The value can be another such object (nested data structures), or an array of such objects.
+// https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0-ID1.html#appendix-A.1.1.2-3.1.2.2.1
+// https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0-ID1.html#appendix-A.1.2.2-3.1.2.3.1
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum MaybeNestedClaims<T> {
+    Object(CredentialSubjectClaims<T>),
+    Array(Vec<CredentialSubjectClaims<T>>),
+    Leaf(T),
+}
+
+impl<T> MaybeNestedClaims<T> {
+    /// Whether the nested claims of `self` are all described by `supported`.
+    ///
+    /// Arrays, and claims whose shape differ from `supported`, are not inspected further.
+    pub fn is_subset_of<U>(&self, supported: &MaybeNestedClaims<U>) -> bool {
+        match (self, supported) {
+            (Self::Object(claims), MaybeNestedClaims::Object(supported)) => {
+                claims_are_subset(claims, supported)
+            }
+            _ => true,
+        }
+    }
+
+    /// Drops the nested claims of `self` that are not described by `supported`.
+    pub fn retain_supported<U>(&mut self, supported: &MaybeNestedClaims<U>) {
+        if let (Self::Object(claims), MaybeNestedClaims::Object(supported)) = (self, supported) {
+            retain_supported_claims(claims, supported);
+        }
+    }
+}
+
+/// Whether the `claims` are all described by `supported`, which allows any claim if empty, as
+/// when a credential configuration does not list the claims of its credential subject.
+fn claims_are_subset<T, U>(
+    claims: &CredentialSubjectClaims<T>,
+    supported: &CredentialSubjectClaims<U>,
+) -> bool {
+    supported.is_empty()
+        || claims.iter().all(|(name, claim)| {
+            supported
+                .get(name)
+                .is_some_and(|supported| claim.is_subset_of(supported))
+        })
+}
+
+/// Drops the `claims` that are not described by `supported`, keeping them all if it is empty.
+fn retain_supported_claims<T, U>(
+    claims: &mut CredentialSubjectClaims<T>,
+    supported: &CredentialSubjectClaims<U>,
+) {
+    if supported.is_empty() {
+        return;
+    }
+    claims.retain(|name, claim| match supported.get(name) {
+        Some(supported) => {
+            claim.retain_supported(supported);
+            true
+        }
+        None => false,
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn build_request_jwt_vc_json() {
+        let configuration: CoreProfilesCredentialConfiguration = serde_json::from_value(json!({
+            "format": "jwt_vc_json",
+            "credential_signing_alg_values_supported": ["ES256"],
+            "credential_definition": {
+                "type": ["VerifiableCredential", "UniversityDegreeCredential"],
+                "credentialSubject": {
+                    "given_name": {
+                        "display": [{ "name": "Given Name", "locale": "en-US" }]
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let request = configuration.build_request();
+
+        assert_json_diff::assert_json_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({
+                "format": "jwt_vc_json",
+                "credential_definition": {
+                    "type": ["VerifiableCredential", "UniversityDegreeCredential"]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn build_request_mso_mdoc() {
+        let configuration: CoreProfilesCredentialConfiguration = serde_json::from_value(json!({
+            "format": "mso_mdoc",
+            "doctype": "org.iso.18013.5.1.mDL",
+            "credential_signing_alg_values_supported": ["ES256"]
+        }))
+        .unwrap();
+
+        let request = configuration.build_request();
+
+        assert_json_diff::assert_json_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({
+                "format": "mso_mdoc",
+                "doctype": "org.iso.18013.5.1.mDL"
+            })
+        );
+    }
+
+    #[test]
+    fn credential_request_forms_mutually_exclusive() {
+        let request: CoreProfilesCredentialRequest = serde_json::from_value(json!({
+            "credential_configuration_id": "UniversityDegreeCredential"
+        }))
+        .unwrap();
+        assert_eq!(
+            request,
+            CoreProfilesCredentialRequest::from_credential_configuration_id(
+                CredentialConfigurationId::new("UniversityDegreeCredential".into())
+            )
+        );
+        assert_json_diff::assert_json_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({ "credential_configuration_id": "UniversityDegreeCredential" })
+        );
+
+        let request: CoreProfilesCredentialRequest = serde_json::from_value(json!({
+            "credential_identifier": "CivilEngineeringDegree-2023"
+        }))
+        .unwrap();
+        assert!(matches!(
+            request,
+            CoreProfilesCredentialRequest::WithIdAndUnresolvedProfile { .. }
+        ));
+
+        let request: CoreProfilesCredentialRequest = serde_json::from_value(json!({
+            "format": "mso_mdoc",
+            "doctype": "org.iso.18013.5.1.mDL"
+        }))
+        .unwrap();
+        assert!(matches!(
+            request,
+            CoreProfilesCredentialRequest::WithFormat { .. }
+        ));
+
+        for value in [
+            json!({
+                "credential_configuration_id": "UniversityDegreeCredential",
+                "credential_identifier": "CivilEngineeringDegree-2023"
+            }),
+            json!({
+                "credential_configuration_id": "org.iso.18013.5.1.mDL",
+                "format": "mso_mdoc",
+                "doctype": "org.iso.18013.5.1.mDL"
+            }),
+            json!({
+                "credential_identifier": "CivilEngineeringDegree-2023",
+                "format": "mso_mdoc",
+                "doctype": "org.iso.18013.5.1.mDL"
+            }),
+        ] {
+            assert!(serde_json::from_value::<CoreProfilesCredentialRequest>(value).is_err());
+        }
+    }
+
+    #[test]
+    fn authorization_details_forms_mutually_exclusive() {
+        let with_format = [
+            json!({
+                "format": "jwt_vc_json",
+                "credential_definition": {
+                    "type": ["VerifiableCredential", "UniversityDegreeCredential"]
+                }
+            }),
+            json!({
+                "format": "jwt_vc_json-ld",
+                "credential_definition": {
+                    "@context": ["https://www.w3.org/2018/credentials/v1"],
+                    "type": ["VerifiableCredential", "UniversityDegreeCredential"]
+                }
+            }),
+            json!({
+                "format": "ldp_vc",
+                "credential_definition": {
+                    "@context": ["https://www.w3.org/2018/credentials/v1"],
+                    "type": ["VerifiableCredential", "UniversityDegreeCredential"]
+                }
+            }),
+            json!({
+                "format": "mso_mdoc",
+                "doctype": "org.iso.18013.5.1.mDL"
+            }),
+        ];
+
+        for mut value in with_format {
+            value["type"] = json!("openid_credential");
+            let authorization_detail: AuthorizationDetailsObject<
+                CoreProfilesAuthorizationDetailsObject,
+            > = serde_json::from_value(value.clone()).unwrap();
+            assert!(matches!(
+                authorization_detail.additional_profile_fields(),
+                CoreProfilesAuthorizationDetailsObject::WithFormat { .. }
+            ));
+
+            value["credential_configuration_id"] = json!("UniversityDegreeCredential");
+            assert!(serde_json::from_value::<
+                AuthorizationDetailsObject<CoreProfilesAuthorizationDetailsObject>,
+            >(value)
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn authorization_details_from_offer() {
+        let metadata: CredentialIssuerMetadata<CoreProfilesCredentialConfiguration> =
+            serde_json::from_value(json!({
+                "credential_issuer": "https://credential-issuer.example.com",
+                "credential_endpoint": "https://credential-issuer.example.com/credential",
+                "credential_configurations_supported": {
+                    "UniversityDegreeCredential": {
+                        "format": "jwt_vc_json",
+                        "credential_definition": {
+                            "type": ["VerifiableCredential", "UniversityDegreeCredential"]
+                        }
+                    },
+                    "org.iso.18013.5.1.mDL": {
+                        "format": "mso_mdoc",
+                        "doctype": "org.iso.18013.5.1.mDL"
+                    }
+                }
+            }))
+            .unwrap();
+        let credential_offer: CredentialOfferParameters = serde_json::from_value(json!({
+            "credential_issuer": "https://credential-issuer.example.com",
+            "credential_configuration_ids": [
+                "UniversityDegreeCredential",
+                "org.iso.18013.5.1.mDL"
+            ]
+        }))
+        .unwrap();
+
+        let authorization_details =
+            AuthorizationDetailsObject::from_offer(&credential_offer, &metadata).unwrap();
+        assert_json_diff::assert_json_eq!(
+            serde_json::to_value(&authorization_details).unwrap(),
+            json!([
+                {
+                    "type": "openid_credential",
+                    "credential_configuration_id": "UniversityDegreeCredential",
+                    "credential_definition": {}
+                },
+                {
+                    "type": "openid_credential",
+                    "credential_configuration_id": "org.iso.18013.5.1.mDL"
+                }
+            ])
+        );
+
+        let credential_offer: CredentialOfferParameters = serde_json::from_value(json!({
+            "credential_issuer": "https://credential-issuer.example.com",
+            "credential_configuration_ids": ["UniversityDegreeCredential", "Unknown"]
+        }))
+        .unwrap();
+        let err = AuthorizationDetailsObject::from_offer(&credential_offer, &metadata).unwrap_err();
+        assert_eq!(err.0[0].as_str(), "Unknown");
+    }
+}
@@ -8,7 +8,7 @@ use crate::{
     profiles::AuthorizationDetailsObjectProfile,
 };
 
-use super::{Claims, Format};
+use super::{check_doctype, Claims, CredentialConfiguration, DoctypeError, Format};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct AuthorizationDetailsObjectWithFormat {
@@ -26,6 +26,18 @@ impl AuthorizationDetailsObjectWithFormat {
             claims,
         }
     }
+
+    /// Same as [`AuthorizationDetailsObjectWithFormat::new`], but checks that `doctype` is the
+    /// one of the credential `configuration`.
+    pub fn for_configuration(
+        configuration: &CredentialConfiguration,
+        doctype: DocType,
+        claims: Claims<AuthorizationDetailsObjectClaim>,
+    ) -> Result<Self, DoctypeError> {
+        check_doctype(configuration, &doctype)?;
+        Ok(Self::new(doctype, claims))
+    }
+
     field_getters_setters![
         pub self [self] ["ISO mDL authorization detail value"] {
             set_doctype -> doctype[DocType],
@@ -64,6 +76,38 @@ mod test {
         profiles::core::profiles::CoreProfilesAuthorizationDetailsObject,
     };
 
+    use super::*;
+
+    #[test]
+    fn for_configuration() {
+        let configuration = CredentialConfiguration::new("org.iso.18013.5.1.mDL".into());
+
+        let authorization_detail = super::AuthorizationDetailsObjectWithFormat::for_configuration(
+            &configuration,
+            "org.iso.18013.5.1.mDL".into(),
+            Claims::new(),
+        )
+        .unwrap();
+        assert_eq!(authorization_detail.doctype(), "org.iso.18013.5.1.mDL");
+
+        assert!(matches!(
+            super::AuthorizationDetailsObjectWithFormat::for_configuration(
+                &configuration,
+                "eu.europa.ec.eudi.pid.1".into(),
+                Claims::new(),
+            ),
+            Err(DoctypeError::Mismatch { .. })
+        ));
+        assert!(matches!(
+            super::AuthorizationDetailsObjectWithFormat::for_configuration(
+                &configuration,
+                String::new(),
+                Claims::new(),
+            ),
+            Err(DoctypeError::Empty)
+        ));
+    }
+
     #[test]
     fn roundtrip_with_format() {
         let expected_json = json!(
@@ -13,7 +13,11 @@ pub struct CredentialConfiguration {
     format: Format,
     // TODO: Enumerate possible COSE algs
     doctype: DocType,
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        alias = "cryptographic_suites_supported"
+    )]
     credential_signing_alg_values_supported: Vec<String>,
     #[serde(default, skip_serializing_if = "Claims::is_empty")]
     claims: Claims<CredentialConfigurationClaim>,
@@ -3,9 +3,10 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     profiles::core::profiles::CredentialConfigurationClaim, profiles::CredentialRequestProfile,
+    profiles::KnownFields,
 };
 
-use super::{Claims, Format};
+use super::{check_doctype, Claims, CredentialConfiguration, DoctypeError, Format};
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct CredentialRequestWithFormat {
@@ -25,6 +26,17 @@ impl CredentialRequestWithFormat {
             claims: Claims::new(),
         }
     }
+
+    /// Same as [`CredentialRequestWithFormat::new`], but checks that `doctype` is the one of the
+    /// credential `configuration`.
+    pub fn for_configuration(
+        configuration: &CredentialConfiguration,
+        doctype: DocType,
+    ) -> Result<Self, DoctypeError> {
+        check_doctype(configuration, &doctype)?;
+        Ok(Self::new(doctype))
+    }
+
     field_getters_setters![
         pub self [self] ["ISO mDL request value"] {
             set_doctype -> doctype[DocType],
@@ -35,6 +47,14 @@ impl CredentialRequestWithFormat {
 
 impl CredentialRequestProfile for CredentialRequestWithFormat {
     type Response = super::CredentialResponse;
+
+    fn known_fields(&self) -> KnownFields {
+        KnownFields::Object(&[
+            ("format", KnownFields::Any),
+            ("doctype", KnownFields::Any),
+            ("claims", KnownFields::Any),
+        ])
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -74,6 +94,28 @@ mod test {
 
     use crate::{credential::Request, profiles::core::profiles::CoreProfilesCredentialRequest};
 
+    use super::*;
+
+    #[test]
+    fn for_configuration() {
+        let configuration = CredentialConfiguration::new("org.iso.18013.5.1.mDL".into());
+
+        let request = super::CredentialRequestWithFormat::for_configuration(
+            &configuration,
+            "org.iso.18013.5.1.mDL".into(),
+        )
+        .unwrap();
+        assert_eq!(request.doctype(), "org.iso.18013.5.1.mDL");
+
+        assert!(matches!(
+            super::CredentialRequestWithFormat::for_configuration(
+                &configuration,
+                "eu.europa.ec.eudi.pid.1".into(),
+            ),
+            Err(DoctypeError::Mismatch { .. })
+        ));
+    }
+
     #[test]
     fn roundtrip_with_format() {
         let expected_json = json!(
@@ -6,23 +6,72 @@ use crate::profiles::CredentialResponseProfile;
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct CredentialResponse;
 
+impl CredentialResponse {
+    /// Returns the credential of an `mso_mdoc` credential response for `issuer_signed`, which is
+    /// sent as the base64url-encoded CBOR `IssuerSigned` structure, see
+    /// [`IsoIssuerSigned::encode`].
+    pub fn from_issuer_signed(issuer_signed: IssuerSigned) -> IsoIssuerSigned {
+        IsoIssuerSigned::new(issuer_signed)
+    }
+}
+
 impl CredentialResponseProfile for CredentialResponse {
     type Type = IsoIssuerSigned;
 }
 
+/// The credential of an `mso_mdoc` credential response, sent as the base64url-encoded CBOR
+/// `IssuerSigned` structure.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct IsoIssuerSigned(#[serde(with = "base64_cbor")] IssuerSigned);
 
+impl IsoIssuerSigned {
+    pub fn new(issuer_signed: IssuerSigned) -> Self {
+        Self(issuer_signed)
+    }
+
+    pub fn issuer_signed(&self) -> &IssuerSigned {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> IssuerSigned {
+        self.0
+    }
+
+    /// Encodes the `IssuerSigned` structure in CBOR, then in base64url.
+    pub fn encode(&self) -> Result<String, EncodingError> {
+        base64_cbor::encode(&self.0)
+    }
+
+    /// Decodes a base64url-encoded CBOR `IssuerSigned` structure.
+    pub fn decode(encoded: &str) -> Result<Self, EncodingError> {
+        base64_cbor::decode(encoded).map(Self)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum EncodingError {
+    #[error("invalid CBOR: {0}")]
+    Cbor(#[from] serde_cbor::Error),
+    #[error("invalid base64url: {0}")]
+    Base64(#[from] base64::DecodeError),
+}
+
 mod base64_cbor {
     use base64::{engine::general_purpose::URL_SAFE, Engine};
     use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
 
+    use super::EncodingError;
+
+    pub fn encode<T: Serialize>(v: &T) -> Result<String, EncodingError> {
+        Ok(URL_SAFE.encode(serde_cbor::to_vec(v)?))
+    }
+
+    pub fn decode<T: DeserializeOwned>(b64: &str) -> Result<T, EncodingError> {
+        Ok(serde_cbor::from_slice(&URL_SAFE.decode(b64)?)?)
+    }
+
     pub fn serialize<T: Sized + Serialize, S: Serializer>(v: &T, s: S) -> Result<S::Ok, S::Error> {
-        let v = match serde_cbor::to_vec(v) {
-            Ok(v) => v,
-            Err(e) => return Err(serde::ser::Error::custom(e)),
-        };
-        let b64 = URL_SAFE.encode(v);
+        let b64 = encode(v).map_err(serde::ser::Error::custom)?;
         String::serialize(&b64, s)
     }
 
@@ -30,12 +79,53 @@ mod base64_cbor {
         d: D,
     ) -> Result<T, D::Error> {
         let b64 = String::deserialize(d)?;
-        match URL_SAFE.decode(b64) {
-            Ok(v) => match serde_cbor::from_slice(&v) {
-                Ok(v) => Ok(v),
-                Err(e) => Err(serde::de::Error::custom(e)),
-            },
-            Err(e) => Err(serde::de::Error::custom(e)),
-        }
+        decode(&b64).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_cbor::Value;
+
+    use super::*;
+
+    fn issuer_signed() -> IssuerSigned {
+        // COSE_Sign1 with an ES256 protected header, a dummy payload and signature.
+        let issuer_auth = Value::Array(vec![
+            Value::Bytes(vec![0xa1, 0x01, 0x26]),
+            Value::Map(Default::default()),
+            Value::Bytes(b"payload".to_vec()),
+            Value::Bytes(vec![0; 64]),
+        ]);
+        let value = Value::Map(
+            [(Value::Text("issuerAuth".into()), issuer_auth)]
+                .into_iter()
+                .collect(),
+        );
+        serde_cbor::value::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn from_issuer_signed_roundtrip() {
+        let issuer_signed = issuer_signed();
+        let credential = CredentialResponse::from_issuer_signed(issuer_signed.clone());
+
+        let encoded = credential.encode().unwrap();
+        assert_eq!(
+            serde_json::to_value(&credential).unwrap(),
+            serde_json::Value::String(encoded.clone())
+        );
+
+        let decoded = IsoIssuerSigned::decode(&encoded).unwrap();
+        assert_eq!(
+            serde_cbor::to_vec(decoded.issuer_signed()).unwrap(),
+            serde_cbor::to_vec(&issuer_signed).unwrap()
+        );
+        let decoded: IsoIssuerSigned =
+            serde_json::from_value(serde_json::Value::String(encoded)).unwrap();
+        assert_eq!(
+            serde_cbor::to_vec(&decoded.into_inner()).unwrap(),
+            serde_cbor::to_vec(&issuer_signed).unwrap()
+        );
     }
 }
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use isomdl::definitions::device_request::{DataElementIdentifier, NameSpace};
+use isomdl::definitions::device_request::{DataElementIdentifier, DocType, NameSpace};
 use serde::{Deserialize, Serialize};
 
 pub mod authorization_detail;
@@ -23,3 +23,28 @@ pub enum Format {
 }
 
 pub type Claims<T> = HashMap<NameSpace, HashMap<DataElementIdentifier, T>>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DoctypeError {
+    #[error("doctype is empty")]
+    Empty,
+    #[error("doctype `{actual}` does not match the doctype `{expected}` of the credential configuration")]
+    Mismatch { expected: DocType, actual: DocType },
+}
+
+/// Checks that `doctype` is the one advertised by the credential configuration.
+fn check_doctype(
+    configuration: &CredentialConfiguration,
+    doctype: &DocType,
+) -> Result<(), DoctypeError> {
+    if doctype.is_empty() {
+        return Err(DoctypeError::Empty);
+    }
+    if doctype != configuration.doctype() {
+        return Err(DoctypeError::Mismatch {
+            expected: configuration.doctype().clone(),
+            actual: doctype.clone(),
+        });
+    }
+    Ok(())
+}
@@ -6,7 +6,7 @@ use serde_json::Value;
 use crate::{
     profiles::{
         AuthorizationDetailsObjectProfile, CredentialConfigurationProfile,
-        CredentialRequestProfile, CredentialResponseProfile, Profile,
+        CredentialRequestProfile, CredentialResponseProfile, KnownFields, Profile,
     },
     types::{ClaimValueType, CredentialConfigurationId, LanguageTag},
 };
@@ -27,7 +27,27 @@ pub enum CustomProfilesCredentialConfiguration {
     VcSdJwt(vc_sd_jwt::CredentialConfiguration),
 }
 
-impl CredentialConfigurationProfile for CustomProfilesCredentialConfiguration {}
+impl CustomProfilesCredentialConfiguration {
+    /// Builds a credential request for this configuration, with the format and the `vct` copied
+    /// from it.
+    pub fn build_request(&self) -> CustomProfilesCredentialRequest {
+        let inner = match self {
+            Self::VcSdJwt(config) => CredentialRequestWithFormat::VcSdJwt(config.to_request()),
+        };
+        CustomProfilesCredentialRequest::WithFormat {
+            inner,
+            _credential_identifier: (),
+        }
+    }
+}
+
+impl CredentialConfigurationProfile for CustomProfilesCredentialConfiguration {
+    fn format_identifier(&self) -> Option<&str> {
+        match self {
+            Self::VcSdJwt(config) => Some(config.format().as_str()),
+        }
+    }
+}
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(untagged)]
@@ -127,6 +147,27 @@ pub enum CustomProfilesCredentialRequest {
 
 impl CredentialRequestProfile for CustomProfilesCredentialRequest {
     type Response = CustomProfilesCredentialResponse;
+
+    fn known_fields(&self) -> KnownFields {
+        match self {
+            Self::WithFormat {
+                inner: CredentialRequestWithFormat::VcSdJwt(request),
+                ..
+            } => request.known_fields(),
+            // Members of unresolved profiles are all kept.
+            Self::WithIdAndUnresolvedProfile { .. } | Self::WithId { .. } => KnownFields::Any,
+        }
+    }
+
+    fn from_credential_identifier(
+        credential_identifier: CredentialConfigurationId,
+    ) -> Option<Self> {
+        Some(Self::WithIdAndUnresolvedProfile {
+            credential_identifier,
+            inner: HashMap::new(),
+            _format: (),
+        })
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -17,6 +17,7 @@ pub struct AuthorizationDetailsObjectWithFormat {
 impl AuthorizationDetailsObjectWithFormat {
     field_getters_setters![
         pub self [self] ["VC SD-JWT authorization detail value"] {
+            set_format -> format[Format],
             set_vct -> vct[String],
             set_claims -> claims[Option<Claims<CredentialConfigurationClaim>>],
         }
@@ -5,12 +5,16 @@ use crate::{
     profiles::CredentialConfigurationProfile,
 };
 
-use super::{Claims, Format};
+use super::{Claims, CredentialRequestWithFormat, Format};
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 pub struct CredentialConfiguration {
     format: Format,
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        alias = "cryptographic_suites_supported"
+    )]
     credential_signing_alg_values_supported: Vec<ssi::jwk::Algorithm>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     claims: Option<Claims<CredentialConfigurationClaim>>,
@@ -29,12 +33,19 @@ impl CredentialConfiguration {
 
     field_getters_setters![
         pub self [self] ["VC SD-JWT metadata value"] {
+            set_format -> format[Format],
             set_credential_signing_alg_values_supported -> credential_signing_alg_values_supported[Vec<ssi::jwk::Algorithm>],
             set_order -> order[Vec<String>],
             set_vct -> vct[String],
             set_claims -> claims[Option<Claims<CredentialConfigurationClaim>>],
         }
     ];
+
+    /// Builds a credential request for this configuration, with the format and the `vct`
+    /// copied from it.
+    pub fn to_request(&self) -> CredentialRequestWithFormat {
+        CredentialRequestWithFormat::new(self.vct.clone(), None).set_format(self.format.clone())
+    }
 }
 
 impl CredentialConfigurationProfile for CredentialConfiguration {}
@@ -45,6 +56,24 @@ mod test {
 
     use crate::metadata::credential_issuer::CredentialConfiguration;
 
+    #[test]
+    fn to_request() {
+        let configuration = super::CredentialConfiguration::new(
+            "https://credentials.example.com/identity_credential".into(),
+        )
+        .set_format(super::Format::DcSdJwt);
+
+        let request = configuration.to_request();
+        assert_eq!(request.vct(), configuration.vct());
+        assert_json_diff::assert_json_eq!(
+            serde_json::to_value(request).unwrap(),
+            json!({
+                "format": "dc+sd-jwt",
+                "vct": "https://credentials.example.com/identity_credential"
+            })
+        );
+    }
+
     #[test]
     fn roundtrip() {
         let expected_json = json!(
@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     profiles::custom::profiles::CredentialConfigurationClaim,
-    profiles::custom::profiles::CredentialRequestProfile,
+    profiles::custom::profiles::CredentialRequestProfile, profiles::custom::profiles::KnownFields,
 };
 
 use super::{Claims, CredentialResponse, Format};
@@ -16,6 +16,7 @@ pub struct CredentialRequestWithFormat {
 }
 
 impl CredentialRequestWithFormat {
+    /// Creates a request for the SD-JWT VC type `vct`, optionally restricted to some `claims`.
     pub fn new(vct: String, claims: Option<Claims<CredentialConfigurationClaim>>) -> Self {
         Self {
             format: Format::default(),
@@ -25,6 +26,7 @@ impl CredentialRequestWithFormat {
     }
     field_getters_setters![
         pub self [self] ["VC SD-JWT request value"] {
+            set_format -> format[Format],
             set_vct -> vct[String],
             set_claims -> claims[Option<Claims<CredentialConfigurationClaim>>],
         }
@@ -33,6 +35,14 @@ impl CredentialRequestWithFormat {
 
 impl CredentialRequestProfile for CredentialRequestWithFormat {
     type Response = CredentialResponse;
+
+    fn known_fields(&self) -> KnownFields {
+        KnownFields::Object(&[
+            ("format", KnownFields::Any),
+            ("vct", KnownFields::Any),
+            ("claims", KnownFields::Any),
+        ])
+    }
 }
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
@@ -67,6 +77,79 @@ mod test {
 
     use crate::credential::Request;
 
+    #[test]
+    fn minimal_request_with_vct() {
+        let credential_request = Request::new(super::CredentialRequestWithFormat::new(
+            "SD_JWT_VC_example_in_OpenID4VCI".into(),
+            None,
+        ));
+
+        assert_json_diff::assert_json_eq!(
+            json!({
+                "format": "dc+sd-jwt",
+                "vct": "SD_JWT_VC_example_in_OpenID4VCI"
+            }),
+            serde_json::to_value(credential_request).unwrap()
+        );
+    }
+
+    #[test]
+    fn build_request_from_configuration() {
+        let configuration: crate::profiles::custom::profiles::CustomProfilesCredentialConfiguration =
+            serde_json::from_value(json!({
+                "format": "spruce-vc+sd-jwt",
+                "vct": "SD_JWT_VC_example_in_OpenID4VCI",
+                "claims": { "given_name": {} }
+            }))
+            .unwrap();
+
+        assert_json_diff::assert_json_eq!(
+            json!({
+                "format": "spruce-vc+sd-jwt",
+                "vct": "SD_JWT_VC_example_in_OpenID4VCI"
+            }),
+            serde_json::to_value(Request::new(configuration.build_request())).unwrap()
+        );
+    }
+
+    #[test]
+    fn dc_sd_jwt_format() {
+        for format in ["vc+sd-jwt", "dc+sd-jwt"] {
+            let configuration: crate::profiles::custom::profiles::CustomProfilesCredentialConfiguration =
+                serde_json::from_value(json!({
+                    "format": format,
+                    "vct": "SD_JWT_VC_example_in_OpenID4VCI"
+                }))
+                .unwrap();
+
+            // The request has the same format identifier as the configuration.
+            assert_json_diff::assert_json_eq!(
+                json!({
+                    "format": format,
+                    "vct": "SD_JWT_VC_example_in_OpenID4VCI"
+                }),
+                serde_json::to_value(Request::new(configuration.build_request())).unwrap()
+            );
+        }
+
+        for (legacy, format) in [(true, "vc+sd-jwt"), (false, "dc+sd-jwt")] {
+            let credential_request = Request::new(
+                super::CredentialRequestWithFormat::new(
+                    "SD_JWT_VC_example_in_OpenID4VCI".into(),
+                    None,
+                )
+                .set_format(super::Format::new(legacy)),
+            );
+            assert_json_diff::assert_json_eq!(
+                json!({
+                    "format": format,
+                    "vct": "SD_JWT_VC_example_in_OpenID4VCI"
+                }),
+                serde_json::to_value(credential_request).unwrap()
+            );
+        }
+    }
+
     #[test]
     fn roundtrip_with_format() {
         let expected_json = json!(
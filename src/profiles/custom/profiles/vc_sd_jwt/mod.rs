@@ -15,9 +15,36 @@ pub use credential_response::CredentialResponse;
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 pub enum Format {
-    #[default]
     #[serde(rename = "spruce-vc+sd-jwt")]
     VcSdJwt,
+    /// The SD-JWT VC format identifier.
+    #[default]
+    #[serde(rename = "dc+sd-jwt")]
+    DcSdJwt,
+    /// The SD-JWT VC format identifier of earlier drafts, before it was renamed `dc+sd-jwt`.
+    #[serde(rename = "vc+sd-jwt")]
+    LegacyVcSdJwt,
+}
+
+impl Format {
+    /// Returns the `dc+sd-jwt` format, or the `vc+sd-jwt` format of earlier drafts for backward
+    /// compatibility with issuers and wallets that only know the latter.
+    pub fn new(legacy: bool) -> Self {
+        if legacy {
+            Self::LegacyVcSdJwt
+        } else {
+            Self::DcSdJwt
+        }
+    }
+
+    /// Returns the format identifier, as serialized in the `format` parameter.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::VcSdJwt => FORMAT_IDENTIFIER,
+            Self::DcSdJwt => "dc+sd-jwt",
+            Self::LegacyVcSdJwt => "vc+sd-jwt",
+        }
+    }
 }
 
 pub type Claims<T> = HashMap<String, Box<MaybeNestedClaims<T>>>;
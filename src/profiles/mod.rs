@@ -1,6 +1,9 @@
 use std::fmt::Debug;
 
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::types::CredentialConfigurationId;
 
 pub mod core;
 pub mod custom;
@@ -11,10 +14,81 @@ pub trait Profile {
     type CredentialRequest: CredentialRequestProfile;
     type CredentialResponse: CredentialResponseProfile;
 }
-pub trait CredentialConfigurationProfile: Clone + Debug + DeserializeOwned + Serialize {}
-pub trait AuthorizationDetailsObjectProfile: Debug + DeserializeOwned + Serialize {}
+pub trait CredentialConfigurationProfile: Clone + Debug + DeserializeOwned + Serialize {
+    /// Returns the `format` identifier of the credential configuration, e.g. `mso_mdoc`, if the
+    /// profile knows it.
+    fn format_identifier(&self) -> Option<&str> {
+        None
+    }
+}
+pub trait AuthorizationDetailsObjectProfile: Debug + DeserializeOwned + Serialize {
+    /// Build an authorization details object referencing a credential configuration of the
+    /// credential issuer metadata, if the profile supports it.
+    fn from_credential_configuration_id(
+        _credential_configuration_id: CredentialConfigurationId,
+    ) -> Option<Self> {
+        None
+    }
+}
+/// The members a profile reads from a JSON object, under their names and aliases.
+#[derive(Clone, Copy, Debug)]
+pub enum KnownFields {
+    /// Any member is read, or the value is not an object.
+    Any,
+    /// Only the listed members are read, with the members their values may contain.
+    Object(&'static [(&'static str, KnownFields)]),
+}
+
+impl KnownFields {
+    /// Pushes the members of `value` that are not known, by their dotted path below `path`.
+    pub(crate) fn collect_unknown(self, value: &Value, path: &str, unknown: &mut Vec<String>) {
+        if let Value::Object(members) = value {
+            for (key, value) in members {
+                self.collect_unknown_member(key, value, path, unknown);
+            }
+        }
+    }
+
+    /// Pushes the member `key` if it is not known, or its own unknown members otherwise.
+    pub(crate) fn collect_unknown_member(
+        self,
+        key: &str,
+        value: &Value,
+        path: &str,
+        unknown: &mut Vec<String>,
+    ) {
+        let Self::Object(fields) = self else {
+            return;
+        };
+        let path = if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{path}.{key}")
+        };
+        match fields.iter().find(|(name, _)| *name == key) {
+            Some((_, known)) => known.collect_unknown(value, &path, unknown),
+            None => unknown.push(path),
+        }
+    }
+}
+
 pub trait CredentialRequestProfile: Clone + Debug + DeserializeOwned + Serialize {
     type Response: CredentialResponseProfile;
+
+    /// The members of a credential request read by the profile, as used for
+    /// [`Request::from_json_strict`](crate::credential::Request::from_json_strict). Defaults to
+    /// accepting any member.
+    fn known_fields(&self) -> KnownFields {
+        KnownFields::Any
+    }
+
+    /// Build a request referencing a credential identifier returned in the token response, if
+    /// the profile supports it.
+    fn from_credential_identifier(
+        _credential_identifier: CredentialConfigurationId,
+    ) -> Option<Self> {
+        None
+    }
 }
 pub trait CredentialResponseProfile: Debug + DeserializeOwned + Serialize {
     type Type: Clone + Debug + DeserializeOwned + Serialize;
@@ -31,7 +105,14 @@ pub enum ProfilesCredentialConfiguration {
     Custom(custom::profiles::CustomProfilesCredentialConfiguration),
 }
 
-impl CredentialConfigurationProfile for ProfilesCredentialConfiguration {}
+impl CredentialConfigurationProfile for ProfilesCredentialConfiguration {
+    fn format_identifier(&self) -> Option<&str> {
+        match self {
+            Self::Core(config) => config.format_identifier(),
+            Self::Custom(config) => config.format_identifier(),
+        }
+    }
+}
 
 /// A type representing the data contained in the `authorization_details` parameter of an authorization
 /// request. This may contain fields that are specific to particular credential formats that the
@@ -44,7 +125,16 @@ pub enum ProfilesAuthorizationDetailsObject {
     Custom(custom::profiles::CustomProfilesAuthorizationDetailsObject),
 }
 
-impl AuthorizationDetailsObjectProfile for ProfilesAuthorizationDetailsObject {}
+impl AuthorizationDetailsObjectProfile for ProfilesAuthorizationDetailsObject {
+    fn from_credential_configuration_id(
+        credential_configuration_id: CredentialConfigurationId,
+    ) -> Option<Self> {
+        core::profiles::CoreProfilesAuthorizationDetailsObject::from_credential_configuration_id(
+            credential_configuration_id,
+        )
+        .map(Self::Core)
+    }
+}
 
 // TODO (SKIT-797): Profiles no longer have specific fields in the credential request data structure as of
 // draft 13. This should be removed.
@@ -57,6 +147,22 @@ pub enum ProfilesCredentialRequest {
 
 impl CredentialRequestProfile for ProfilesCredentialRequest {
     type Response = ProfilesCredentialResponse;
+
+    fn known_fields(&self) -> KnownFields {
+        match self {
+            Self::Core(request) => request.known_fields(),
+            Self::Custom(request) => request.known_fields(),
+        }
+    }
+
+    fn from_credential_identifier(
+        credential_identifier: CredentialConfigurationId,
+    ) -> Option<Self> {
+        core::profiles::CoreProfilesCredentialRequest::from_credential_identifier(
+            credential_identifier,
+        )
+        .map(Self::Core)
+    }
 }
 
 // TODO (SKIT-797): Profiles no longer have specific fields in the credential request data structure as of
@@ -1,17 +1,27 @@
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use std::future::Future;
+
+use base64::prelude::*;
+use oauth2::TokenResponse;
+use serde::{de, de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use serde_with::skip_serializing_none;
+use sha2::{Digest, Sha256};
 use ssi::claims::{
     jws::{self, Header},
     jwt,
 };
-use ssi::dids::DIDURLBuf;
+use ssi::dids::{DIDBuf, DIDURLBuf};
 use ssi::jwk::{Algorithm, JWKResolver, JWK};
 use time::{Duration, OffsetDateTime};
 use url::Url;
 
-use crate::types::Nonce;
+use crate::{
+    credential::ErrorType, metadata::CredentialIssuerMetadata,
+    profiles::CredentialConfigurationProfile, token, types::Nonce,
+};
 
 const JWS_TYPE: &str = "openid4vci-proof+jwt";
+const KEY_ATTESTATION_JWS_TYPE: &str = "keyattestation+jwt";
 
 pub type ProofSigningAlgValuesSupported = Vec<ssi::jwk::Algorithm>;
 
@@ -20,6 +30,42 @@ pub struct KeyProofTypesSupported {
     #[serde(rename = "$key$")]
     key: KeyProofType,
     proof_signing_alg_values_supported: Vec<ssi::jwk::Algorithm>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key_attestations_required: Option<KeyAttestationsRequired>,
+}
+
+impl KeyProofTypesSupported {
+    pub fn new(key: KeyProofType, proof_signing_alg_values_supported: Vec<Algorithm>) -> Self {
+        Self {
+            key,
+            proof_signing_alg_values_supported,
+            key_attestations_required: None,
+        }
+    }
+
+    field_getters_setters![
+        pub self [self] ["supported key proof type value"] {
+            set_key -> key[KeyProofType],
+            set_proof_signing_alg_values_supported -> proof_signing_alg_values_supported[Vec<Algorithm>],
+            set_key_attestations_required -> key_attestations_required[Option<KeyAttestationsRequired>],
+        }
+    ];
+}
+
+#[skip_serializing_none]
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct KeyAttestationsRequired {
+    key_storage: Option<Vec<String>>,
+    user_authentication: Option<Vec<String>>,
+}
+
+impl KeyAttestationsRequired {
+    field_getters_setters![
+        pub self [self] ["key attestation requirement"] {
+            set_key_storage -> key_storage[Option<Vec<String>>],
+            set_user_authentication -> user_authentication[Option<Vec<String>>],
+        }
+    ];
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
@@ -30,9 +76,11 @@ pub enum KeyProofType {
     Cwt,
     #[serde(rename = "ldp_vp")]
     LdpVp,
+    #[serde(rename = "attestation")]
+    Attestation,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[derive(Debug, Serialize, PartialEq, Clone)]
 #[serde(tag = "proof_type")]
 pub enum Proof {
     #[serde(rename = "jwt")]
@@ -41,6 +89,126 @@ pub enum Proof {
     Cwt { cwt: String },
     #[serde(rename = "ldp_vp")]
     LdpVp { ldp_vp: Value },
+    #[serde(rename = "attestation")]
+    Attestation { attestation: String },
+    /// A proof whose type is not supported by this crate, kept as is so that the rest of the
+    /// credential request can still be parsed, and the proof rejected.
+    #[serde(untagged)]
+    Unknown {
+        proof_type: String,
+        #[serde(flatten)]
+        extra: Map<String, Value>,
+    },
+}
+
+impl<'de> Deserialize<'de> for Proof {
+    /// Dispatches on `proof_type` first, so that a proof of a known type with invalid fields is
+    /// an error rather than a [`Proof::Unknown`] proof.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        fn field<T, E>(fields: &mut Map<String, Value>, name: &'static str) -> Result<T, E>
+        where
+            T: DeserializeOwned,
+            E: de::Error,
+        {
+            let value = fields.remove(name).ok_or_else(|| E::missing_field(name))?;
+            serde_json::from_value(value).map_err(E::custom)
+        }
+
+        let mut fields = Map::deserialize(deserializer)?;
+        let proof_type = match fields.remove("proof_type") {
+            Some(Value::String(proof_type)) => proof_type,
+            Some(_) => return Err(de::Error::custom("`proof_type` must be a string")),
+            None => return Err(de::Error::missing_field("proof_type")),
+        };
+        match serde_json::from_value(Value::String(proof_type.clone())) {
+            Ok(KeyProofType::Jwt) => Ok(Self::Jwt {
+                jwt: field(&mut fields, "jwt")?,
+            }),
+            Ok(KeyProofType::Cwt) => Ok(Self::Cwt {
+                cwt: field(&mut fields, "cwt")?,
+            }),
+            Ok(KeyProofType::LdpVp) => Ok(Self::LdpVp {
+                ldp_vp: field(&mut fields, "ldp_vp")?,
+            }),
+            Ok(KeyProofType::Attestation) => Ok(Self::Attestation {
+                attestation: field(&mut fields, "attestation")?,
+            }),
+            Err(_) => Ok(Self::Unknown {
+                proof_type,
+                extra: fields,
+            }),
+        }
+    }
+}
+
+/// Several proofs of the same type, sent in the `proofs` parameter of a credential request to
+/// obtain one credential per proof.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub enum Proofs {
+    #[serde(rename = "jwt")]
+    Jwt(Vec<String>),
+    #[serde(rename = "cwt")]
+    Cwt(Vec<String>),
+    #[serde(rename = "ldp_vp")]
+    LdpVp(Vec<Value>),
+    #[serde(rename = "attestation")]
+    Attestation(Vec<String>),
+}
+
+impl Proofs {
+    /// Groups proofs by type, returning `None` if they are not all of the same type or if there
+    /// is no proof.
+    pub fn from_proofs(proofs: Vec<Proof>) -> Option<Self> {
+        let mut proofs = proofs.into_iter();
+        let mut grouped = match proofs.next()? {
+            Proof::Jwt { jwt } => Self::Jwt(vec![jwt]),
+            Proof::Cwt { cwt } => Self::Cwt(vec![cwt]),
+            Proof::LdpVp { ldp_vp } => Self::LdpVp(vec![ldp_vp]),
+            Proof::Attestation { attestation } => Self::Attestation(vec![attestation]),
+            Proof::Unknown { .. } => return None,
+        };
+        for proof in proofs {
+            match (&mut grouped, proof) {
+                (Self::Jwt(proofs), Proof::Jwt { jwt }) => proofs.push(jwt),
+                (Self::Cwt(proofs), Proof::Cwt { cwt }) => proofs.push(cwt),
+                (Self::LdpVp(proofs), Proof::LdpVp { ldp_vp }) => proofs.push(ldp_vp),
+                (Self::Attestation(proofs), Proof::Attestation { attestation }) => {
+                    proofs.push(attestation)
+                }
+                _ => return None,
+            }
+        }
+        Some(grouped)
+    }
+
+    /// Splits the proofs into individual [`Proof`]s.
+    pub fn to_proofs(&self) -> Vec<Proof> {
+        match self {
+            Self::Jwt(proofs) => proofs
+                .iter()
+                .map(|jwt| Proof::Jwt { jwt: jwt.clone() })
+                .collect(),
+            Self::Cwt(proofs) => proofs
+                .iter()
+                .map(|cwt| Proof::Cwt { cwt: cwt.clone() })
+                .collect(),
+            Self::LdpVp(proofs) => proofs
+                .iter()
+                .map(|ldp_vp| Proof::LdpVp {
+                    ldp_vp: ldp_vp.clone(),
+                })
+                .collect(),
+            Self::Attestation(proofs) => proofs
+                .iter()
+                .map(|attestation| Proof::Attestation {
+                    attestation: attestation.clone(),
+                })
+                .collect(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -67,10 +235,68 @@ pub struct ProofOfPossessionBody {
     pub nonce: Nonce,
 }
 
+impl ProofOfPossessionBody {
+    /// Whether the proof is expired at `now`, i.e. `now` is after its `exp`.
+    pub fn is_expired(&self, now: OffsetDateTime) -> bool {
+        now > self.expires_at
+    }
+
+    /// Whether the proof can be used at `now`, i.e. `now` is between its `nbf`, if any, and its
+    /// `exp`.
+    ///
+    /// Unlike [`ProofOfPossession::verify`], no clock skew tolerance is applied.
+    pub fn is_active(&self, now: OffsetDateTime) -> bool {
+        !self.not_before.is_some_and(|not_before| now < not_before) && !self.is_expired(now)
+    }
+
+    /// Returns how long the proof remains valid after `now`, zero if it is expired.
+    pub fn time_to_live(&self, now: OffsetDateTime) -> Duration {
+        (self.expires_at - now).max(Duration::ZERO)
+    }
+}
+
+/// Claims of a key attestation, in which a wallet provider attests to the properties of one or
+/// more keys held by the wallet.
+///
+/// See <https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0-15.html#appendix-D.1>.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KeyAttestationBody {
+    #[serde(rename = "iss", default, skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+    #[serde(rename = "iat", with = "time::serde::timestamp")]
+    pub issued_at: OffsetDateTime,
+    #[serde(
+        rename = "exp",
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "time::serde::timestamp::option"
+    )]
+    pub expires_at: Option<OffsetDateTime>,
+    pub attested_keys: Vec<JWK>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_storage: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_authentication: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub certification: Option<Url>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<Nonce>,
+}
+
+/// A validated key attestation, as sent with the `attestation` proof type.
+#[derive(Debug, Clone)]
+pub struct KeyAttestation {
+    pub body: KeyAttestationBody,
+    /// Key of the attestation issuer, used to verify the attestation.
+    pub issuer_jwk: JWK,
+}
+
 #[derive(Debug, Clone)]
 pub struct ProofOfPossession {
     pub body: ProofOfPossessionBody,
     pub controller: ProofOfPossessionController,
+    /// Algorithm of the proof JWT, defaulting to the algorithm of the controller's JWK.
+    pub algorithm: Option<Algorithm>,
 }
 
 #[derive(Debug, Clone)]
@@ -79,6 +305,17 @@ pub struct ProofOfPossessionController {
     pub jwk: JWK,
 }
 
+impl ProofOfPossessionController {
+    /// Sets the `kid` of the JWK to its RFC 7638 thumbprint.
+    ///
+    /// Without a verification method, the proof header then embeds the public JWK along with this
+    /// deterministic `kid`, instead of referencing the key by `kid` only.
+    pub fn with_thumbprint_kid(mut self) -> Result<Self, ssi::jwk::Error> {
+        self.jwk.key_id = Some(self.jwk.thumbprint()?);
+        Ok(self)
+    }
+}
+
 pub struct ProofOfPossessionParams {
     pub audience: Url,
     pub issuer: String,
@@ -86,6 +323,7 @@ pub struct ProofOfPossessionParams {
     pub controller: ProofOfPossessionController,
 }
 
+#[derive(Clone, Debug)]
 pub struct ProofOfPossessionVerificationParams {
     pub audience: Url,
     pub issuer: String,
@@ -96,6 +334,25 @@ pub struct ProofOfPossessionVerificationParams {
     pub nbf_tolerance: Option<Duration>,
     /// Slack in exp validation to deal with clock synchronisation issues.
     pub exp_tolerance: Option<Duration>,
+    /// Algorithms the proof may be signed with, any algorithm being accepted if `None`.
+    pub allowed_algorithms: Option<Vec<Algorithm>>,
+    /// Do not compare the nonce of the proof to `nonce`, for issuers validating it separately.
+    pub skip_nonce_check: bool,
+}
+
+/// How to handle proof JWTs whose header contains more than one key parameter (`kid`, `jwk`,
+/// `x5c`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeyParameterPolicy {
+    /// Exactly one key parameter must be present, as required by the specification.
+    #[default]
+    Strict,
+    /// Several key parameters are accepted, as long as they refer to the same key.
+    ///
+    /// The `x5c` chain is not parsed: the key referenced by `kid` or `jwk` must either contain
+    /// the same leaf certificate in its own `x5c` member, or its SHA-256 thumbprint in
+    /// `x5t#S256`.
+    Lenient,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -114,6 +371,20 @@ pub enum VerificationError {
     InvalidJWK,
     #[error("proof of possession DID does not match, expected `{expected}`, found `{actual}`")]
     InvalidDID { actual: String, expected: String },
+    #[error("proof of possession algorithm `{0:?}` is not allowed")]
+    DisallowedAlgorithm(Option<Algorithm>),
+    #[error("proof of possession nonce does not match")]
+    InvalidNonce,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum BindingError {
+    #[error("proof of possession audience must be the credential issuer, expected `{expected}`, found `{actual}`")]
+    InvalidAudience { actual: String, expected: String },
+    #[error("proof of possession must use the `c_nonce` of the token response")]
+    MissingNonce,
+    #[error("proof of possession nonce does not match the `c_nonce` of the token response")]
+    InvalidNonce,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -124,6 +395,43 @@ pub enum ConversionError {
     SigningError(#[from] ssi::claims::jws::Error),
     #[error("Unable to select JWT algorithm, please specify in JWK")]
     MissingJWKAlg,
+    #[error("signer failed: {0}")]
+    SignerError(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// Signs JWT signing inputs asynchronously, e.g. with a key held in a secure enclave or HSM that
+/// cannot be exported as a [`JWK`].
+///
+/// Implementations can use an `async fn`, as long as the returned future is `Send`.
+pub trait AsyncSigner {
+    /// Returns the JWS signature of `signing_input` for the `algorithm` of the JWT header.
+    fn sign(
+        &self,
+        algorithm: Algorithm,
+        signing_input: &[u8],
+    ) -> impl Future<Output = Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>> + Send;
+}
+
+impl<T: AsyncSigner> AsyncSigner for &T {
+    fn sign(
+        &self,
+        algorithm: Algorithm,
+        signing_input: &[u8],
+    ) -> impl Future<Output = Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>> + Send
+    {
+        T::sign(self, algorithm, signing_input)
+    }
+}
+
+/// Signs with an in-memory private key, as [`ProofOfPossession::to_jwt`] does.
+impl AsyncSigner for JWK {
+    async fn sign(
+        &self,
+        algorithm: Algorithm,
+        signing_input: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(jws::sign_bytes(algorithm, signing_input, self)?)
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -138,6 +446,10 @@ pub enum ParsingError {
     MissingKeyParameters,
     #[error("Too many key parameters specified, exactly one of the following parameters needs to be present: (kid, jwk, x5c)")]
     TooManyKeyParameters,
+    #[error("Unsupported key parameter `{0}`")]
+    UnsupportedKeyParameter(String),
+    #[error("Key parameters (kid, jwk, x5c) do not refer to the same key")]
+    InconsistentKeyParameters,
     #[error("Could not retrieve JWK from KID: {0}")]
     KIDDereferenceError(String),
     #[error(transparent)]
@@ -146,25 +458,195 @@ pub enum ParsingError {
     InvalidDIDURL(#[from] ssi::dids::InvalidDIDURL<String>),
     #[error(transparent)]
     ProofValidationError(#[from] ssi::claims::ProofValidationError),
+    #[error("Unexpected proof type `{0:?}`")]
+    UnexpectedProofType(KeyProofType),
+    #[error("Unsupported proof type `{0}`")]
+    UnsupportedProofType(String),
+    #[error("Key attestation does not attest any key")]
+    MissingAttestedKeys,
+    #[error("Key attestation is expired")]
+    ExpiredKeyAttestation,
 }
 
-impl ProofOfPossession {
-    pub fn generate(params: &ProofOfPossessionParams, expiry: Duration) -> Self {
+/// Errors that can occur when parsing and verifying a proof of possession, see
+/// [`ProofOfPossession::parse_and_verify`].
+#[derive(thiserror::Error, Debug)]
+pub enum ProofError {
+    #[error(transparent)]
+    Parsing(#[from] ParsingError),
+    #[error(transparent)]
+    Verification(#[from] VerificationError),
+}
+
+impl ProofError {
+    /// The error code of the credential error response to return to the wallet.
+    pub fn error_type(&self) -> ErrorType {
+        match self {
+            Self::Parsing(
+                ParsingError::UnexpectedProofType(_) | ParsingError::UnsupportedProofType(_),
+            ) => ErrorType::InvalidCredentialRequest,
+            Self::Parsing(_) | Self::Verification(_) => ErrorType::InvalidProof,
+        }
+    }
+}
+
+impl KeyAttestation {
+    pub async fn from_proof(
+        proof: &Proof,
+        resolver: impl JWKResolver,
+    ) -> Result<Self, ParsingError> {
+        match proof {
+            Proof::Attestation { attestation } => Self::from_jwt(attestation, resolver).await,
+            Proof::Jwt { .. } => Err(ParsingError::UnexpectedProofType(KeyProofType::Jwt)),
+            Proof::Cwt { .. } => Err(ParsingError::UnexpectedProofType(KeyProofType::Cwt)),
+            Proof::LdpVp { .. } => Err(ParsingError::UnexpectedProofType(KeyProofType::LdpVp)),
+            Proof::Unknown { proof_type, .. } => {
+                Err(ParsingError::UnsupportedProofType(proof_type.clone()))
+            }
+        }
+    }
+
+    /// Parse and verify a key attestation JWT, and validate its `key_attestation` claims.
+    pub async fn from_jwt(jwt: &str, resolver: impl JWKResolver) -> Result<Self, ParsingError> {
+        let header: Header = jws::decode_unverified(jwt)?.0;
+
+        if header.type_.as_deref() != Some(KEY_ATTESTATION_JWS_TYPE) {
+            return Err(ParsingError::InvalidJWSType {
+                actual: format!("{:?}", header.type_),
+                expected: KEY_ATTESTATION_JWS_TYPE.to_string(),
+            });
+        }
+        if header.algorithm == Algorithm::None {
+            return Err(ParsingError::MissingJWSAlg);
+        }
+        let issuer_jwk = match (header.key_id, header.jwk, header.x509_certificate_chain) {
+            (Some(kid), None, None) => resolver
+                .fetch_public_jwk(Some(&kid))
+                .await
+                .map(|r| r.into_owned())?,
+            (None, Some(jwk), None) => jwk,
+            (None, None, Some(_x5c)) => {
+                return Err(ParsingError::UnsupportedKeyParameter("x5c".to_string()))
+            }
+            (None, None, None) => return Err(ParsingError::MissingKeyParameters),
+            _ => return Err(ParsingError::TooManyKeyParameters),
+        };
+        let body: KeyAttestationBody = jwt::decode_verify(jwt, &issuer_jwk)?;
+
+        if body.attested_keys.is_empty() {
+            return Err(ParsingError::MissingAttestedKeys);
+        }
+        if let Some(expires_at) = body.expires_at {
+            if OffsetDateTime::now_utc() > expires_at {
+                return Err(ParsingError::ExpiredKeyAttestation);
+            }
+        }
+
+        Ok(Self { body, issuer_jwk })
+    }
+}
+
+/// Builder of a [`ProofOfPossession`], see [`ProofOfPossession::builder`].
+#[derive(Debug, Clone)]
+pub struct ProofOfPossessionBuilder {
+    issuer: String,
+    audience: Url,
+    nonce: Option<Nonce>,
+    controller: ProofOfPossessionController,
+    expiry: Duration,
+    not_before: bool,
+    issued_at: bool,
+}
+
+impl ProofOfPossessionBuilder {
+    /// Sets the `jti` claim of the proof, which is otherwise the nonce of the parameters or a
+    /// random value.
+    pub fn set_nonce(mut self, nonce: Nonce) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Whether to include the `nbf` claim, `true` by default.
+    pub fn set_not_before(mut self, not_before: bool) -> Self {
+        self.not_before = not_before;
+        self
+    }
+
+    /// Whether to include the `iat` claim, `true` by default.
+    pub fn set_issued_at(mut self, issued_at: bool) -> Self {
+        self.issued_at = issued_at;
+        self
+    }
+
+    pub fn build(self) -> ProofOfPossession {
         let now = OffsetDateTime::now_utc();
-        let exp = now + expiry;
-        Self {
+        ProofOfPossession {
             body: ProofOfPossessionBody {
-                issuer: params.issuer.clone(),
-                audience: params.audience.clone(),
-                not_before: Some(now),
-                issued_at: Some(now),
-                expires_at: exp,
-                nonce: params.nonce.clone().unwrap_or_else(Nonce::new_random),
+                issuer: self.issuer,
+                audience: self.audience,
+                not_before: self.not_before.then_some(now),
+                issued_at: self.issued_at.then_some(now),
+                expires_at: now + self.expiry,
+                nonce: self.nonce.unwrap_or_else(Nonce::new_random),
             },
+            controller: self.controller,
+            algorithm: None,
+        }
+    }
+}
+
+impl ProofOfPossession {
+    /// Returns a builder of a proof of possession, which allows leaving out the `nbf` and `iat`
+    /// claims that some credential issuers reject.
+    pub fn builder(params: &ProofOfPossessionParams, expiry: Duration) -> ProofOfPossessionBuilder {
+        ProofOfPossessionBuilder {
+            issuer: params.issuer.clone(),
+            audience: params.audience.clone(),
+            nonce: params.nonce.clone(),
             controller: params.controller.clone(),
+            expiry,
+            not_before: true,
+            issued_at: true,
         }
     }
 
+    pub fn generate(params: &ProofOfPossessionParams, expiry: Duration) -> Self {
+        Self::builder(params, expiry).build()
+    }
+
+    /// Generate a proof of possession, after checking that the parameters are bound to the
+    /// credential issuer and to the `c_nonce` returned in the token response.
+    ///
+    /// The nonce is obtained from the token endpoint, but the audience of the proof must be the
+    /// credential issuer and not the authorization server.
+    pub fn generate_for_token_response<CM>(
+        params: &ProofOfPossessionParams,
+        expiry: Duration,
+        token_response: &token::Response,
+        credential_issuer_metadata: &CredentialIssuerMetadata<CM>,
+    ) -> Result<Self, BindingError>
+    where
+        CM: CredentialConfigurationProfile,
+    {
+        let credential_issuer = credential_issuer_metadata.credential_issuer();
+        if &params.audience != credential_issuer.url() {
+            return Err(BindingError::InvalidAudience {
+                expected: credential_issuer.to_string(),
+                actual: params.audience.to_string(),
+            });
+        }
+
+        if let Some(c_nonce) = &token_response.extra_fields().c_nonce {
+            match &params.nonce {
+                None => return Err(BindingError::MissingNonce),
+                Some(nonce) if nonce != c_nonce => return Err(BindingError::InvalidNonce),
+                Some(_) => {}
+            }
+        }
+
+        Ok(Self::generate(params, expiry))
+    }
+
     fn to_unsigned_jwt(&self) -> Result<(Header, String), ConversionError> {
         let jwk = &self.controller.jwk;
         let alg = if let Some(a) = jwk.get_algorithm() {
@@ -175,6 +657,11 @@ impl ProofOfPossession {
         let payload = serde_json::to_string(&self.body)?;
         let (h_kid, h_jwk) = match (self.controller.vm.clone(), jwk.key_id.clone()) {
             (Some(vm), _) => (Some(vm.to_string()), None),
+            // A thumbprint `kid` cannot be resolved by the issuer, see
+            // `ProofOfPossessionController::with_thumbprint_kid`.
+            (None, Some(kid)) if jwk.thumbprint().is_ok_and(|thumbprint| thumbprint == kid) => {
+                (None, Some(jwk.to_public()))
+            }
             (None, Some(kid)) => (Some(kid), None),
             (None, None) => (None, Some(jwk.to_public())),
         };
@@ -188,48 +675,84 @@ impl ProofOfPossession {
         Ok((header, payload))
     }
 
-    pub fn to_jwt_signing_input(&self) -> Result<Vec<u8>, ConversionError> {
-        use base64::prelude::*;
-
-        let (header, payload) = self.to_unsigned_jwt()?;
-        let json = serde_json::to_string(&header)?;
+    fn signing_input(header: &Header, payload: &str) -> Result<Vec<u8>, ConversionError> {
+        let json = serde_json::to_string(header)?;
         let header = BASE64_URL_SAFE_NO_PAD.encode(json);
-        let payload = BASE64_URL_SAFE_NO_PAD.encode(&payload);
+        let payload = BASE64_URL_SAFE_NO_PAD.encode(payload);
         let signing_input = [header.as_bytes(), b".", payload.as_bytes()]
             .concat()
             .to_vec();
         Ok(signing_input)
     }
 
+    /// Returns the JWT header used by [`ProofOfPossession::to_jwt`], derived from the controller
+    /// key.
+    pub fn to_jwt_header(&self) -> Result<Header, ConversionError> {
+        Ok(self.to_unsigned_jwt()?.0)
+    }
+
+    pub fn to_jwt_signing_input(&self) -> Result<Vec<u8>, ConversionError> {
+        let (header, payload) = self.to_unsigned_jwt()?;
+        Self::signing_input(&header, &payload)
+    }
+
     pub fn to_jwt(&self) -> Result<String, ConversionError> {
         let jwk = &self.controller.jwk;
         let (header, payload) = self.to_unsigned_jwt()?;
         Ok(jws::encode_sign_custom_header(&payload, jwk, &header)?)
     }
 
+    /// Same as [`ProofOfPossession::to_jwt`], but the JWT with the given `header` is signed by
+    /// `signer`, so the controller key only needs to hold the public key.
+    ///
+    /// [`ProofOfPossession::to_jwt_header`] returns the default header.
+    pub async fn to_jwt_with_signer(
+        &self,
+        header: Header,
+        signer: impl AsyncSigner,
+    ) -> Result<String, ConversionError> {
+        let payload = serde_json::to_string(&self.body)?;
+        let mut jwt = Self::signing_input(&header, &payload)?;
+        let signature = signer
+            .sign(header.algorithm, &jwt)
+            .await
+            .map_err(ConversionError::SignerError)?;
+        jwt.push(b'.');
+        jwt.extend(BASE64_URL_SAFE_NO_PAD.encode(signature).into_bytes());
+        Ok(String::from_utf8(jwt).expect("JWT is base64url encoded"))
+    }
+
     pub async fn from_proof(
         proof: &Proof,
         resolver: impl JWKResolver,
     ) -> Result<Self, ParsingError> {
         match proof {
             Proof::Jwt { jwt } => Self::from_jwt(jwt, resolver).await,
-            Proof::Cwt { .. } => todo!(),
-            Proof::LdpVp { .. } => todo!(),
+            Proof::Cwt { .. } => Err(ParsingError::UnexpectedProofType(KeyProofType::Cwt)),
+            Proof::LdpVp { .. } => Err(ParsingError::UnexpectedProofType(KeyProofType::LdpVp)),
+            // A key attestation does not carry a proof of possession, it has to be validated
+            // with `KeyAttestation::from_proof` instead.
+            Proof::Attestation { .. } => {
+                Err(ParsingError::UnexpectedProofType(KeyProofType::Attestation))
+            }
+            Proof::Unknown { proof_type, .. } => {
+                Err(ParsingError::UnsupportedProofType(proof_type.clone()))
+            }
         }
     }
 
     pub async fn from_jwt(jwt: &str, resolver: impl JWKResolver) -> Result<Self, ParsingError> {
-        let header: Header = jws::decode_unverified(jwt)?.0;
+        Self::from_jwt_with_policy(jwt, resolver, KeyParameterPolicy::Strict).await
+    }
 
-        if header.type_ != Some(JWS_TYPE.to_string()) {
-            return Err(ParsingError::InvalidJWSType {
-                actual: format!("{:?}", header.type_),
-                expected: JWS_TYPE.to_string(),
-            });
-        }
-        if header.algorithm == Algorithm::None {
-            return Err(ParsingError::MissingJWSAlg);
-        }
+    /// Same as [`ProofOfPossession::from_jwt`], with a configurable handling of headers
+    /// containing several key parameters.
+    pub async fn from_jwt_with_policy(
+        jwt: &str,
+        resolver: impl JWKResolver,
+        key_parameter_policy: KeyParameterPolicy,
+    ) -> Result<Self, ParsingError> {
+        let header = Self::decode_header(jwt)?;
         let (controller, jwk) = match (header.key_id, header.jwk, header.x509_certificate_chain) {
             (Some(kid), None, None) => {
                 let vm = kid.parse()?;
@@ -241,10 +764,15 @@ impl ProofOfPossession {
             }
             (None, Some(jwk), None) => (None, jwk),
             (None, None, Some(_x5c)) => {
-                unimplemented!();
+                return Err(ParsingError::UnsupportedKeyParameter("x5c".to_string()))
             }
             (None, None, None) => return Err(ParsingError::MissingKeyParameters),
-            _ => return Err(ParsingError::TooManyKeyParameters),
+            (kid, jwk, x5c) => match key_parameter_policy {
+                KeyParameterPolicy::Strict => return Err(ParsingError::TooManyKeyParameters),
+                KeyParameterPolicy::Lenient => {
+                    consistent_key_parameters(kid, jwk, x5c, resolver).await?
+                }
+            },
         };
         let body = jwt::decode_verify(jwt, &jwk)?;
         Ok(Self {
@@ -253,9 +781,54 @@ impl ProofOfPossession {
                 vm: controller,
                 jwk,
             },
+            algorithm: Some(header.algorithm),
+        })
+    }
+
+    /// Same as [`ProofOfPossession::from_jwt`], but verifies the JWT against `jwk` instead of
+    /// resolving the key parameters of the header, e.g. for issuers that pin the holder key.
+    pub fn from_jwt_with_key(jwt: &str, jwk: JWK) -> Result<Self, ParsingError> {
+        let header = Self::decode_header(jwt)?;
+        let body = jwt::decode_verify(jwt, &jwk)?;
+        Ok(Self {
+            body,
+            controller: ProofOfPossessionController { vm: None, jwk },
+            algorithm: Some(header.algorithm),
         })
     }
 
+    fn decode_header(jwt: &str) -> Result<Header, ParsingError> {
+        let header: Header = jws::decode_unverified(jwt)?.0;
+
+        if header.type_ != Some(JWS_TYPE.to_string()) {
+            return Err(ParsingError::InvalidJWSType {
+                actual: format!("{:?}", header.type_),
+                expected: JWS_TYPE.to_string(),
+            });
+        }
+        if header.algorithm == Algorithm::None {
+            return Err(ParsingError::MissingJWSAlg);
+        }
+        Ok(header)
+    }
+
+    /// Parses a proof of possession sent in a credential request, and verifies it.
+    pub async fn parse_and_verify(
+        proof: &Proof,
+        resolver: impl JWKResolver,
+        params: &ProofOfPossessionVerificationParams,
+    ) -> Result<Self, ProofError> {
+        let proof_of_possession = Self::from_proof(proof, resolver).await?;
+        proof_of_possession.verify(params).await?;
+        Ok(proof_of_possession)
+    }
+
+    /// Returns the DID of the holder, i.e. the verification method of the proof without its
+    /// fragment, e.g. to be used as the `credentialSubject.id` of the issued credential.
+    pub fn holder_did(&self) -> Option<DIDBuf> {
+        self.controller.vm.as_ref().map(|vm| vm.did().to_owned())
+    }
+
     pub async fn verify(
         &self,
         params: &ProofOfPossessionVerificationParams,
@@ -275,6 +848,15 @@ impl ProofOfPossession {
             return Err(VerificationError::Expired);
         }
 
+        if let Some(allowed_algorithms) = &params.allowed_algorithms {
+            let algorithm = self
+                .algorithm
+                .or_else(|| self.controller.jwk.get_algorithm());
+            if !algorithm.is_some_and(|algorithm| allowed_algorithms.contains(&algorithm)) {
+                return Err(VerificationError::DisallowedAlgorithm(algorithm));
+            }
+        }
+
         if self.body.issuer != params.issuer {
             return Err(VerificationError::InvalidIssuer {
                 expected: params.issuer.clone(),
@@ -289,6 +871,10 @@ impl ProofOfPossession {
             });
         }
 
+        if !params.skip_nonce_check && self.body.nonce != params.nonce {
+            return Err(VerificationError::InvalidNonce);
+        }
+
         if let Some(jwk) = &params.controller_jwk {
             if jwk != &self.controller.jwk {
                 return Err(VerificationError::InvalidJWK);
@@ -307,6 +893,66 @@ impl ProofOfPossession {
     }
 }
 
+/// Resolves the key referenced by several key parameters, checking that they are consistent.
+async fn consistent_key_parameters(
+    kid: Option<String>,
+    jwk: Option<JWK>,
+    x5c: Option<Vec<String>>,
+    resolver: impl JWKResolver,
+) -> Result<(Option<DIDURLBuf>, JWK), ParsingError> {
+    let (vm, key) = match (kid, &jwk) {
+        (Some(kid), _) => {
+            let vm = kid.parse()?;
+            let resolved = resolver
+                .fetch_public_jwk(Some(&kid))
+                .await
+                .map(|r| r.into_owned())?;
+            if jwk
+                .as_ref()
+                .is_some_and(|jwk| !jwk.equals_public(&resolved))
+            {
+                return Err(ParsingError::InconsistentKeyParameters);
+            }
+            (Some(vm), resolved)
+        }
+        (None, Some(jwk)) => (None, jwk.clone()),
+        (None, None) => return Err(ParsingError::MissingKeyParameters),
+    };
+
+    if let Some(x5c) = x5c {
+        let bound = [Some(&key), jwk.as_ref()]
+            .into_iter()
+            .flatten()
+            .any(|key| leaf_certificate_matches(&x5c, key));
+        if !bound {
+            return Err(ParsingError::InconsistentKeyParameters);
+        }
+    }
+
+    Ok((vm, key))
+}
+
+/// Whether the leaf certificate of `x5c` is the one the JWK is bound to, through its own `x5c` or
+/// `x5t#S256` members.
+fn leaf_certificate_matches(x5c: &[String], jwk: &JWK) -> bool {
+    let decode = |chain: &[String]| {
+        chain
+            .first()
+            .and_then(|cert| BASE64_STANDARD.decode(cert).ok())
+    };
+    let Some(leaf) = decode(x5c) else {
+        return false;
+    };
+    if let Some(chain) = &jwk.x509_certificate_chain {
+        if decode(chain).as_ref() == Some(&leaf) {
+            return true;
+        }
+    }
+    jwk.x509_thumbprint_sha256
+        .as_ref()
+        .is_some_and(|thumbprint| thumbprint.0 == Sha256::digest(&leaf).as_slice())
+}
+
 #[cfg(test)]
 mod test {
     use serde_json::json;
@@ -351,6 +997,8 @@ mod test {
             .await
             .unwrap();
 
+        assert_eq!(pop.holder_did().as_deref(), Some(did.did()));
+
         pop.verify(&ProofOfPossessionVerificationParams {
             nonce: pop.body.nonce.clone(),
             audience: pop.body.audience.clone(),
@@ -359,6 +1007,161 @@ mod test {
             controller_jwk: None,
             nbf_tolerance: None,
             exp_tolerance: None,
+            allowed_algorithms: None,
+            skip_nonce_check: false,
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn to_jwt_with_signer() {
+        struct MockSigner(JWK);
+
+        impl AsyncSigner for MockSigner {
+            async fn sign(
+                &self,
+                algorithm: Algorithm,
+                signing_input: &[u8],
+            ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+                AsyncSigner::sign(&self.0, algorithm, signing_input).await
+            }
+        }
+
+        let (mut pop, did) = generate_pop(Duration::minutes(5));
+        let signer = MockSigner(pop.controller.jwk.clone());
+        pop.controller.jwk = pop.controller.jwk.to_public();
+
+        let header = pop.to_jwt_header().unwrap();
+        let pop_jwt = pop.to_jwt_with_signer(header, &signer).await.unwrap();
+        assert!(
+            pop_jwt.starts_with(std::str::from_utf8(&pop.to_jwt_signing_input().unwrap()).unwrap())
+        );
+
+        let resolver: VerificationMethodDIDResolver<_, AnyMethod> = DIDJWK.into_vm_resolver();
+        let pop = ProofOfPossession::from_jwt(&pop_jwt, resolver)
+            .await
+            .unwrap();
+        pop.verify(&ProofOfPossessionVerificationParams {
+            nonce: pop.body.nonce.clone(),
+            audience: pop.body.audience.clone(),
+            issuer: "test".to_string(),
+            controller_did: Some(did),
+            controller_jwk: None,
+            nbf_tolerance: None,
+            exp_tolerance: None,
+            allowed_algorithms: None,
+            skip_nonce_check: false,
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn allowed_algorithms() {
+        let (pop, did) = generate_pop(Duration::minutes(5));
+        let pop_jwt = pop.to_jwt().unwrap();
+        let resolver: VerificationMethodDIDResolver<_, AnyMethod> = DIDJWK.into_vm_resolver();
+        let pop = ProofOfPossession::from_jwt(&pop_jwt, resolver)
+            .await
+            .unwrap();
+        assert_eq!(pop.algorithm, Some(Algorithm::EdDSA));
+
+        let mut params = ProofOfPossessionVerificationParams {
+            nonce: pop.body.nonce.clone(),
+            audience: pop.body.audience.clone(),
+            issuer: "test".to_string(),
+            controller_did: Some(did),
+            controller_jwk: None,
+            nbf_tolerance: None,
+            exp_tolerance: None,
+            allowed_algorithms: Some(vec![Algorithm::ES256, Algorithm::EdDSA]),
+            skip_nonce_check: false,
+        };
+        pop.verify(&params).await.unwrap();
+
+        params.allowed_algorithms = Some(vec![Algorithm::ES256]);
+        let err = pop.verify(&params).await.unwrap_err();
+        assert!(matches!(
+            err,
+            VerificationError::DisallowedAlgorithm(Some(Algorithm::EdDSA))
+        ));
+    }
+
+    #[tokio::test]
+    async fn nonce() {
+        let (pop, did) = generate_pop(Duration::minutes(5));
+        let pop_jwt = pop.to_jwt().unwrap();
+        let resolver: VerificationMethodDIDResolver<_, AnyMethod> = DIDJWK.into_vm_resolver();
+        let pop = ProofOfPossession::from_jwt(&pop_jwt, resolver)
+            .await
+            .unwrap();
+
+        let mut params = ProofOfPossessionVerificationParams {
+            nonce: pop.body.nonce.clone(),
+            audience: pop.body.audience.clone(),
+            issuer: "test".to_string(),
+            controller_did: Some(did),
+            controller_jwk: None,
+            nbf_tolerance: None,
+            exp_tolerance: None,
+            allowed_algorithms: None,
+            skip_nonce_check: false,
+        };
+        pop.verify(&params).await.unwrap();
+
+        params.nonce = Nonce::new("wlbQc6pCJp".into());
+        let err = pop.verify(&params).await.unwrap_err();
+        assert!(matches!(err, VerificationError::InvalidNonce));
+
+        params.skip_nonce_check = true;
+        pop.verify(&params).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn builder_without_nbf() {
+        let jwk: JWK = serde_json::from_value(json!({"kty":"OKP","crv":"Ed25519","x":"h3GzIK3pU8oTspVBKstiPSHR3VH_USS2FA0NrAOZ51s","d":"pfYMFvJ-LlMO4-EBBsrjpfAVz5UEYNVgbTphLPZypbE"})).unwrap();
+        let did_url = DIDJWK::generate_url(&jwk);
+        let pop = ProofOfPossession::builder(
+            &ProofOfPossessionParams {
+                issuer: "test".to_string(),
+                audience: Url::parse("http://localhost:300").unwrap(),
+                nonce: None,
+                controller: ProofOfPossessionController {
+                    jwk,
+                    vm: Some(did_url.clone()),
+                },
+            },
+            Duration::minutes(5),
+        )
+        .set_not_before(false)
+        .set_issued_at(false)
+        .set_nonce(Nonce::new("tZignsnFbp".into()))
+        .build();
+
+        let claims = serde_json::to_value(&pop.body).unwrap();
+        assert!(claims.get("nbf").is_none());
+        assert!(claims.get("iat").is_none());
+        assert_eq!(claims["jti"], "tZignsnFbp");
+
+        let pop_jwt = pop.to_jwt().unwrap();
+        let resolver: VerificationMethodDIDResolver<_, AnyMethod> = DIDJWK.into_vm_resolver();
+        let pop = ProofOfPossession::from_jwt(&pop_jwt, resolver)
+            .await
+            .unwrap();
+        assert!(pop.body.not_before.is_none());
+        assert!(pop.body.issued_at.is_none());
+
+        pop.verify(&ProofOfPossessionVerificationParams {
+            nonce: Nonce::new("tZignsnFbp".into()),
+            audience: pop.body.audience.clone(),
+            issuer: "test".to_string(),
+            controller_did: Some(did_url),
+            controller_jwk: None,
+            nbf_tolerance: None,
+            exp_tolerance: None,
+            allowed_algorithms: None,
+            skip_nonce_check: false,
         })
         .await
         .unwrap();
@@ -395,11 +1198,400 @@ mod test {
             controller_jwk: None,
             nbf_tolerance: None,
             exp_tolerance: None,
+            allowed_algorithms: None,
+            skip_nonce_check: false,
         })
         .await
         .unwrap();
     }
 
+    #[tokio::test]
+    async fn thumbprint_kid() {
+        let jwk: JWK = serde_json::from_value(json!({"kty":"OKP","crv":"Ed25519","x":"h3GzIK3pU8oTspVBKstiPSHR3VH_USS2FA0NrAOZ51s","d":"pfYMFvJ-LlMO4-EBBsrjpfAVz5UEYNVgbTphLPZypbE"})).unwrap();
+        let thumbprint = jwk.thumbprint().unwrap();
+
+        let pop = ProofOfPossession::generate(
+            &ProofOfPossessionParams {
+                issuer: "test".to_string(),
+                audience: Url::parse("http://localhost:300").unwrap(),
+                nonce: None,
+                controller: ProofOfPossessionController { jwk, vm: None }
+                    .with_thumbprint_kid()
+                    .unwrap(),
+            },
+            Duration::minutes(5),
+        );
+        let jwt = pop.to_jwt().unwrap();
+
+        let header: Header = jws::decode_unverified(&jwt).unwrap().0;
+        assert_eq!(header.key_id, None);
+        assert_eq!(header.jwk.unwrap().key_id, Some(thumbprint.clone()));
+
+        let resolver: VerificationMethodDIDResolver<_, AnyMethod> = DIDJWK.into_vm_resolver();
+        let pop = ProofOfPossession::from_jwt(&jwt, resolver).await.unwrap();
+        assert_eq!(pop.controller.jwk.key_id, Some(thumbprint));
+    }
+
+    #[test]
+    fn from_jwt_with_key() {
+        let (pop, _) = generate_pop(Duration::minutes(5));
+        let jwt = pop.to_jwt().unwrap();
+
+        let parsed =
+            ProofOfPossession::from_jwt_with_key(&jwt, pop.controller.jwk.to_public()).unwrap();
+        assert_eq!(parsed.body.audience, pop.body.audience);
+
+        let other_jwk: JWK = serde_json::from_value(
+            json!({"kty":"OKP","crv":"Ed25519","x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"}),
+        )
+        .unwrap();
+        ProofOfPossession::from_jwt_with_key(&jwt, other_jwk).unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn parse_and_verify() {
+        let (pop, did) = generate_pop(Duration::minutes(5));
+        let proof = Proof::Jwt {
+            jwt: pop.to_jwt().unwrap(),
+        };
+        let resolver: VerificationMethodDIDResolver<_, AnyMethod> = DIDJWK.into_vm_resolver();
+        let mut params = ProofOfPossessionVerificationParams {
+            nonce: pop.body.nonce.clone(),
+            audience: pop.body.audience.clone(),
+            issuer: "test".to_string(),
+            controller_did: Some(did),
+            controller_jwk: None,
+            nbf_tolerance: None,
+            exp_tolerance: None,
+            allowed_algorithms: None,
+            skip_nonce_check: false,
+        };
+
+        ProofOfPossession::parse_and_verify(&proof, &resolver, &params)
+            .await
+            .unwrap();
+
+        params.issuer = "other".to_string();
+        let err = ProofOfPossession::parse_and_verify(&proof, &resolver, &params)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ProofError::Verification(VerificationError::InvalidIssuer { .. })
+        ));
+        assert_eq!(err.error_type(), ErrorType::InvalidProof);
+
+        let err = ProofOfPossession::parse_and_verify(
+            &Proof::Attestation {
+                attestation: "eyJ0eXAiOiJrZXlhdHRlc3RhdGlvbitqd3QiLCJhbGciOiJFUzI1NiJ9".into(),
+            },
+            &resolver,
+            &params,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.error_type(), ErrorType::InvalidCredentialRequest);
+    }
+
+    #[tokio::test]
+    async fn key_attestation_x5c_unsupported() {
+        let jwk = JWK::generate_p256();
+        let header = Header {
+            algorithm: jwk.get_algorithm().unwrap(),
+            type_: Some(KEY_ATTESTATION_JWS_TYPE.to_string()),
+            x509_certificate_chain: Some(vec!["MIIBljCCATygAwIBAgIBATAKBggqhkjOPQQDAjA".into()]),
+            ..Default::default()
+        };
+        let payload = json!({
+            "iat": OffsetDateTime::now_utc().unix_timestamp(),
+            "attested_keys": [jwk.to_public()]
+        });
+        let jwt = jws::encode_sign_custom_header(&payload.to_string(), &jwk, &header).unwrap();
+
+        assert!(matches!(
+            KeyAttestation::from_jwt(&jwt, jwk.to_public()).await,
+            Err(ParsingError::UnsupportedKeyParameter(parameter)) if parameter == "x5c"
+        ));
+    }
+
+    #[test]
+    fn attestation_proof_roundtrip() {
+        let expected_json = json!({
+            "proof_type": "attestation",
+            "attestation": "eyJ0eXAiOiJrZXlhdHRlc3RhdGlvbitqd3QiLCJhbGciOiJFUzI1NiJ9...WZwmhmn9OQp6YxX0a2L"
+        });
+
+        let proof: Proof = serde_json::from_value(expected_json.clone()).unwrap();
+        assert!(matches!(proof, Proof::Attestation { .. }));
+
+        let roundtripped = serde_json::to_value(proof).unwrap();
+        assert_json_diff::assert_json_eq!(expected_json, roundtripped);
+    }
+
+    #[test]
+    fn body_validity() {
+        let at = |timestamp| OffsetDateTime::from_unix_timestamp(timestamp).unwrap();
+        let body = ProofOfPossessionBody {
+            issuer: "s6BhdRkqt3".into(),
+            audience: Url::parse("https://credential-issuer.example.com").unwrap(),
+            not_before: Some(at(1_700_000_000)),
+            issued_at: Some(at(1_700_000_000)),
+            expires_at: at(1_700_000_300),
+            nonce: Nonce::new("tZignsnFbp".into()),
+        };
+
+        assert!(!body.is_active(at(1_699_999_999)));
+        assert!(body.is_active(at(1_700_000_000)));
+        assert!(body.is_active(at(1_700_000_300)));
+        assert!(!body.is_expired(at(1_700_000_300)));
+        assert!(body.is_expired(at(1_700_000_301)));
+        assert!(!body.is_active(at(1_700_000_301)));
+
+        assert_eq!(body.time_to_live(at(1_700_000_000)), Duration::minutes(5));
+        assert_eq!(body.time_to_live(at(1_700_000_300)), Duration::ZERO);
+        assert_eq!(body.time_to_live(at(1_700_000_400)), Duration::ZERO);
+
+        let body = ProofOfPossessionBody {
+            not_before: None,
+            ..body
+        };
+        assert!(body.is_active(at(1_600_000_000)));
+    }
+
+    #[test]
+    fn unknown_proof_roundtrip() {
+        let proof: Proof = serde_json::from_value(json!({
+            "proof_type": "ldp_vp",
+            "ldp_vp": {
+                "@context": ["https://www.w3.org/ns/credentials/v2"],
+                "type": ["VerifiablePresentation"]
+            }
+        }))
+        .unwrap();
+        assert!(matches!(proof, Proof::LdpVp { .. }));
+
+        let expected_json = json!({
+            "proof_type": "di_vp",
+            "di_vp": {
+                "@context": ["https://www.w3.org/ns/credentials/v2"],
+                "type": ["VerifiablePresentation"]
+            }
+        });
+        let proof: Proof = serde_json::from_value(expected_json.clone()).unwrap();
+        let Proof::Unknown { proof_type, extra } = &proof else {
+            panic!("unexpected proof {proof:?}")
+        };
+        assert_eq!(proof_type, "di_vp");
+        assert!(extra.contains_key("di_vp"));
+        assert!(Proofs::from_proofs(vec![proof.clone()]).is_none());
+
+        let roundtripped = serde_json::to_value(proof).unwrap();
+        assert_json_diff::assert_json_eq!(expected_json, roundtripped);
+
+        // A known proof type is never parsed as an unknown proof.
+        serde_json::from_value::<Proof>(json!({"proof_type": "jwt"})).unwrap_err();
+        serde_json::from_value::<Proof>(json!({"proof_type": "jwt", "jwt": 1})).unwrap_err();
+        serde_json::from_value::<Proof>(json!({"jwt": "eyJ"})).unwrap_err();
+    }
+
+    #[test]
+    fn generate_for_token_response() {
+        let jwk = JWK::generate_p256();
+        let token_response: token::Response = serde_json::from_value(json!({
+            "access_token": "eyJhbGciOiJSUzI1NiIsInR5cCI6Ikp..sHQ",
+            "token_type": "bearer",
+            "c_nonce": "tZignsnFbp"
+        }))
+        .unwrap();
+        let credential_issuer_metadata =
+            crate::profiles::core::metadata::CredentialIssuerMetadata::new(
+                crate::types::IssuerUrl::new("https://credential-issuer.example.com".into())
+                    .unwrap(),
+                crate::types::CredentialUrl::new(
+                    "https://credential-issuer.example.com/credential".into(),
+                )
+                .unwrap(),
+            );
+        let mut params = ProofOfPossessionParams {
+            issuer: "test".to_string(),
+            audience: Url::parse("https://server.example.com/token").unwrap(),
+            nonce: Some(Nonce::new("tZignsnFbp".into())),
+            controller: ProofOfPossessionController { jwk, vm: None },
+        };
+
+        let err = ProofOfPossession::generate_for_token_response(
+            &params,
+            Duration::minutes(5),
+            &token_response,
+            &credential_issuer_metadata,
+        )
+        .unwrap_err();
+        assert!(matches!(err, BindingError::InvalidAudience { .. }));
+
+        params.audience = Url::parse("https://credential-issuer.example.com").unwrap();
+        params.nonce = Some(Nonce::new("wlbQc6pCJp".into()));
+        let err = ProofOfPossession::generate_for_token_response(
+            &params,
+            Duration::minutes(5),
+            &token_response,
+            &credential_issuer_metadata,
+        )
+        .unwrap_err();
+        assert!(matches!(err, BindingError::InvalidNonce));
+
+        params.nonce = Some(Nonce::new("tZignsnFbp".into()));
+        let pop = ProofOfPossession::generate_for_token_response(
+            &params,
+            Duration::minutes(5),
+            &token_response,
+            &credential_issuer_metadata,
+        )
+        .unwrap();
+        assert_eq!(pop.body.nonce.secret(), "tZignsnFbp");
+    }
+
+    fn sign_with_header(pop: &ProofOfPossession, header: Header) -> String {
+        let payload = serde_json::to_string(&pop.body).unwrap();
+        jws::encode_sign_custom_header(&payload, &pop.controller.jwk, &header).unwrap()
+    }
+
+    #[tokio::test]
+    async fn kid_and_jwk_key_parameters() {
+        let (pop, did) = generate_pop(Duration::minutes(5));
+        let header = Header {
+            algorithm: pop.controller.jwk.get_algorithm().unwrap(),
+            key_id: Some(did.to_string()),
+            jwk: Some(pop.controller.jwk.to_public()),
+            type_: Some(JWS_TYPE.to_string()),
+            ..Default::default()
+        };
+        let pop_jwt = sign_with_header(&pop, header.clone());
+
+        let resolver: VerificationMethodDIDResolver<_, AnyMethod> = DIDJWK.into_vm_resolver();
+        let err = ProofOfPossession::from_jwt(&pop_jwt, &resolver)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ParsingError::TooManyKeyParameters));
+
+        let parsed = ProofOfPossession::from_jwt_with_policy(
+            &pop_jwt,
+            &resolver,
+            KeyParameterPolicy::Lenient,
+        )
+        .await
+        .unwrap();
+        assert_eq!(parsed.controller.vm, Some(did));
+
+        let inconsistent_jwt = sign_with_header(
+            &pop,
+            Header {
+                jwk: Some(JWK::generate_p256().to_public()),
+                ..header
+            },
+        );
+        let err = ProofOfPossession::from_jwt_with_policy(
+            &inconsistent_jwt,
+            &resolver,
+            KeyParameterPolicy::Lenient,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, ParsingError::InconsistentKeyParameters));
+    }
+
+    #[tokio::test]
+    async fn kid_and_x5c_key_parameters() {
+        // The certificate is not parsed, arbitrary bytes are enough.
+        let certificate = b"leaf certificate".to_vec();
+        let mut jwk = JWK::generate_p256();
+        jwk.x509_thumbprint_sha256 = Some(ssi::jwk::Base64urlUInt(
+            Sha256::digest(&certificate).to_vec(),
+        ));
+        let (mut pop, _) = generate_pop(Duration::minutes(5));
+        pop.controller.jwk = jwk.clone();
+        let header = Header {
+            algorithm: jwk.get_algorithm().unwrap(),
+            key_id: Some("did:example:123#key-1".to_string()),
+            x509_certificate_chain: Some(vec![BASE64_STANDARD.encode(&certificate)]),
+            type_: Some(JWS_TYPE.to_string()),
+            ..Default::default()
+        };
+        let resolver = jwk.to_public();
+
+        let pop_jwt = sign_with_header(&pop, header.clone());
+        ProofOfPossession::from_jwt_with_policy(&pop_jwt, &resolver, KeyParameterPolicy::Lenient)
+            .await
+            .unwrap();
+
+        let other_jwt = sign_with_header(
+            &pop,
+            Header {
+                x509_certificate_chain: Some(vec![BASE64_STANDARD.encode(b"other certificate")]),
+                ..header
+            },
+        );
+        let err = ProofOfPossession::from_jwt_with_policy(
+            &other_jwt,
+            &resolver,
+            KeyParameterPolicy::Lenient,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, ParsingError::InconsistentKeyParameters));
+    }
+
+    #[tokio::test]
+    async fn x5c_key_parameter_unsupported() {
+        let (pop, _) = generate_pop(Duration::minutes(5));
+        let header = Header {
+            algorithm: pop.controller.jwk.get_algorithm().unwrap(),
+            x509_certificate_chain: Some(vec![BASE64_STANDARD.encode(b"leaf certificate")]),
+            type_: Some(JWS_TYPE.to_string()),
+            ..Default::default()
+        };
+        let pop_jwt = sign_with_header(&pop, header);
+
+        let err = ProofOfPossession::from_jwt(&pop_jwt, pop.controller.jwk.to_public())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ParsingError::UnsupportedKeyParameter(parameter) if parameter == "x5c"
+        ));
+    }
+
+    #[tokio::test]
+    async fn cwt_proof_unexpected() {
+        let err = ProofOfPossession::from_proof(
+            &Proof::Cwt {
+                cwt: "2D3ShEOhASagWQEm".into(),
+            },
+            JWK::generate_p256(),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ParsingError::UnexpectedProofType(KeyProofType::Cwt)
+        ));
+    }
+
+    #[tokio::test]
+    async fn ldp_vp_proof_unexpected() {
+        let err = ProofOfPossession::from_proof(
+            &Proof::LdpVp {
+                ldp_vp: json!({"type": ["VerifiablePresentation"]}),
+            },
+            JWK::generate_p256(),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ParsingError::UnexpectedProofType(KeyProofType::LdpVp)
+        ));
+    }
+
     #[tokio::test]
     async fn nbf_tolerance() {
         let expires_in = Duration::minutes(5);
@@ -426,6 +1618,8 @@ mod test {
             controller_jwk: None,
             nbf_tolerance: None,
             exp_tolerance: None,
+            allowed_algorithms: None,
+            skip_nonce_check: false,
         };
 
         pop.verify(&verification_params)
@@ -461,6 +1655,8 @@ mod test {
             controller_jwk: None,
             nbf_tolerance: None,
             exp_tolerance: None,
+            allowed_algorithms: None,
+            skip_nonce_check: false,
         };
 
         pop.verify(&verification_params)
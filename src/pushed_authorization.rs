@@ -1,11 +1,14 @@
 use std::{borrow::Cow, collections::HashMap, future::Future};
 
 use crate::{
-    authorization::{AuthorizationDetailsObject, AuthorizationRequest},
+    authorization::{
+        AuthorizationDetailsObject, AuthorizationRequest, UnsupportedCodeChallengeMethod,
+        UnsupportedResponseMode,
+    },
     credential::RequestError,
     http_utils::{content_type_has_essence, MIME_TYPE_FORM_URLENCODED, MIME_TYPE_JSON},
     profiles::AuthorizationDetailsObjectProfile,
-    types::{IssuerState, IssuerUrl, Nonce, ParUrl, UserHint},
+    types::{IssuerState, IssuerUrl, Nonce, ParUrl, ResponseMode, UserHint},
 };
 use oauth2::{
     http::{
@@ -69,6 +72,7 @@ pub struct PushedAuthorizationRequest<'a> {
     inner: AuthorizationRequest<'a>,
     par_auth_url: ParUrl,
     auth_url: AuthUrl,
+    allow_plain_pkce: bool,
 }
 
 impl<'a> PushedAuthorizationRequest<'a> {
@@ -81,6 +85,7 @@ impl<'a> PushedAuthorizationRequest<'a> {
             inner,
             par_auth_url,
             auth_url,
+            allow_plain_pkce: false,
         }
     }
 
@@ -209,11 +214,41 @@ impl<'a> PushedAuthorizationRequest<'a> {
         }
     }
 
-    pub fn set_pkce_challenge(mut self, pkce_code_challenge: PkceCodeChallenge) -> Self {
-        self.inner = self.inner.set_pkce_challenge(pkce_code_challenge);
+    /// Allows the `plain` code challenge method in [`PushedAuthorizationRequest::set_pkce_challenge`].
+    ///
+    /// It offers no protection if the challenge is intercepted, and should only be allowed for
+    /// authorization servers that do not support `S256`.
+    pub fn allow_plain_pkce(mut self) -> Self {
+        self.allow_plain_pkce = true;
         self
     }
 
+    /// See [`AuthorizationRequest::set_pkce_challenge`].
+    ///
+    /// Also fails if the method of the challenge is `plain`, unless explicitly allowed with
+    /// [`PushedAuthorizationRequest::allow_plain_pkce`].
+    pub fn set_pkce_challenge(
+        mut self,
+        pkce_code_challenge: PkceCodeChallenge,
+    ) -> Result<Self, UnsupportedCodeChallengeMethod> {
+        if !self.allow_plain_pkce && pkce_code_challenge.method().as_str() == "plain" {
+            return Err(UnsupportedCodeChallengeMethod(
+                pkce_code_challenge.method().clone(),
+            ));
+        }
+        self.inner = self.inner.set_pkce_challenge(pkce_code_challenge)?;
+        Ok(self)
+    }
+
+    /// See [`AuthorizationRequest::set_response_mode`].
+    pub fn set_response_mode(
+        mut self,
+        response_mode: ResponseMode,
+    ) -> Result<Self, UnsupportedResponseMode> {
+        self.inner = self.inner.set_response_mode(response_mode)?;
+        Ok(self)
+    }
+
     pub fn set_authorization_details<AD: AuthorizationDetailsObjectProfile>(
         mut self,
         authorization_details: Vec<AuthorizationDetailsObject<AD>>,
@@ -319,10 +354,63 @@ mod test {
             .pushed_authorization_request(move || state)
             .unwrap()
             .set_pkce_challenge(pkce_challenge)
+            .unwrap()
             .set_authorization_details::<CoreProfilesAuthorizationDetailsObject>(vec![])
             .unwrap()
             .prepare_request()
             .unwrap();
         assert_json_eq!(expected_body, body);
     }
+
+    #[test]
+    fn plain_pkce_challenge() {
+        let issuer = IssuerUrl::new("https://server.example.com".into()).unwrap();
+
+        let credential_issuer_metadata = CredentialIssuerMetadata::new(
+            issuer.clone(),
+            CredentialUrl::new("https://server.example.com/credential".into()).unwrap(),
+        );
+
+        let authorization_server_metadata = AuthorizationServerMetadata::new(
+            issuer,
+            TokenUrl::new("https://server.example.com/token".into()).unwrap(),
+        )
+        .set_authorization_endpoint(Some(
+            AuthUrl::new("https://server.example.com/authorize".into()).unwrap(),
+        ))
+        .set_pushed_authorization_request_endpoint(Some(
+            ParUrl::new("https://server.example.com/as/par".into()).unwrap(),
+        ));
+
+        let client = crate::profiles::core::client::Client::from_issuer_metadata(
+            ClientId::new("s6BhdRkqt3".to_string()),
+            RedirectUrl::new("https://client.example.org/cb".into()).unwrap(),
+            credential_issuer_metadata,
+            authorization_server_metadata,
+        );
+
+        let pkce_challenge: PkceCodeChallenge = serde_json::from_value(json!({
+            "code_challenge": "challengechallengechallengechallengechallenge",
+            "code_challenge_method": "plain"
+        }))
+        .unwrap();
+
+        let err = client
+            .pushed_authorization_request(|| CsrfToken::new("state".into()))
+            .unwrap()
+            .set_pkce_challenge(pkce_challenge.clone())
+            .err()
+            .unwrap();
+        assert_eq!(err.0.as_str(), "plain");
+
+        let (_, body, _) = client
+            .pushed_authorization_request(|| CsrfToken::new("state".into()))
+            .unwrap()
+            .allow_plain_pkce()
+            .set_pkce_challenge(pkce_challenge)
+            .unwrap()
+            .prepare_request()
+            .unwrap();
+        assert_eq!(body.code_challenge_method.as_str(), "plain");
+    }
 }
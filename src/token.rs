@@ -1,15 +1,18 @@
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
 use oauth2::basic::BasicTokenType;
 use oauth2::{
-    AuthorizationCode, ClientId, ExtraTokenFields, RedirectUrl, RefreshToken, StandardTokenResponse,
+    AccessToken, AuthorizationCode, ClientId, ExtraTokenFields, RedirectUrl, RefreshToken,
+    StandardTokenResponse,
 };
 use serde::{Deserialize, Serialize};
-use serde_with::{serde_as, skip_serializing_none};
+use serde_with::{serde_as, skip_serializing_none, DurationSeconds};
 
 use crate::authorization::AuthorizationDetailsObject;
-use crate::profiles::ProfilesAuthorizationDetailsObject;
-use crate::types::{Nonce, PreAuthorizedCode};
+use crate::profiles::{
+    core::profiles::CoreProfilesAuthorizationDetailsObject, ProfilesAuthorizationDetailsObject,
+};
+use crate::types::{CredentialConfigurationId, Nonce, PreAuthorizedCode};
 use crate::{profiles::AuthorizationDetailsObjectProfile, types::TxCode};
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -42,11 +45,38 @@ where
     AD: AuthorizationDetailsObjectProfile,
 {
     pub c_nonce: Option<Nonce>,
+    #[serde(default)]
+    #[serde_as(as = "Option<DurationSeconds<u64>>")]
     pub c_nonce_expires_in: Option<Duration>,
     #[serde(bound = "AD: AuthorizationDetailsObjectProfile")]
     pub authorization_details: Option<Vec<AuthorizationDetailsObject<AD>>>,
 }
 
+impl<AD> ExtraResponseTokenFields<AD>
+where
+    AD: AuthorizationDetailsObjectProfile,
+{
+    /// Returns the `c_nonce` returned in the token response, if any, to be used in the proof of
+    /// possession of the first credential request.
+    pub fn c_nonce(&self) -> Option<&Nonce> {
+        self.c_nonce.as_ref()
+    }
+
+    /// Returns the lifetime of the `c_nonce` returned in the token response, if any.
+    pub fn c_nonce_expires_in(&self) -> Option<Duration> {
+        self.c_nonce_expires_in
+    }
+
+    /// Returns the credential identifiers of all the `authorization_details` returned in the
+    /// token response.
+    pub fn credential_identifiers(&self) -> impl Iterator<Item = &CredentialConfigurationId> {
+        self.authorization_details
+            .iter()
+            .flatten()
+            .flat_map(|authorization_detail| authorization_detail.credential_identifiers())
+    }
+}
+
 pub type Response = StandardTokenResponse<
     ExtraResponseTokenFields<ProfilesAuthorizationDetailsObject>,
     BasicTokenType,
@@ -56,3 +86,206 @@ impl<AD> ExtraTokenFields for ExtraResponseTokenFields<AD> where
     AD: AuthorizationDetailsObjectProfile
 {
 }
+
+/// Parameters of a token response issued for a pre-authorized code, see
+/// [`issue_pre_authorized_response`].
+#[derive(Clone, Debug)]
+pub struct PreAuthorizedResponseParams {
+    pub access_token: AccessToken,
+    pub expires_in: Option<Duration>,
+    pub refresh_token: Option<RefreshToken>,
+    pub c_nonce: Option<Nonce>,
+    pub c_nonce_expires_in: Option<Duration>,
+    /// Credential identifiers issued for each authorized credential configuration, returned in
+    /// `authorization_details`.
+    pub credential_identifiers: Vec<(CredentialConfigurationId, Vec<CredentialConfigurationId>)>,
+}
+
+/// Builds the token response of the authorization server to a pre-authorized code token request.
+///
+/// The response is a `Bearer` token response, with one `authorization_details` entry per
+/// credential configuration of `params.credential_identifiers`, if any.
+pub fn issue_pre_authorized_response(params: PreAuthorizedResponseParams) -> Response {
+    let authorization_details: Vec<_> = params
+        .credential_identifiers
+        .into_iter()
+        .map(|(credential_configuration_id, credential_identifiers)| {
+            AuthorizationDetailsObject::new(ProfilesAuthorizationDetailsObject::Core(
+                CoreProfilesAuthorizationDetailsObject::WithIdAndUnresolvedProfile {
+                    credential_configuration_id,
+                    inner: HashMap::new(),
+                    _format: (),
+                },
+            ))
+            .set_credential_identifiers(credential_identifiers)
+        })
+        .collect();
+
+    let mut response = Response::new(
+        params.access_token,
+        BasicTokenType::Bearer,
+        ExtraResponseTokenFields {
+            c_nonce: params.c_nonce,
+            c_nonce_expires_in: params.c_nonce_expires_in,
+            authorization_details: Some(authorization_details).filter(|ad| !ad.is_empty()),
+        },
+    );
+    response.set_expires_in(params.expires_in.as_ref());
+    response.set_refresh_token(params.refresh_token);
+    response
+}
+
+#[cfg(test)]
+mod test {
+    use oauth2::TokenResponse;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn token_response_with_c_nonce() {
+        let response: Response = serde_json::from_value(json!({
+            "access_token": "eyJhbGciOiJSUzI1NiIsInR5cCI6Ikp..sHQ",
+            "token_type": "bearer",
+            "expires_in": 86400,
+            "c_nonce": "tZignsnFbp",
+            "c_nonce_expires_in": 86400
+        }))
+        .unwrap();
+
+        assert_eq!(
+            response.extra_fields().c_nonce().unwrap().secret(),
+            "tZignsnFbp"
+        );
+        assert_eq!(
+            response.extra_fields().c_nonce_expires_in(),
+            Some(Duration::from_secs(86400))
+        );
+
+        let response: Response = serde_json::from_value(json!({
+            "access_token": "eyJhbGciOiJSUzI1NiIsInR5cCI6Ikp..sHQ",
+            "token_type": "bearer"
+        }))
+        .unwrap();
+        assert!(response.extra_fields().c_nonce().is_none());
+        assert!(response.extra_fields().c_nonce_expires_in().is_none());
+    }
+
+    #[test]
+    fn example_token_response_with_authorization_details() {
+        let response: Response = serde_json::from_value(json!({
+            "access_token": "eyJhbGciOiJSUzI1NiIsInR5cCI6Ikp..sHQ",
+            "token_type": "bearer",
+            "expires_in": 86400,
+            "c_nonce": "tZignsnFbp",
+            "c_nonce_expires_in": 86400,
+            "authorization_details": [
+                {
+                    "type": "openid_credential",
+                    "credential_configuration_id": "UniversityDegreeCredential",
+                    "credential_identifiers": [
+                        "CivilEngineeringDegree-2023",
+                        "ElectricalEngineeringDegree-2023"
+                    ]
+                }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(response.expires_in(), Some(Duration::from_secs(86400)));
+        assert_eq!(
+            response.extra_fields().c_nonce_expires_in,
+            Some(Duration::from_secs(86400))
+        );
+        assert_eq!(
+            response
+                .extra_fields()
+                .credential_identifiers()
+                .map(|id| id.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "CivilEngineeringDegree-2023",
+                "ElectricalEngineeringDegree-2023"
+            ]
+        );
+    }
+
+    #[test]
+    fn issue_pre_authorized_response_roundtrip() {
+        let response = issue_pre_authorized_response(PreAuthorizedResponseParams {
+            access_token: AccessToken::new("eyJhbGciOiJSUzI1NiIsInR5cCI6Ikp..sHQ".into()),
+            expires_in: Some(Duration::from_secs(86400)),
+            refresh_token: Some(RefreshToken::new("tGzv3JOkF0XG5Qx2TlKWIA".into())),
+            c_nonce: Some(Nonce::new("tZignsnFbp".into())),
+            c_nonce_expires_in: Some(Duration::from_secs(86400)),
+            credential_identifiers: vec![(
+                CredentialConfigurationId::new("UniversityDegreeCredential".into()),
+                vec![
+                    CredentialConfigurationId::new("CivilEngineeringDegree-2023".into()),
+                    CredentialConfigurationId::new("ElectricalEngineeringDegree-2023".into()),
+                ],
+            )],
+        });
+
+        let value = serde_json::to_value(&response).unwrap();
+        assert_json_diff::assert_json_eq!(
+            value,
+            json!({
+                "access_token": "eyJhbGciOiJSUzI1NiIsInR5cCI6Ikp..sHQ",
+                "token_type": "bearer",
+                "expires_in": 86400,
+                "refresh_token": "tGzv3JOkF0XG5Qx2TlKWIA",
+                "c_nonce": "tZignsnFbp",
+                "c_nonce_expires_in": 86400,
+                "authorization_details": [
+                    {
+                        "type": "openid_credential",
+                        "credential_configuration_id": "UniversityDegreeCredential",
+                        "credential_identifiers": [
+                            "CivilEngineeringDegree-2023",
+                            "ElectricalEngineeringDegree-2023"
+                        ]
+                    }
+                ]
+            })
+        );
+
+        let parsed: Response = serde_json::from_value(value).unwrap();
+        assert_eq!(
+            parsed.access_token().secret(),
+            response.access_token().secret()
+        );
+        assert_eq!(parsed.expires_in(), Some(Duration::from_secs(86400)));
+        assert_eq!(
+            parsed.refresh_token().unwrap().secret(),
+            "tGzv3JOkF0XG5Qx2TlKWIA"
+        );
+        assert_eq!(
+            parsed
+                .extra_fields()
+                .credential_identifiers()
+                .map(|id| id.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "CivilEngineeringDegree-2023",
+                "ElectricalEngineeringDegree-2023"
+            ]
+        );
+
+        let response = issue_pre_authorized_response(PreAuthorizedResponseParams {
+            access_token: AccessToken::new("eyJhbGciOiJSUzI1NiIsInR5cCI6Ikp..sHQ".into()),
+            expires_in: None,
+            refresh_token: None,
+            c_nonce: None,
+            c_nonce_expires_in: None,
+            credential_identifiers: vec![],
+        });
+        assert_json_diff::assert_json_eq!(
+            serde_json::to_value(&response).unwrap(),
+            json!({
+                "access_token": "eyJhbGciOiJSUzI1NiIsInR5cCI6Ikp..sHQ",
+                "token_type": "bearer"
+            })
+        );
+    }
+}
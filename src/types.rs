@@ -1,10 +1,12 @@
 use std::fmt::{Debug, Error as FormatterError, Formatter};
 use std::hash::{Hash, Hasher};
 use std::ops::Deref;
+use std::str::FromStr;
 
 use anyhow::bail;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use ssi::jwk::JWK;
 use url::Url;
 
 macro_rules! new_type {
@@ -173,6 +175,24 @@ macro_rules! new_secret_type {
             }
         }
 
+        #[cfg(feature = "zeroize")]
+        impl zeroize::Zeroize for $name {
+            fn zeroize(&mut self) {
+                self.0.zeroize()
+            }
+        }
+
+        /// The secret is wiped from memory when dropped.
+        #[cfg(feature = "zeroize")]
+        impl Drop for $name {
+            fn drop(&mut self) {
+                zeroize::Zeroize::zeroize(self)
+            }
+        }
+
+        #[cfg(feature = "zeroize")]
+        impl zeroize::ZeroizeOnDrop for $name {}
+
     };
 }
 
@@ -324,15 +344,34 @@ new_url_type![
     /// Base URL of the [Credential] Issuer.
     IssuerUrl
     impl {
-        /// Parse a string as a URL, with this URL as the base URL.
+        /// Appends `suffix` to the path of this URL, as done for credential issuer metadata by
+        /// OID4VCI, e.g. `https://example.com/issuer1/.well-known/openid-credential-issuer`.
         ///
-        /// See [`Url::parse`].
+        /// The query and fragment of the URL, if any, are dropped.
         pub fn join(&self, suffix: &str) -> Result<Url, url::ParseError> {
-            if let Some('/') = self.1.chars().next_back() {
-                Url::parse(&(self.1.clone() + suffix))
-            } else {
-                Url::parse(&(self.1.clone() + "/" + suffix))
-            }
+            let mut url = self.base_url();
+            let path = format!("{}/{}", url.path().trim_end_matches('/'), suffix);
+            url.set_path(&path);
+            Ok(url)
+        }
+
+        /// Inserts `suffix` between the host and the path of this URL, as done for `.well-known`
+        /// URIs by [RFC 8414](https://www.rfc-editor.org/rfc/rfc8414#section-3.1), e.g.
+        /// `https://example.com/.well-known/oauth-authorization-server/issuer1`.
+        ///
+        /// The query and fragment of the URL, if any, are dropped.
+        pub fn join_well_known(&self, suffix: &str) -> Result<Url, url::ParseError> {
+            let mut url = self.base_url();
+            let path = format!("/{}{}", suffix, url.path().trim_end_matches('/'));
+            url.set_path(&path);
+            Ok(url)
+        }
+
+        fn base_url(&self) -> Url {
+            let mut url = self.0.clone();
+            url.set_query(None);
+            url.set_fragment(None);
+            url
         }
     }
 ];
@@ -341,7 +380,7 @@ new_url_type![
     /// The credential offer request as a URL, as represented in a QR code or deep link.
     CredentialOfferRequest
     impl {
-        const DEFAULT_URL_SCHEME: &'static str = "openid-credential-offer";
+        pub const DEFAULT_URL_SCHEME: &'static str = "openid-credential-offer";
 
         /// Parse the credential offer request from a URL, and validate that the URL scheme is
         /// `scheme`.
@@ -359,9 +398,33 @@ new_url_type![
         pub fn from_url_checked(url: Url) -> Result<Self, anyhow::Error> {
             Self::from_url_checked_with_scheme(url, Self::DEFAULT_URL_SCHEME)
         }
+
+        /// Parse the credential offer request from a URL with any scheme, e.g. a custom scheme
+        /// deep link or an `https` universal link, and validate that the URL carries a
+        /// `credential_offer` or `credential_offer_uri` parameter.
+        pub fn from_url_any_scheme(url: Url) -> Result<Self, anyhow::Error> {
+            if !url
+                .query_pairs()
+                .any(|(name, _)| name == "credential_offer" || name == "credential_offer_uri")
+            {
+                bail!("URL has no 'credential_offer' or 'credential_offer_uri' parameter")
+            }
+            Ok(Self::from_url(url))
+        }
     }
 ];
 
+impl FromStr for CredentialOfferRequest {
+    type Err = anyhow::Error;
+
+    /// Parse the credential offer request from a string, whatever its URL scheme.
+    ///
+    /// See [`CredentialOfferRequest::from_url_any_scheme`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_url_any_scheme(Url::parse(s)?)
+    }
+}
+
 new_url_type![
     /// URL of the Credential Issuer's Credential Endpoint.
     CredentialUrl
@@ -393,6 +456,31 @@ new_url_type![
     JsonWebKeySetUrl
 ];
 
+/// JWK Set document (see [RFC7517](https://datatracker.ietf.org/doc/html/rfc7517#section-5)).
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct JsonWebKeySet {
+    keys: Vec<JWK>,
+}
+
+impl JsonWebKeySet {
+    pub fn new(keys: Vec<JWK>) -> Self {
+        Self { keys }
+    }
+
+    field_getters_setters![
+        pub self [self] ["JWK set value"] {
+            set_keys -> keys[Vec<JWK>],
+        }
+    ];
+
+    /// Returns the key with the given `kid`, if any.
+    pub fn find(&self, key_id: &str) -> Option<&JWK> {
+        self.keys
+            .iter()
+            .find(|key| key.key_id.as_deref() == Some(key_id))
+    }
+}
+
 new_url_type![
     /// URL of the authorization server's OAuth 2.0 Dynamic Client Registration endpoint
     /// (see [RFC7591](https://datatracker.ietf.org/doc/html/rfc7591)).
@@ -404,13 +492,44 @@ new_url_type![
     /// The Wallet needs to determine the scheme, since the URI value could use the `https:` scheme,
     /// the `data:` scheme, etc.
     LogoUri
+    impl {
+        /// Returns the media type and the decoded content of a `data:` URL
+        /// ([RFC2397](https://datatracker.ietf.org/doc/html/rfc2397)), e.g.
+        /// `data:image/png;base64,iVBORw0KGgo=`, or `None` for other schemes or an invalid payload.
+        pub fn as_data_url(&self) -> Option<(String, Vec<u8>)> {
+            use base64::prelude::*;
+
+            if self.url().scheme() != "data" {
+                return None;
+            }
+            let (_, data) = self.1.split_once(':')?;
+            let (media_type, payload) = data.split_once(',')?;
+            let payload = percent_encoding::percent_decode_str(payload).collect::<Vec<u8>>();
+            let (media_type, content) = match media_type.strip_suffix(";base64") {
+                Some(media_type) => {
+                    let payload: Vec<u8> = payload
+                        .into_iter()
+                        .filter(|b| !b.is_ascii_whitespace())
+                        .collect();
+                    (media_type, BASE64_STANDARD.decode(payload).ok()?)
+                }
+                None => (media_type, payload),
+            };
+            let media_type = if media_type.is_empty() {
+                "text/plain;charset=US-ASCII"
+            } else {
+                media_type
+            };
+            Some((media_type.to_owned(), content))
+        }
+    }
 ];
 
 new_type![
     /// A unique identifier of the supported Credential being described.
     /// This identifier is used in the Credential Offer to communicate to the Wallet which
     /// Credential is being offered.
-    #[derive(Deserialize, Serialize, Eq, Hash)]
+    #[derive(Deserialize, Eq, Hash, Ord, PartialOrd, Serialize)]
     CredentialConfigurationId(String)
 ];
 
@@ -490,3 +609,144 @@ new_secret_type![
     #[derive(Deserialize, Serialize)]
     TxCode(String)
 ];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn credential_offer_request_any_scheme() {
+        let request: CredentialOfferRequest =
+            "haip://?credential_offer_uri=https%3A%2F%2Fserver.example.com%2Fcredential-offer"
+                .parse()
+                .unwrap();
+        assert_eq!(request.url().scheme(), "haip");
+        assert!(CredentialOfferRequest::from_url_checked(request.url().clone()).is_err());
+
+        let request: CredentialOfferRequest = "https://wallet.example.com/offer?credential_offer=%7B%22credential_issuer%22%3A%22https%3A%2F%2Fcredential-issuer.example.com%22%7D"
+            .parse()
+            .unwrap();
+        assert_eq!(request.url().scheme(), "https");
+
+        assert!("https://wallet.example.com/offer?foo=bar"
+            .parse::<CredentialOfferRequest>()
+            .is_err());
+        assert!("not a url".parse::<CredentialOfferRequest>().is_err());
+    }
+
+    const SUFFIX: &str = ".well-known/openid-credential-issuer";
+
+    fn join(issuer: &str) -> (String, String) {
+        let issuer = IssuerUrl::new(issuer.into()).unwrap();
+        (
+            issuer.join(SUFFIX).unwrap().to_string(),
+            issuer.join_well_known(SUFFIX).unwrap().to_string(),
+        )
+    }
+
+    #[test]
+    fn join_root() {
+        for issuer in ["https://example.com", "https://example.com/"] {
+            assert_eq!(
+                join(issuer),
+                (
+                    "https://example.com/.well-known/openid-credential-issuer".into(),
+                    "https://example.com/.well-known/openid-credential-issuer".into()
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn join_subpath() {
+        assert_eq!(
+            join("https://example.com/tenant/issuer1"),
+            (
+                "https://example.com/tenant/issuer1/.well-known/openid-credential-issuer".into(),
+                "https://example.com/.well-known/openid-credential-issuer/tenant/issuer1".into()
+            )
+        );
+    }
+
+    #[test]
+    fn join_trailing_slash() {
+        assert_eq!(
+            join("https://example.com/issuer1/"),
+            (
+                "https://example.com/issuer1/.well-known/openid-credential-issuer".into(),
+                "https://example.com/.well-known/openid-credential-issuer/issuer1".into()
+            )
+        );
+    }
+
+    #[test]
+    fn join_query_and_fragment() {
+        assert_eq!(
+            join("https://example.com/issuer1?tenant=1#fragment"),
+            (
+                "https://example.com/issuer1/.well-known/openid-credential-issuer".into(),
+                "https://example.com/.well-known/openid-credential-issuer/issuer1".into()
+            )
+        );
+    }
+
+    #[test]
+    fn logo_data_url() {
+        let logo = LogoUri::new("data:image/png;base64,iVBORw0KGgo=".into()).unwrap();
+        let (media_type, content) = logo.as_data_url().unwrap();
+        assert_eq!(media_type, "image/png");
+        assert_eq!(content, b"\x89PNG\r\n\x1a\n");
+
+        let logo = LogoUri::new("data:,Hello%2C%20World".into()).unwrap();
+        assert_eq!(
+            logo.as_data_url(),
+            Some((
+                "text/plain;charset=US-ASCII".into(),
+                b"Hello, World".to_vec()
+            ))
+        );
+
+        let logo = LogoUri::new("https://example.com/logo.png".into()).unwrap();
+        assert_eq!(logo.as_data_url(), None);
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn zeroize_secret() {
+        use zeroize::Zeroize;
+
+        let mut tx_code = TxCode::new("493536".into());
+        assert_eq!(format!("{tx_code:?}"), "TxCode([redacted])");
+        tx_code.zeroize();
+        assert_eq!(tx_code.secret(), "");
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn zeroize_on_drop_secrets() {
+        use zeroize::{Zeroize, ZeroizeOnDrop};
+
+        // `Drop` clears the secrets through `Zeroize`, which is checked on a live value rather
+        // than by reading one that was dropped.
+        fn assert_zeroized_on_drop<T: Zeroize + ZeroizeOnDrop>(
+            mut secret: T,
+            secret_of: fn(&T) -> &String,
+        ) {
+            assert!(!secret_of(&secret).is_empty());
+            secret.zeroize();
+            assert!(secret_of(&secret).is_empty());
+        }
+
+        assert_zeroized_on_drop(Nonce::new("wlbQc6pCJp".into()), Nonce::secret);
+        assert_zeroized_on_drop(
+            PreAuthorizedCode::new("adhjhdjajkdkhjhdj".into()),
+            PreAuthorizedCode::secret,
+        );
+        assert_zeroized_on_drop(
+            IssuerState::new("eyJhbGciOiJSU0Et...FYUaBy".into()),
+            IssuerState::secret,
+        );
+        assert_zeroized_on_drop(UserHint::new("user@example.com".into()), UserHint::secret);
+        assert_zeroized_on_drop(TxCode::new("493536".into()), TxCode::secret);
+    }
+}
@@ -0,0 +1,532 @@
+use ssi::{
+    dids::DIDURLBuf,
+    jwk::{Algorithm, JWKResolver, JWK},
+};
+use time::Duration;
+use url::Url;
+
+use crate::{
+    authorization::AuthorizationDetailsObject,
+    credential::{ErrorType, Request},
+    metadata::{credential_issuer::CredentialConfiguration, CredentialIssuerMetadata},
+    profiles::core::profiles::{
+        CoreProfilesAuthorizationDetailsObject, CoreProfilesCredentialConfiguration,
+        CoreProfilesCredentialRequest, CredentialRequestWithFormat,
+    },
+    proof_of_possession::{
+        ParsingError, Proof, ProofError, ProofOfPossession, ProofOfPossessionVerificationParams,
+    },
+    types::{CredentialConfigurationId, Nonce},
+};
+
+/// Parameters to verify the proofs of possession of a credential request, see
+/// [`ProofOfPossessionVerificationParams`]. There is no audience, the proofs are always verified
+/// against the credential issuer identifier.
+#[derive(Clone, Debug)]
+pub struct CredentialRequestVerificationParams {
+    pub issuer: String,
+    pub nonce: Nonce,
+    pub controller_did: Option<DIDURLBuf>,
+    pub controller_jwk: Option<JWK>,
+    /// Slack in nbf validation to deal with clock synchronisation issues.
+    pub nbf_tolerance: Option<Duration>,
+    /// Slack in exp validation to deal with clock synchronisation issues.
+    pub exp_tolerance: Option<Duration>,
+    /// Algorithms the proofs may be signed with, any algorithm being accepted if `None`.
+    pub allowed_algorithms: Option<Vec<Algorithm>>,
+    /// Do not compare the nonce of the proofs to `nonce`, for issuers validating it separately.
+    pub skip_nonce_check: bool,
+}
+
+impl CredentialRequestVerificationParams {
+    fn with_audience(&self, audience: Url) -> ProofOfPossessionVerificationParams {
+        ProofOfPossessionVerificationParams {
+            audience,
+            issuer: self.issuer.clone(),
+            nonce: self.nonce.clone(),
+            controller_did: self.controller_did.clone(),
+            controller_jwk: self.controller_jwk.clone(),
+            nbf_tolerance: self.nbf_tolerance,
+            exp_tolerance: self.exp_tolerance,
+            allowed_algorithms: self.allowed_algorithms.clone(),
+            skip_nonce_check: self.skip_nonce_check,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CredentialRequestError {
+    #[error("no credential configuration matches the requested format")]
+    UnsupportedCredentialFormat,
+    #[error("unknown credential identifier `{}`", .0.as_str())]
+    UnknownCredentialIdentifier(CredentialConfigurationId),
+    #[error("unknown credential configuration `{}`", .0.as_str())]
+    UnknownCredentialConfiguration(CredentialConfigurationId),
+    #[error("the credential request has no proof")]
+    MissingProof,
+    #[error("the credential request has both `proof` and `proofs`")]
+    AmbiguousProofs,
+    #[error(transparent)]
+    Proof(#[from] ProofError),
+}
+
+impl CredentialRequestError {
+    /// The error code of the credential error response to return to the wallet.
+    pub fn error_type(&self) -> ErrorType {
+        match self {
+            Self::UnsupportedCredentialFormat => ErrorType::UnsupportedCredentialFormat,
+            Self::UnknownCredentialIdentifier(_) => ErrorType::InvalidCredentialRequest,
+            Self::UnknownCredentialConfiguration(_) => ErrorType::UnsupportedCredentialType,
+            Self::MissingProof => ErrorType::InvalidProof,
+            Self::AmbiguousProofs => ErrorType::InvalidCredentialRequest,
+            Self::Proof(e) => e.error_type(),
+        }
+    }
+}
+
+/// Validates a credential request received by the credential issuer.
+///
+/// The request must reference one of the credential configurations of `issuer_metadata`:
+/// - by format, with the same credential types, or doctype, as the configuration;
+/// - by credential configuration identifier;
+/// - by credential identifier, as issued in the `authorization_details` of the token response.
+///
+/// Its proofs of possession, in either `proof` or `proofs`, must be JWT proofs valid for
+/// `params`, with the credential issuer identifier of `issuer_metadata` as audience.
+///
+/// Returns the requested configuration, and the keys the credentials are to be bound to, one per
+/// proof.
+pub async fn verify_credential_request<'m>(
+    request: &Request<CoreProfilesCredentialRequest>,
+    issuer_metadata: &'m CredentialIssuerMetadata<CoreProfilesCredentialConfiguration>,
+    authorization_details: &[AuthorizationDetailsObject<CoreProfilesAuthorizationDetailsObject>],
+    params: &CredentialRequestVerificationParams,
+    resolver: impl JWKResolver,
+) -> Result<
+    (
+        &'m CredentialConfiguration<CoreProfilesCredentialConfiguration>,
+        Vec<JWK>,
+    ),
+    CredentialRequestError,
+> {
+    let configuration_by_id = |credential_configuration_id: &CredentialConfigurationId| {
+        issuer_metadata
+            .credential_configurations_supported()
+            .iter()
+            .find(|configuration| configuration.id() == credential_configuration_id)
+    };
+    let configuration = match request.additional_profile_fields() {
+        CoreProfilesCredentialRequest::WithFormat { inner, .. } => issuer_metadata
+            .credential_configurations_supported()
+            .iter()
+            .find(|configuration| format_matches(configuration.profile_specific_fields(), inner))
+            .ok_or(CredentialRequestError::UnsupportedCredentialFormat)?,
+        CoreProfilesCredentialRequest::WithIdAndUnresolvedProfile {
+            credential_identifier,
+            ..
+        }
+        | CoreProfilesCredentialRequest::WithId {
+            credential_identifier,
+            ..
+        } => {
+            let unknown = || {
+                CredentialRequestError::UnknownCredentialIdentifier(credential_identifier.clone())
+            };
+            let credential_configuration_id = authorization_details
+                .iter()
+                .find(|detail| {
+                    detail
+                        .credential_identifiers()
+                        .contains(credential_identifier)
+                })
+                .and_then(|detail| authorized_configuration_id(detail.additional_profile_fields()))
+                .ok_or_else(unknown)?;
+            configuration_by_id(credential_configuration_id).ok_or_else(unknown)?
+        }
+        CoreProfilesCredentialRequest::WithConfigurationId {
+            credential_configuration_id,
+            ..
+        } => configuration_by_id(credential_configuration_id).ok_or_else(|| {
+            CredentialRequestError::UnknownCredentialConfiguration(
+                credential_configuration_id.clone(),
+            )
+        })?,
+    };
+
+    let proofs = match (request.proof(), request.proofs()) {
+        (Some(proof), None) => vec![proof.clone()],
+        (None, Some(proofs)) => proofs.to_proofs(),
+        (Some(_), Some(_)) => return Err(CredentialRequestError::AmbiguousProofs),
+        (None, None) => Vec::new(),
+    };
+    if proofs.is_empty() {
+        return Err(CredentialRequestError::MissingProof);
+    }
+    if let Some(proof_type) = proofs.iter().find_map(unsupported_proof_type) {
+        return Err(ProofError::from(ParsingError::UnsupportedProofType(proof_type)).into());
+    }
+
+    let params = params.with_audience(issuer_metadata.credential_issuer().url().clone());
+    let mut keys = Vec::with_capacity(proofs.len());
+    for proof in &proofs {
+        let proof_of_possession =
+            ProofOfPossession::parse_and_verify(proof, &resolver, &params).await?;
+        keys.push(proof_of_possession.controller.jwk);
+    }
+
+    Ok((configuration, keys))
+}
+
+/// The type of `proof`, if it is not a JWT proof, the only type supported for credential requests.
+fn unsupported_proof_type(proof: &Proof) -> Option<String> {
+    match proof {
+        Proof::Jwt { .. } => None,
+        Proof::Cwt { .. } => Some("cwt".to_string()),
+        Proof::LdpVp { .. } => Some("ldp_vp".to_string()),
+        Proof::Attestation { .. } => Some("attestation".to_string()),
+        Proof::Unknown { proof_type, .. } => Some(proof_type.clone()),
+    }
+}
+
+/// Whether a credential request by format is for the credential `configuration`, i.e. has the
+/// same format and credential types, or doctype. Other parameters, such as requested claims,
+/// are not compared.
+fn format_matches(
+    configuration: &CoreProfilesCredentialConfiguration,
+    request: &CredentialRequestWithFormat,
+) -> bool {
+    fn same_types(configuration: &[String], request: &[String]) -> bool {
+        configuration.len() == request.len() && request.iter().all(|t| configuration.contains(t))
+    }
+
+    match (configuration, request) {
+        (
+            CoreProfilesCredentialConfiguration::JwtVcJson(configuration),
+            CredentialRequestWithFormat::JwtVcJson(request),
+        ) => same_types(
+            configuration.credential_definition().r#type(),
+            request.credential_definition().r#type(),
+        ),
+        (
+            CoreProfilesCredentialConfiguration::JwtVcJsonLd(configuration),
+            CredentialRequestWithFormat::JwtVcJsonLd(request),
+        ) => same_types(
+            configuration.credential_definition().r#type(),
+            request.credential_definition().r#type(),
+        ),
+        (
+            CoreProfilesCredentialConfiguration::LdpVc(configuration),
+            CredentialRequestWithFormat::LdpVc(request),
+        ) => same_types(
+            configuration.credential_definition().r#type(),
+            request.credential_definition().r#type(),
+        ),
+        (
+            CoreProfilesCredentialConfiguration::MsoMdoc(configuration),
+            CredentialRequestWithFormat::MsoMdoc(request),
+        ) => configuration.doctype() == request.doctype(),
+        _ => false,
+    }
+}
+
+/// Credential configuration of an authorization detail granted by identifier.
+fn authorized_configuration_id(
+    authorization_detail: &CoreProfilesAuthorizationDetailsObject,
+) -> Option<&CredentialConfigurationId> {
+    match authorization_detail {
+        CoreProfilesAuthorizationDetailsObject::WithIdAndUnresolvedProfile {
+            credential_configuration_id,
+            ..
+        }
+        | CoreProfilesAuthorizationDetailsObject::WithId {
+            credential_configuration_id,
+            ..
+        } => Some(credential_configuration_id),
+        CoreProfilesAuthorizationDetailsObject::WithFormat { .. } => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use ssi::{
+        dids::{jwk::DIDJWK, DIDResolver, VerificationMethodDIDResolver},
+        prelude::AnyMethod,
+    };
+    use time::Duration;
+    use url::Url;
+
+    use crate::{
+        profiles::CredentialRequestProfile,
+        proof_of_possession::{
+            Proof, ProofOfPossessionController, ProofOfPossessionParams, Proofs, VerificationError,
+        },
+        types::{CredentialUrl, IssuerUrl, Nonce},
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn verify_credential_request() {
+        let issuer = IssuerUrl::new("https://credential-issuer.example.com".into()).unwrap();
+        let metadata: CredentialIssuerMetadata<CoreProfilesCredentialConfiguration> =
+            CredentialIssuerMetadata::new(
+                issuer.clone(),
+                CredentialUrl::new("https://credential-issuer.example.com/credential".into())
+                    .unwrap(),
+            )
+            .set_credential_configurations_supported(vec![serde_json::from_value(
+                json!({
+                    "$key$": "UniversityDegreeCredential",
+                    "format": "jwt_vc_json",
+                    "credential_definition": {
+                        "type": ["VerifiableCredential", "UniversityDegreeCredential"]
+                    }
+                }),
+            )
+            .unwrap()]);
+
+        let jwk: JWK = serde_json::from_value(json!({"kty":"OKP","crv":"Ed25519","x":"h3GzIK3pU8oTspVBKstiPSHR3VH_USS2FA0NrAOZ51s","d":"pfYMFvJ-LlMO4-EBBsrjpfAVz5UEYNVgbTphLPZypbE"})).unwrap();
+        let did_url = DIDJWK::generate_url(&jwk);
+        let proof = |audience: &str| {
+            let pop = ProofOfPossession::generate(
+                &ProofOfPossessionParams {
+                    issuer: "s6BhdRkqt3".to_string(),
+                    audience: Url::parse(audience).unwrap(),
+                    nonce: None,
+                    controller: ProofOfPossessionController {
+                        jwk: jwk.clone(),
+                        vm: Some(did_url.clone()),
+                    },
+                },
+                Duration::minutes(5),
+            );
+            (
+                Proof::Jwt {
+                    jwt: pop.to_jwt().unwrap(),
+                },
+                pop.body.nonce,
+            )
+        };
+        let resolver: VerificationMethodDIDResolver<_, AnyMethod> = DIDJWK.into_vm_resolver();
+        let params = |nonce| CredentialRequestVerificationParams {
+            issuer: "s6BhdRkqt3".to_string(),
+            nonce,
+            controller_did: Some(did_url.clone()),
+            controller_jwk: None,
+            nbf_tolerance: None,
+            exp_tolerance: None,
+            allowed_algorithms: None,
+            skip_nonce_check: false,
+        };
+
+        let configuration = &metadata.credential_configurations_supported()[0];
+        let (valid_proof, nonce) = proof("https://credential-issuer.example.com");
+        let request = Request::new(configuration.profile_specific_fields().build_request())
+            .set_proof(Some(valid_proof.clone()));
+        let (requested, keys) = super::verify_credential_request(
+            &request,
+            &metadata,
+            &[],
+            &params(nonce.clone()),
+            &resolver,
+        )
+        .await
+        .unwrap();
+        assert_eq!(requested.id(), configuration.id());
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].thumbprint().unwrap(), jwk.thumbprint().unwrap());
+
+        let (invalid_proof, nonce) = proof("https://other-issuer.example.com");
+        let request = request.set_proof(Some(invalid_proof));
+        let err =
+            super::verify_credential_request(&request, &metadata, &[], &params(nonce), &resolver)
+                .await
+                .unwrap_err();
+        assert!(matches!(
+            err,
+            CredentialRequestError::Proof(ProofError::Verification(
+                VerificationError::InvalidAudience { .. }
+            ))
+        ));
+        assert_eq!(err.error_type(), ErrorType::InvalidProof);
+
+        // Requested claims do not prevent the request from matching its configuration.
+        let (valid_proof, nonce) = proof("https://credential-issuer.example.com");
+        let request: Request<CoreProfilesCredentialRequest> = serde_json::from_value(json!({
+            "format": "jwt_vc_json",
+            "credential_definition": {
+                "type": ["UniversityDegreeCredential", "VerifiableCredential"],
+                "credentialSubject": {
+                    "given_name": {}
+                }
+            }
+        }))
+        .unwrap();
+        let request = request.set_proof(Some(valid_proof));
+        let (requested, _) =
+            super::verify_credential_request(&request, &metadata, &[], &params(nonce), &resolver)
+                .await
+                .unwrap();
+        assert_eq!(requested.id(), configuration.id());
+
+        let (valid_proof, nonce) = proof("https://credential-issuer.example.com");
+        let request: Request<CoreProfilesCredentialRequest> = serde_json::from_value(json!({
+            "format": "jwt_vc_json",
+            "credential_definition": {
+                "type": ["VerifiableCredential", "DriverLicenseCredential"]
+            }
+        }))
+        .unwrap();
+        let request = request.set_proof(Some(valid_proof));
+        let err =
+            super::verify_credential_request(&request, &metadata, &[], &params(nonce), &resolver)
+                .await
+                .unwrap_err();
+        assert!(matches!(
+            err,
+            CredentialRequestError::UnsupportedCredentialFormat
+        ));
+    }
+
+    #[tokio::test]
+    async fn verify_credential_request_batch_and_identifier() {
+        let issuer = IssuerUrl::new("https://credential-issuer.example.com".into()).unwrap();
+        let metadata: CredentialIssuerMetadata<CoreProfilesCredentialConfiguration> =
+            CredentialIssuerMetadata::new(
+                issuer.clone(),
+                CredentialUrl::new("https://credential-issuer.example.com/credential".into())
+                    .unwrap(),
+            )
+            .set_credential_configurations_supported(vec![serde_json::from_value(
+                json!({
+                    "$key$": "UniversityDegreeCredential",
+                    "format": "jwt_vc_json",
+                    "credential_definition": {
+                        "type": ["VerifiableCredential", "UniversityDegreeCredential"]
+                    }
+                }),
+            )
+            .unwrap()]);
+        let authorization_details: Vec<
+            AuthorizationDetailsObject<CoreProfilesAuthorizationDetailsObject>,
+        > = serde_json::from_value(json!([{
+            "type": "openid_credential",
+            "credential_configuration_id": "UniversityDegreeCredential",
+            "credential_identifiers": ["CivilEngineeringDegree-2023"]
+        }]))
+        .unwrap();
+
+        let proof = |jwk: &JWK| Proof::Jwt {
+            jwt: ProofOfPossession::generate(
+                &ProofOfPossessionParams {
+                    issuer: "s6BhdRkqt3".to_string(),
+                    audience: issuer.url().clone(),
+                    nonce: None,
+                    controller: ProofOfPossessionController {
+                        jwk: jwk.clone(),
+                        vm: Some(DIDJWK::generate_url(jwk)),
+                    },
+                },
+                Duration::minutes(5),
+            )
+            .to_jwt()
+            .unwrap(),
+        };
+        let jwks = [
+            JWK::generate_ed25519().unwrap(),
+            JWK::generate_ed25519().unwrap(),
+        ];
+        let resolver: VerificationMethodDIDResolver<_, AnyMethod> = DIDJWK.into_vm_resolver();
+        let params = CredentialRequestVerificationParams {
+            issuer: "s6BhdRkqt3".to_string(),
+            nonce: Nonce::new_random(),
+            controller_did: None,
+            controller_jwk: None,
+            nbf_tolerance: None,
+            exp_tolerance: None,
+            allowed_algorithms: None,
+            skip_nonce_check: true,
+        };
+
+        let request = Request::new(
+            CoreProfilesCredentialRequest::from_credential_identifier(
+                CredentialConfigurationId::new("CivilEngineeringDegree-2023".into()),
+            )
+            .unwrap(),
+        )
+        .set_proofs(Proofs::from_proofs(jwks.iter().map(proof).collect()));
+        let (requested, keys) = super::verify_credential_request(
+            &request,
+            &metadata,
+            &authorization_details,
+            &params,
+            &resolver,
+        )
+        .await
+        .unwrap();
+        assert_eq!(requested.id().as_str(), "UniversityDegreeCredential");
+        let thumbprints: Vec<_> = keys.iter().map(|key| key.thumbprint().unwrap()).collect();
+        let expected: Vec<_> = jwks.iter().map(|key| key.thumbprint().unwrap()).collect();
+        assert_eq!(thumbprints, expected);
+
+        let err = super::verify_credential_request(
+            &request.clone().set_proof(Some(proof(&jwks[0]))),
+            &metadata,
+            &authorization_details,
+            &params,
+            &resolver,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, CredentialRequestError::AmbiguousProofs));
+
+        // A credential configuration identifier is not a credential identifier.
+        let request = Request::new(
+            CoreProfilesCredentialRequest::from_credential_identifier(
+                CredentialConfigurationId::new("UniversityDegreeCredential".into()),
+            )
+            .unwrap(),
+        )
+        .set_proof(Some(proof(&jwks[0])));
+        let err = super::verify_credential_request(
+            &request,
+            &metadata,
+            &authorization_details,
+            &params,
+            &resolver,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            CredentialRequestError::UnknownCredentialIdentifier(_)
+        ));
+        assert_eq!(err.error_type(), ErrorType::InvalidCredentialRequest);
+
+        // Only JWT proofs are supported.
+        let request: Request<CoreProfilesCredentialRequest> = serde_json::from_value(json!({
+            "credential_identifier": "CivilEngineeringDegree-2023",
+            "proofs": {
+                "cwt": ["2D3ShEOhASagWQEm"]
+            }
+        }))
+        .unwrap();
+        let err = super::verify_credential_request(
+            &request,
+            &metadata,
+            &authorization_details,
+            &params,
+            &resolver,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            CredentialRequestError::Proof(ProofError::Parsing(
+                ParsingError::UnsupportedProofType(ref proof_type)
+            )) if proof_type == "cwt"
+        ));
+        assert_eq!(err.error_type(), ErrorType::InvalidCredentialRequest);
+    }
+}